@@ -0,0 +1,401 @@
+//! Chess Engine
+//!
+//! Iterative deepening alpha-beta search with a quiescence search of the
+//! captures, evaluating the material and the piece-square tables.
+
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::game::prelude::*;
+use crate::error::Error;
+
+/// Score of a checkmate, minus the plies to reach it
+const MATE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+
+// piece-square tables from White's point of view, the eighth rank first
+
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0
+];
+
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50
+];
+
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20
+];
+
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0
+];
+
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20
+];
+
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20
+];
+
+/// Material value of a piece in centipawns
+pub fn value(level: Level) -> i32 {
+    match level {
+        Level::Pawn => 100,
+        Level::Knight => 320,
+        Level::Bishop => 330,
+        Level::Rook => 500,
+        Level::Queen => 900,
+        Level::King => 0
+    }
+}
+
+/// Positional value of a piece of the color on the square
+fn square_value(color: Color, level: Level, square: u8) -> i32 {
+    let table = match level {
+        Level::Pawn => &PAWN_TABLE,
+        Level::Knight => &KNIGHT_TABLE,
+        Level::Bishop => &BISHOP_TABLE,
+        Level::Rook => &ROOK_TABLE,
+        Level::Queen => &QUEEN_TABLE,
+        Level::King => &KING_TABLE
+    };
+    let (row, col) = ((square / 8) as usize, (square % 8) as usize);
+    let rank = if color == Color::White { 7 - row } else { row };
+    table[rank * 8 + col]
+}
+
+/// Evaluate the board in centipawns for the side to move
+///
+/// ### Examples
+///
+/// ```
+/// # use chess::game::prelude::*;
+/// use chess::engine::evaluate;
+/// let mut board = Board::new();
+/// assert_eq!(evaluate(&board)?, 0);
+///
+/// board.set("d8", None)?;
+/// assert!(evaluate(&board)? > 800);
+/// # Ok::<(), Error>(())
+/// ```
+pub fn evaluate(board: &Board<Piece>) -> Result<i32, Error> {
+    Ok(evaluate_position(&Position::from_board(board)?))
+}
+
+/// Evaluate the position in centipawns for the side to move
+pub fn evaluate_position(position: &Position) -> i32 {
+    let mut score = 0;
+    for color in [Color::White, Color::Black] {
+        let sign = if color == Color::White { 1 } else { -1 };
+        for level in [Level::Pawn, Level::Knight, Level::Bishop, Level::Rook, Level::Queen, Level::King] {
+            let mut pieces = position.pieces(color, level);
+            while pieces != 0 {
+                let square = pieces.trailing_zeros() as u8;
+                pieces &= pieces - 1;
+                score += sign * (value(level) + square_value(color, level, square));
+            }
+        }
+    }
+
+    if position.side_to_move == Color::White { score } else { -score }
+}
+
+/// Move found by the search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BestMove {
+    pub from: String,
+    pub to: String,
+    pub promotion: Option<Level>,
+    /// Score in centipawns for the side to move
+    pub score: i32,
+    /// Depth of the deepest finished iteration, 0 when the time ran out
+    /// before the first move was searched
+    pub depth: u32
+}
+
+/// Legal moves of the side to move, ordered by most valuable victim and
+/// least valuable attacker, then promotions
+fn ordered_moves(position: &Position) -> Vec<Move> {
+    let mut moves = position.legal_moves();
+    moves.sort_by_key(|mv| {
+        let attacker = position.piece_at(mv.from()).map_or(0, |(_color, level)| value(level));
+        let victim = match position.piece_at(mv.to()) {
+            Some((_color, level)) => value(level),
+            None if mv.is_en_passant() => value(Level::Pawn),
+            None => 0
+        };
+        let capture = if mv.is_capture() { victim * 10 - attacker / 10 } else { 0 };
+        std::cmp::Reverse(capture + mv.promotion().map_or(0, value))
+    });
+    moves
+}
+
+/// State of a running search
+struct Search {
+    deadline: Instant,
+    aborted: bool
+}
+
+impl Search {
+    fn out_of_time(&mut self) -> bool {
+        if !self.aborted && Instant::now() >= self.deadline {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    fn alpha_beta(&mut self, position: &mut Position, depth: u32, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(position, alpha, beta);
+        }
+
+        let moves = ordered_moves(position);
+        if moves.is_empty() {
+            return if position.is_check(position.side_to_move) { -MATE + ply } else { 0 };
+        }
+        if position.halfmove_clock >= 100 {
+            return 0;
+        }
+
+        for mv in moves {
+            let undo = position.make(mv);
+            let score = -self.alpha_beta(position, depth - 1, -beta, -alpha, ply + 1);
+            position.unmake(mv, undo);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    /// Search the captures until the position is quiet
+    fn quiescence(&mut self, position: &mut Position, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+
+        let stand_pat = evaluate_position(position);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        for mv in ordered_moves(position).into_iter().filter(Move::is_capture) {
+            let undo = position.make(mv);
+            let score = -self.quiescence(position, -beta, -alpha);
+            position.unmake(mv, undo);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+}
+
+/// Search settings of the engine
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Engine {
+    /// Maximum depth of the search in plies
+    pub depth: u32,
+    /// Maximum thinking time, the move of the deepest finished iteration is played
+    pub time: Duration
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        Engine { depth: 3, time: Duration::from_secs(2) }
+    }
+}
+
+impl Engine {
+    /// Create new `Engine` searching up to `depth` plies for at most `time`
+    pub fn new(depth: u32, time: Duration) -> Engine {
+        Engine { depth, time }
+    }
+
+    /// Search the best move of the side to move, `None` when it has no legal move
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// use chess::engine::Engine;
+    /// use std::time::Duration;
+    ///
+    /// let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")?;
+    /// let best = Engine::new(2, Duration::from_secs(10)).best_move(&board)?.unwrap();
+    ///
+    /// assert_eq!((best.from.as_str(), best.to.as_str()), ("a1", "a8"));
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn best_move(&self, board: &Board<Piece>) -> Result<Option<BestMove>, Error> {
+        let mut search = Search { deadline: Instant::now() + self.time, aborted: false };
+        let mut position = Position::from_board(board)?;
+        let mut moves = ordered_moves(&position);
+        let mut best: Option<(Move, i32, u32)> = None;
+
+        for depth in 1..=self.depth.max(1) {
+            let mut alpha = -INFINITY;
+            let mut best_index = None;
+
+            for (index, mv) in moves.iter().enumerate() {
+                let undo = position.make(*mv);
+                let score = -search.alpha_beta(&mut position, depth - 1, -INFINITY, -alpha, 1);
+                position.unmake(*mv, undo);
+                if search.aborted {
+                    break;
+                }
+                if score > alpha {
+                    alpha = score;
+                    best_index = Some(index);
+                }
+            }
+
+            // an unfinished iteration is only played when there's nothing better
+            if let Some(index) = best_index.filter(|_| !search.aborted || best.is_none()) {
+                // the best move is searched first in the next iteration
+                let mv = moves.remove(index);
+                moves.insert(0, mv);
+                best = Some((mv, alpha, depth));
+            }
+
+            if search.aborted || alpha.abs() >= MATE - depth as i32 {
+                break;
+            }
+        }
+
+        // out of time before any move was searched, the first ordered one is played
+        let best = best.or_else(|| moves.first().map(|mv| (*mv, evaluate_position(&position), 0)));
+        Ok(best.map(|(mv, score, depth)| {
+            let (from, to) = mv.cells();
+            BestMove { from, to, promotion: mv.promotion(), score, depth }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn best_move(fen: &str, depth: u32) -> Result<Option<BestMove>, Error> {
+        Engine::new(depth, Duration::from_secs(30)).best_move(&Board::from_fen(fen)?)
+    }
+
+    #[test]
+    fn evaluate_material() -> Result<(), Error> {
+        let board = Board::new();
+        assert_eq!(evaluate(&board)?, 0);
+
+        // a knight up for White, scored for the side to move
+        let board = Board::from_fen("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")?;
+        assert!(evaluate(&board)? > 250);
+        let board = Board::from_fen("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")?;
+        assert!(evaluate(&board)? < -250);
+        Ok(())
+    }
+
+    #[test]
+    fn engine_mate_in_one() -> Result<(), Error> {
+        let best = best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3)?.unwrap();
+        assert_eq!((best.from.as_str(), best.to.as_str()), ("a1", "a8"));
+        assert!(best.score > MATE - 10);
+        Ok(())
+    }
+
+    #[test]
+    fn engine_captures() -> Result<(), Error> {
+        // the hanging queen is taken
+        let best = best_move("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2)?.unwrap();
+        assert_eq!((best.from.as_str(), best.to.as_str()), ("d2", "d5"));
+
+        // the quiescence search sees the defended pawn
+        let best = best_move("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", 1)?.unwrap();
+        assert_ne!(best.to, "d5");
+        Ok(())
+    }
+
+    #[test]
+    fn engine_promotes() -> Result<(), Error> {
+        let best = best_move("8/P6k/8/8/8/8/8/K7 w - - 0 1", 2)?.unwrap();
+        assert_eq!((best.from.as_str(), best.to.as_str()), ("a7", "a8"));
+        assert_eq!(best.promotion, Some(Level::Queen));
+        Ok(())
+    }
+
+    #[test]
+    fn engine_no_moves() -> Result<(), Error> {
+        assert!(best_move("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 2)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn engine_time_limit() -> Result<(), Error> {
+        let engine = Engine::new(20, Duration::from_millis(200));
+        let start = Instant::now();
+        let best = engine.best_move(&Board::new())?;
+        assert!(best.is_some());
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // a move is played even without time to search it
+        let best = Engine::new(3, Duration::ZERO).best_move(&Board::new())?.unwrap();
+        assert_eq!(best.depth, 0);
+        assert!(Engine::new(3, Duration::ZERO).best_move(&Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1")?)?.is_none());
+        Ok(())
+    }
+}
@@ -4,12 +4,17 @@ use std::fmt;
 use std::error;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     InvalidNotation(String),
     PromotionError(String),
     CastlingError(String),
     IllegalMoves(String),
-    GameError(String)
+    TurnError(String),
+    GameError(String),
+    ProtocolError(String),
+    StorageError(String),
+    AccountError(String)
 }
 
 impl error::Error for Error {}
@@ -21,7 +26,11 @@ impl fmt::Display for Error {
             Error::PromotionError(msg) => write!(f, "Promotion Error: {msg}"),
             Error::CastlingError(msg) => write!(f, "Castling Error: {msg}"),
             Error::IllegalMoves(msg) => write!(f, "Invalid Moves: {msg}"),
-            Error::GameError(msg) => write!(f, "Game Error: {msg}")
+            Error::TurnError(msg) => write!(f, "Turn Error: {msg}"),
+            Error::GameError(msg) => write!(f, "Game Error: {msg}"),
+            Error::ProtocolError(msg) => write!(f, "Protocol Error: {msg}"),
+            Error::StorageError(msg) => write!(f, "Storage Error: {msg}"),
+            Error::AccountError(msg) => write!(f, "Account Error: {msg}")
         }
     }
 }
@@ -1,19 +1,24 @@
 //! Chess Game
 //!
-pub mod vectors;
 pub mod color;
 pub mod level;
 pub mod piece;
 pub mod history;
 pub mod board;
+pub mod draw;
+pub mod moves;
+pub mod position;
+pub mod perft;
 
 pub mod prelude {
-    pub use crate::game::vectors::Movement;
     pub use crate::game::color::Color;
     pub use crate::game::level::Level;
     pub use crate::game::piece::Piece;
     pub use crate::game::history::History;
     pub use crate::game::board::Board;
+    pub use crate::game::draw::DrawReason;
+    pub use crate::game::moves::Move;
+    pub use crate::game::position::Position;
     pub use crate::error::Error;
 }
 
@@ -30,22 +35,29 @@ use crate::error::Error::{self, *};
 /// # Ok::<(), chess::error::Error>(())
 /// ```
 pub fn convert(cell: &str) -> Result<(i8, i8), Error> {
-    let col = match &cell[0..1] {
-        "a" => 0,
-        "b" => 1,
-        "c" => 2,
-        "d" => 3,
-        "e" => 4,
-        "f" => 5,
-        "g" => 6,
-        "h" => 7,
-        _ => return Err(InvalidNotation("use proper notation, examples: 'a1' 'b2' 'h8'".to_owned())),
+    let invalid = || InvalidNotation("use proper notation, examples: 'a1' 'b2' 'h8'".to_owned());
+    let mut chars = cell.chars();
+    let (file, rank) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(file), Some(rank), None) => (file, rank),
+        _ => return Err(invalid())
     };
 
-    match (&cell[1..2]).parse::<i8>() {
-        Ok(n) if n <= 8 && n > 0 => return Ok((n - 1, col)),
-        _ => return Err(InvalidNotation("use proper notation, examples: 'a1' 'b2' 'h8'".to_owned()))
+    let col = match file {
+        'a' => 0,
+        'b' => 1,
+        'c' => 2,
+        'd' => 3,
+        'e' => 4,
+        'f' => 5,
+        'g' => 6,
+        'h' => 7,
+        _ => return Err(invalid()),
     };
+
+    match rank.to_digit(10) {
+        Some(n) if (1..=8).contains(&n) => Ok((n as i8 - 1, col)),
+        _ => Err(invalid())
+    }
 }
 
 /// Convert tuple (row, col) into chess notation
@@ -70,13 +82,41 @@ pub fn invert(row: i8, col: i8) -> Result<String, Error> {
         _ => return Err(InvalidNotation("col must be one of (01234567)".to_owned())),
     };
 
-    if row > 7 || row < 0 {
+    if !(0..=7).contains(&row) {
         return Err(InvalidNotation("row must be one of (01234567)".to_owned()));
     }
 
     Ok(format!("{}{}", col, row + 1))
 }
 
+/// Convert chess notation into a square, from `0` for a1 to `63` for h8
+///
+/// ### Examples
+///
+/// ```
+/// use chess::game::convert_square;
+/// assert_eq!(convert_square("a1")?, 0);
+/// assert_eq!(convert_square("e4")?, 28);
+/// # Ok::<(), chess::error::Error>(())
+/// ```
+pub fn convert_square(cell: &str) -> Result<u8, Error> {
+    let (row, col) = convert(cell)?;
+    Ok((row * 8 + col) as u8)
+}
+
+/// Convert a square into chess notation
+///
+/// ### Examples
+///
+/// ```
+/// use chess::game::invert_square;
+/// assert_eq!(invert_square(63)?, "h8");
+/// # Ok::<(), chess::error::Error>(())
+/// ```
+pub fn invert_square(square: u8) -> Result<String, Error> {
+    invert((square / 8) as i8, (square % 8) as i8)
+}
+
 /// Get enemy color given color
 ///
 /// ### Examples
@@ -101,6 +141,9 @@ mod tests {
     fn converting() -> Result<(), Error> {
         assert_eq!(convert("a1")?, (0, 0));
         assert_eq!(convert("h8")?, (7, 7));
+        for cell in ["", "e", "e22", "i1", "a0", "a9", "é1", "1é"] {
+            assert!(convert(cell).is_err());
+        }
         Ok(())
     }
 
@@ -111,6 +154,14 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn squares() -> Result<(), Error> {
+        assert_eq!(convert_square("h1")?, 7);
+        assert_eq!(invert_square(convert_square("c6")?)?, "c6");
+        assert!(invert_square(64).is_err());
+        Ok(())
+    }
+
     #[test]
     fn enemy_color() {
         assert_eq!(get_enemy_color(Color::White), Color::Black);
@@ -0,0 +1,1484 @@
+//! Chess Board
+//!
+//! The cells keep the pieces with their notation, the moves are generated
+//! and checked on the bitboard [`Position`] of the board.
+
+use std::{fmt::Display, io::{self, Write}};
+use std::collections::HashMap;
+use super::{
+    Color,
+    Level,
+    Piece,
+    History,
+    DrawReason,
+    history::Castling,
+    Move,
+    Position,
+    convert,
+    convert_square,
+    invert,
+    invert_square,
+    position::squares,
+    get_enemy_color
+};
+use crate::error::Error::{self, *};
+use serde::{Deserialize, Serialize};
+
+/// Chess Board
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Board<T> {
+    pub cells: Vec<Vec<Option<T>>>,
+    pub history: Vec<History>,
+    #[serde(default)]
+    pub turn: Color,
+    /// Moves since the last capture or pawn advance
+    #[serde(default)]
+    pub halfmove_clock: u32,
+    /// Number of the full move, starts at 1 and incremented after Black's move
+    #[serde(default = "default_fullmove_number")]
+    pub fullmove_number: u32,
+    /// En passant target of the starting position, used while `history` is empty
+    #[serde(default)]
+    pub en_passant: Option<String>,
+    /// FEN of the starting position, `None` for the standard starting position
+    #[serde(default)]
+    pub start_fen: Option<String>
+}
+
+fn default_fullmove_number() -> u32 { 1 }
+
+impl<T: Display> Display for Board<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (_i, row) in self.cells.iter().enumerate().rev() {
+            for cell in row.iter() {
+                if let Some(piece) = cell {
+                    write!(f, "{} ", piece)?;
+                } else {
+                    write!(f, "⬛ ")?;
+                };
+            };
+            writeln!(f)?;
+        };
+        Ok(())
+    }
+}
+
+impl Default for Board<Piece> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Board<Piece> {
+    /// Create a new chess board already filled with pieces
+    pub fn new() -> Self {
+        Self {
+            cells: vec![
+                vec![
+                    Some(Piece::new(Level::Rook, Color::White)),
+                    Some(Piece::new(Level::Knight, Color::White)),
+                    Some(Piece::new(Level::Bishop, Color::White)),
+                    Some(Piece::new(Level::Queen, Color::White)),
+                    Some(Piece::new(Level::King, Color::White)),
+                    Some(Piece::new(Level::Bishop, Color::White)),
+                    Some(Piece::new(Level::Knight, Color::White)),
+                    Some(Piece::new(Level::Rook, Color::White)),
+                ],
+                vec![Some(Piece::new(Level::Pawn, Color::White)); 8],
+                vec![None; 8],
+                vec![None; 8],
+                vec![None; 8],
+                vec![None; 8],
+                vec![Some(Piece::new(Level::Pawn, Color::Black)); 8],
+                vec![
+                    Some(Piece::new(Level::Rook, Color::Black)),
+                    Some(Piece::new(Level::Knight, Color::Black)),
+                    Some(Piece::new(Level::Bishop, Color::Black)),
+                    Some(Piece::new(Level::Queen, Color::Black)),
+                    Some(Piece::new(Level::King, Color::Black)),
+                    Some(Piece::new(Level::Bishop, Color::Black)),
+                    Some(Piece::new(Level::Knight, Color::Black)),
+                    Some(Piece::new(Level::Rook, Color::Black)),
+                ],
+            ],
+            history: vec![],
+            turn: Color::White,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            en_passant: None,
+            start_fen: None
+        }
+    }
+
+    /// Get the color of the side to move
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// assert_eq!(board.side_to_move(), Color::White);
+    ///
+    /// board.moves_piece("e2", "e4", None)?;
+    /// assert_eq!(board.side_to_move(), Color::Black);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn side_to_move(&self) -> Color {
+        self.turn
+    }
+
+    /// Print chess board for debuging purposes
+    pub fn print(&self) -> std::fmt::Result {
+        for (i, row) in self.cells.iter().enumerate().rev() {
+            print!("{} ", i+1);
+            for cell in row.iter() {
+                if let Some(piece) = cell {
+                    print!("{} ", piece);
+                } else {
+                    print!("⬛");
+                };
+            };
+            println!();
+        };
+        println!("  a b c d e f g h");
+        io::stdout().flush().unwrap();
+        Ok(())
+    }
+
+    /// Get a piece on the board
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::new();
+    /// let piece = board.get("a1")?.unwrap();
+    ///
+    /// assert_eq!(piece.color, Color::White);
+    /// assert_eq!(piece.level, Level::Rook);
+    /// # assert!(board.get("a3")?.is_none());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn get(&self, cell: &str) -> Result<Option<Piece>, Error> {
+        let (row, col) = convert(cell)?;
+        Ok(self.cells[row as usize][col as usize].clone())
+    }
+
+    /// Get a piece on the board, failing when the cell is empty
+    fn get_piece(&self, cell: &str) -> Result<Piece, Error> {
+        self.get(cell)?.ok_or_else(|| IllegalMoves(format!("There is no piece on {}", cell)))
+    }
+
+    /// Set a piece on the board
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// let white_queen = Piece::new(Level::Queen, Color::White);
+    /// board.set("a1", Some(white_queen));
+    ///
+    /// # let piece = board.get("a1")?.unwrap();
+    /// # assert_eq!(piece.color, Color::White);
+    /// # assert_eq!(piece.level, Level::Queen);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn set(&mut self, cell: &str, piece: Option<Piece>) -> Result<(), Error> {
+        let (row, col) = convert(cell)?;
+        self.cells[row as usize][col as usize] = piece;
+        Ok(())
+    }
+
+    /// Get moves history
+    pub fn get_history(&self) -> &Vec<History> {
+        &self.history
+    }
+
+    /// Show captured piece by color
+    pub fn get_captured(&self, color: Color) -> Result<Vec<Piece>, Error> {
+        let mut captured_piece = vec![];
+        for hist in self.history.iter() {
+            if let Some(piece) = hist.captured.clone() {
+                if piece.color == color {
+                    captured_piece.push(piece);
+                }
+            }
+        }
+        Ok(captured_piece)
+    }
+
+    /// Get the en passant target cell, if any
+    ///
+    /// The target is the cell skipped by a pawn's two-square advance
+    /// in the last move, where an enemy pawn can capture it en passant.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// assert_eq!(board.get_en_passant_target()?, Some("e3".to_owned()));
+    ///
+    /// board.moves_piece("e7", "e6", None)?;
+    /// assert_eq!(board.get_en_passant_target()?, None);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn get_en_passant_target(&self) -> Result<Option<String>, Error> {
+        let his = match self.history.last() {
+            Some(his) => his,
+            None => return Ok(self.en_passant.clone())
+        };
+
+        match self.get(&his.to)? {
+            Some(piece) if piece.level == Level::Pawn => (),
+            _ => return Ok(None)
+        }
+
+        let (from_row, col) = convert(&his.from)?;
+        let (to_row, _col) = convert(&his.to)?;
+        if (from_row - to_row).abs() != 2 {
+            return Ok(None);
+        }
+
+        Ok(Some(invert((from_row + to_row) / 2, col)?))
+    }
+
+    /// Get all possible moves for a piece given the cell location
+    ///
+    /// The moves leaving the own king in check are included, see
+    /// [`Board::legal_moves`]. A king that can castle has the cell two files
+    /// towards its rook among its moves.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::new();
+    /// let white_pawn = board.get_possible_moves("a2")?;
+    /// let black_knight = board.get_possible_moves("b8")?;
+    ///
+    /// assert_eq!(white_pawn, ["a3", "a4"]);
+    /// assert_eq!(black_knight, ["a6", "c6"]);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn get_possible_moves(&self, cell: &str) -> Result<Vec<String>, Error> {
+        let piece = self.get_piece(cell)?;
+        let from = convert_square(cell)?;
+        Ok(targets(self.position_for(piece.color)?.pseudo_legal_moves(), from))
+    }
+
+    /// Get possible moves for a cell return it as a string
+    pub fn get_possible_moves_as_string(&self, cell: &str) -> String {
+        match self.get_possible_moves(cell) {
+            Ok(vek) => vek.join(" "),
+            Err(_) => "".to_owned()
+        }
+    }
+
+    /// Get all possible moves for all pieces by color
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// let paz = board.get_possible_moves_by_color(Color::White)?;
+    ///
+    /// assert_eq!(*paz.get("c2").unwrap(), ["c3", "c4"]);
+    /// assert_eq!(*paz.get("d1").unwrap(), ["e2", "f3", "g4", "h5"]);
+    /// assert_eq!(*paz.get("e1").unwrap(), ["e2"]);
+    /// assert_eq!(*paz.get("f1").unwrap(), ["a6", "b5", "c4", "d3", "e2"]);
+    /// assert_eq!(*paz.get("g1").unwrap(), ["e2", "f3", "h3"]);
+    /// assert_eq!(paz.get("h1"), None);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn get_possible_moves_by_color(&self, color: Color) -> Result<HashMap<String, Vec<String>>, Error> {
+        Ok(targets_by_cell(self.position_for(color)?.pseudo_legal_moves()))
+    }
+
+    /// Get all legal moves for a piece given the cell location
+    ///
+    /// Unlike [`Board::get_possible_moves`], moves leaving the own king
+    /// in check are excluded. Castling is included as the king moving two
+    /// cells towards its rook when the king is not in check and doesn't
+    /// pass through an attacked cell.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// board.moves_piece("e7", "e5", None)?;
+    /// board.moves_piece("d1", "h5", None)?;
+    ///
+    /// // the pawn is pinned to its king
+    /// assert!(!board.get_possible_moves("f7")?.is_empty());
+    /// assert!(board.legal_moves("f7")?.is_empty());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn legal_moves(&self, cell: &str) -> Result<Vec<String>, Error> {
+        let piece = self.get(cell)?
+            .ok_or_else(|| IllegalMoves(format!("there is no piece on {}", cell)))?;
+        let from = convert_square(cell)?;
+        Ok(targets(self.position_for(piece.color)?.legal_moves(), from))
+    }
+
+    /// Get all legal moves for all pieces by color
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// board.moves_piece("e7", "e5", None)?;
+    /// board.moves_piece("f1", "c4", None)?;
+    /// board.moves_piece("b8", "c6", None)?;
+    /// board.moves_piece("d1", "h5", None)?;
+    /// board.moves_piece("g8", "f6", None)?;
+    /// board.moves_piece("h5", "f7", None)?;
+    ///
+    /// assert!(board.legal_moves_by_color(Color::Black)?.is_empty());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn legal_moves_by_color(&self, color: Color) -> Result<HashMap<String, Vec<String>>, Error> {
+        Ok(targets_by_cell(self.position_for(color)?.legal_moves()))
+    }
+
+    /// Get the bitboard position with the `color` to move
+    ///
+    /// The en passant capture is only possible for the side to move.
+    fn position_for(&self, color: Color) -> Result<Position, Error> {
+        let mut position = Position::from_board(self)?;
+        if position.side_to_move != color {
+            position.side_to_move = color;
+            position.en_passant = None;
+        }
+        Ok(position)
+    }
+
+    /// Moves a piece on the board
+    ///
+    /// The move must be legal for the color of the piece, it can't leave its
+    /// own king in check. A king moving two cells castles, its rook is moved too. A pawn
+    /// reaching the last rank must be promoted to the `promotion` level,
+    /// which is only allowed for such a move.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("a2", "a4", None)?;
+    ///
+    /// assert_eq!(board.get("a4")?.unwrap().level, Level::Pawn);
+    /// assert_eq!(board.get("a4")?.unwrap().color, Color::White);
+    /// assert!(board.get("a2")?.is_none());
+    ///
+    /// let mut board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1")?;
+    /// assert!(board.moves_piece("a7", "a8", None).is_err());
+    /// board.moves_piece("a7", "a8", Some(Level::Queen))?;
+    /// assert_eq!(board.get("a8")?.unwrap().level, Level::Queen);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn moves_piece(&mut self, src_cell: &str, des_cell: &str, promotion: Option<Level>) -> Result<String, Error> {
+        let mut src_piece = self.get_piece(src_cell)?;
+        let mut des_piece = self.get(des_cell)?;
+        let mut has_moved = None;
+        let mut captured_at = None;
+        let mut castling = None;
+
+        if !self.legal_moves(src_cell)?.iter().any(|s| { s == des_cell }) {
+            return Err(IllegalMoves(format!("can't move {} to {}", src_cell, des_cell)));
+        }
+
+        let (src_row, src_col) = convert(src_cell)?;
+        let (des_row, des_col) = convert(des_cell)?;
+        let last_rank = src_piece.level == Level::Pawn && (des_row == 0 || des_row == 7);
+        match (last_rank, promotion) {
+            (true, None) => return Err(PromotionError(format!("The pawn moving to {} must be promoted.", des_cell))),
+            (true, Some(Level::Pawn)) => return Err(PromotionError("Can't promote a pawn to a pawn.".to_owned())),
+            (true, Some(Level::King)) => return Err(PromotionError("Can't promote a pawn to a king.".to_owned())),
+            (false, Some(_)) => return Err(PromotionError("Only a pawn reaching the last rank can be promoted.".to_owned())),
+            _ => ()
+        }
+
+        // a king moving two cells castles, the rook jumps over it
+        if src_piece.level == Level::King && (src_col - des_col).abs() == 2 {
+            let rook_from = invert(src_row, if des_col > src_col { 7 } else { 0 })?;
+            let rook_to = invert(src_row, (src_col + des_col) / 2)?;
+            let mut rook = self.get_piece(&rook_from)?;
+            rook.moved = Some(true);
+            self.set(&rook_to, Some(rook))?;
+            self.set(&rook_from, None)?;
+            castling = Some(Castling { rook_from, rook_to });
+        }
+
+        // a pawn moving diagonally to an empty cell captures en passant,
+        // the captured pawn stands beside the source cell
+        if src_piece.level == Level::Pawn && des_piece.is_none() {
+            let (src_row, src_col) = convert(src_cell)?;
+            let (_des_row, des_col) = convert(des_cell)?;
+            if src_col != des_col {
+                let cell = invert(src_row, des_col)?;
+                des_piece = self.get(&cell)?;
+                self.set(&cell, None)?;
+                captured_at = Some(cell);
+            }
+        }
+
+        // change moved to true for pawn, rook, or a king
+        match &src_piece.level {
+            Level::Pawn | Level::Rook | Level::King if src_piece.moved == Some(false) => {
+                src_piece.moved = Some(true);
+                has_moved = Some(true);
+            },
+            _ => ()
+        }
+
+        // do the actual moves, the promoted piece replaces the pawn
+        let moved_piece = match promotion {
+            Some(level) => Piece::new(level, src_piece.color),
+            None => src_piece.clone()
+        };
+        self.set(des_cell, Some(moved_piece))?;
+        self.set(src_cell, None)?;
+
+        // write the moves to board history
+        let castled = castling.is_some();
+        self.history.push(History {
+            from: src_cell.to_owned(),
+            to: des_cell.to_owned(),
+            captured: des_piece.clone(),
+            has_moved,
+            captured_at,
+            halfmove_clock: self.halfmove_clock,
+            promotion,
+            position: 0,
+            castling
+        });
+        self.turn = get_enemy_color(src_piece.color);
+        self.tick_clocks(src_piece.color, src_piece.level == Level::Pawn || des_piece.is_some());
+        self.record_position();
+
+        if castled {
+            return Ok(format!("Castling {} {} from {} to {}", src_piece.color, src_piece.level, src_cell, des_cell));
+        }
+
+        let mut description = format!("Moving {} {} from {} to {}", src_piece.color, src_piece.level, src_cell, des_cell);
+        if let Some(piece) = des_piece {
+            description.push_str(&format!(", captured {} {}", piece.color, piece.level));
+        }
+        if let Some(level) = promotion {
+            description.push_str(&format!(", promoted to {}", level));
+        }
+        Ok(description)
+    }
+
+    /// Update the move clocks after `color` has moved
+    fn tick_clocks(&mut self, color: Color, irreversible: bool) {
+        if irreversible {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == Color::Black {
+            self.fullmove_number += 1;
+        }
+    }
+
+    /// Store the hash of the current position on the last history entry
+    fn record_position(&mut self) {
+        let position = self.position_hash();
+        if let Some(his) = self.history.last_mut() {
+            his.position = position;
+        }
+    }
+
+    /// Get the hash of the current position
+    ///
+    /// Two positions are the same when the pieces, the side to move and
+    /// the castling rights are the same, and the same en passant capture
+    /// is possible.
+    pub fn position_hash(&self) -> u64 {
+        let fen = self.to_fen();
+        let mut key = fen.split(' ').take(3).collect::<Vec<&str>>().join(" ");
+        if let Some(target) = self.get_capturable_en_passant() {
+            key.push(' ');
+            key.push_str(&target);
+        }
+
+        // FNV-1a, stable across builds so the hashes can be stored
+        key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    /// Get the en passant target only when a pawn of the side to move can capture on it
+    fn get_capturable_en_passant(&self) -> Option<String> {
+        let target = self.get_en_passant_target().ok().flatten()?;
+        let (row, col) = convert(&target).ok()?;
+        let pawn_row = if self.turn == Color::White { row - 1 } else { row + 1 };
+        let capturable = [col - 1, col + 1].iter()
+            .filter(|c| (0..8).contains(*c))
+            .filter_map(|c| self.cells[pawn_row as usize][*c as usize].as_ref())
+            .any(|p| p.level == Level::Pawn && p.color == self.turn);
+        capturable.then_some(target)
+    }
+
+    /// Undo the last moves in board history
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("a2", "a4", None)?;
+    /// board.undo_moves()?;
+    ///
+    /// assert_eq!(board.get("a2")?.unwrap().level, Level::Pawn);
+    /// assert_eq!(board.get("a2")?.unwrap().color, Color::White);
+    /// assert!(board.get("a4")?.is_none());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn undo_moves(&mut self) -> Result<String, Error> {
+        let his = self.history.pop()
+            .ok_or_else(|| GameError("Already the oldest state.".to_owned()))?;
+        let mut piece = self.get_piece(&his.to)?;
+
+        // a promoted piece turns back into a pawn
+        if his.promotion.is_some() {
+            piece = Piece::new(Level::Pawn, piece.color);
+            piece.moved = Some(true);
+        }
+
+        if his.has_moved.is_some() {
+            piece.moved = Some(false);
+        }
+
+        self.turn = piece.color;
+        self.halfmove_clock = his.halfmove_clock;
+        if piece.color == Color::Black {
+            self.fullmove_number -= 1;
+        }
+        self.set(&his.from, Some(piece))?;
+        match his.captured_at {
+            Some(ref cell) => {
+                self.set(&his.to, None)?;
+                self.set(cell, his.captured)?;
+            },
+            None => self.set(&his.to, his.captured)?
+        }
+
+        // a castling puts the rook back on its unmoved cell
+        if let Some(castling) = his.castling {
+            let mut rook = self.get_piece(&castling.rook_to)?;
+            rook.moved = Some(false);
+            self.set(&castling.rook_from, Some(rook))?;
+            self.set(&castling.rook_to, None)?;
+        }
+
+        Ok(format!("Undo the moves from {} to {}", his.from, his.to))
+    }
+
+    /// Get the board after the first `ply` moves, replaying the history on
+    /// the starting position
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// board.moves_piece("e7", "e5", None)?;
+    ///
+    /// assert_eq!(board.position_at(0)?, Board::new());
+    /// assert_eq!(board.position_at(1)?.get("e4")?.unwrap().level, Level::Pawn);
+    /// assert!(board.position_at(1)?.get("e5")?.is_none());
+    /// assert_eq!(board.position_at(2)?, board);
+    /// assert!(board.position_at(3).is_err());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn position_at(&self, ply: usize) -> Result<Board<Piece>, Error> {
+        let moves = self.history.get(..ply)
+            .ok_or_else(|| GameError(format!("The game has only {} moves.", self.history.len())))?;
+        let mut board = match &self.start_fen {
+            Some(fen) => Board::from_fen(fen)?,
+            None => Board::new()
+        };
+        for his in moves {
+            board.moves_piece(&his.from, &his.to, his.promotion)?;
+        }
+        Ok(board)
+    }
+
+    /// Castling
+    ///
+    /// The rules for castling
+    /// * castling is only possible if neither the king nor the rook has moved
+    /// * there must not be any pieces between the king and the rook
+    /// * the king may not be in check
+    /// * the square the king goes to and any intervening squares may not be under attack
+    /// * however, there is nothing to prevent castling if the rook is under attack
+    ///
+    /// The king moves two cells towards the rook, see [`Board::moves_piece`].
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")?;
+    /// board.castling("e1", "a1")?;
+    ///
+    /// assert_eq!(board.get("c1")?.unwrap().level, Level::King);
+    /// assert_eq!(board.get("d1")?.unwrap().level, Level::Rook);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn castling(&mut self, king_cell: &str, rook_cell: &str) -> Result<String, Error> {
+        let (king_to, _rook_to) = self.castling_cells(king_cell, rook_cell)?;
+        self.moves_piece(king_cell, &king_to, None)
+    }
+
+    /// Get the cells the king and the rook move to when castling
+    ///
+    /// Fails with a `CastlingError` telling why when the castling isn't possible.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")?;
+    /// assert_eq!(board.castling_cells("e1", "h1")?, ("g1".to_owned(), "f1".to_owned()));
+    /// assert!(board.castling_cells("e8", "h8").is_ok());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn castling_cells(&self, king_cell: &str, rook_cell: &str) -> Result<(String, String), Error> {
+        let king = self.get_piece(king_cell)?;
+        let rook = self.get(rook_cell)?
+            .ok_or_else(|| CastlingError("Only a King and a Rook can do castling".to_owned()))?;
+        let (king_row, king_col) = convert(king_cell)?;
+        let (rook_row, rook_col) = convert(rook_cell)?;
+        let enemy_color = get_enemy_color(king.color);
+
+        if king.level != Level::King || rook.level != Level::Rook || king_row != rook_row {
+            return Err(CastlingError("Only a King and a Rook can do castling".to_owned()))
+        }
+
+        if king.color != rook.color {
+            return Err(CastlingError("King and Rook have different color".to_owned()))
+        }
+
+        if king.moved != Some(false) || rook.moved != Some(false) {
+            return Err(CastlingError("King or Rook has already moved".to_owned()))
+        }
+
+        let (start, end, king_to, rook_to);
+        if king_col < rook_col {
+            (start, end, king_to, rook_to) = (king_col, rook_col, 6, 5);
+        } else {
+            (start, end, king_to, rook_to) = (rook_col, king_col, 2, 3);
+        }
+
+        for y in (start + 1)..end {
+            if let Some(_piece) = self.get(&invert(king_row, y)?)? {
+                return Err(CastlingError("Can't do castling, the path is blocked".to_owned()));
+            }
+        }
+
+        if self.is_king_checked(king.color)? {
+            return Err(CastlingError("King is in check.".to_owned()));
+        }
+
+        // only the cells the king passes through must be safe
+        let position = Position::from_board(self)?;
+        for y in king_to.min(rook_to)..=king_to.max(rook_to) {
+            if position.is_attacked(convert_square(&invert(king_row, y)?)?, enemy_color) {
+                return Err(CastlingError("Can't do castling, the path is under attack".to_owned()));
+            }
+        }
+
+        Ok((invert(king_row, king_to)?, invert(king_row, rook_to)?))
+    }
+
+    /// Get all pieces position on the board by its color
+    pub fn get_pieces_positions_by_color(&self, color: Color) -> Result<Vec<String>, Error> {
+        let mut all_pos = vec![];
+
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if let Some(piece) = cell {
+                    if piece.color == color {
+                        let pos = invert(y as i8, x as i8)?;
+                        all_pos.push(pos);
+                    }
+                }
+            }
+        }
+
+        Ok(all_pos)
+    }
+
+    /// Get all under attack cells by piece color
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// let paz = board.get_possible_attack_by_color(Color::White)?;
+    ///
+    /// assert_eq!(*paz.get("c2").unwrap(), ["b3", "d3"]);
+    /// assert_eq!(*paz.get("d1").unwrap(), ["e2", "f3", "g4", "h5"]);
+    /// assert_eq!(*paz.get("e1").unwrap(), ["e2"]);
+    /// assert_eq!(*paz.get("f1").unwrap(), ["a6", "b5", "c4", "d3", "e2"]);
+    /// assert_eq!(*paz.get("g1").unwrap(), ["e2", "f3", "h3"]);
+    /// assert_eq!(paz.get("h1"), None);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn get_possible_attack_by_color(&self, color: Color) -> Result<HashMap<String, Vec<String>>, Error> {
+        let position = Position::from_board(self)?;
+        let mut attack_pos = HashMap::new();
+
+        for pos in self.get_pieces_positions_by_color(color)? {
+            let attacks = position.attacks(convert_square(&pos)?) & !position.occupied_by(color);
+            let mut paz = squares(attacks).map(invert_square).collect::<Result<Vec<String>, Error>>()?;
+            paz.sort();
+            if !paz.is_empty() {
+                attack_pos.insert(pos, paz);
+            }
+        }
+
+        Ok(attack_pos)
+    }
+
+    /// Get king position given the color
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::new();
+    /// assert_eq!(board.get_king_position(Color::White)?, "e1");
+    /// assert_eq!(board.get_king_position(Color::Black)?, "e8");
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn get_king_position(&self, color: Color) -> Result<String, Error> {
+        for pos in self.get_pieces_positions_by_color(color)?.iter() {
+            if self.get_piece(pos)?.level == Level::King {
+                return Ok(pos.to_owned());
+            }
+        }
+        Err(GameError("King is not found.".to_owned()))
+    }
+
+    /// Checking if the king is under attack (check)
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// board.moves_piece("f7", "f6", None)?;
+    /// board.moves_piece("d1", "h5", None)?;
+    ///
+    /// assert_eq!(board.is_king_checked(Color::Black)?, true);
+    /// assert_eq!(board.is_king_checked(Color::White)?, false);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn is_king_checked(&self, king_color: Color) -> Result<bool, Error> {
+        let position = Position::from_board(self)?;
+        let king = position.king(king_color)
+            .ok_or_else(|| GameError("King is not found.".to_owned()))?;
+        Ok(position.is_attacked(king, get_enemy_color(king_color)))
+    }
+
+    /// Checking if checkmate
+    pub fn is_checkmate(&mut self, king_color: Color) -> Result<bool, Error> {
+        Ok(self.is_king_checked(king_color)? && !self.has_safe_moves(king_color)?)
+    }
+
+    /// Check is there a safe move(s) given the color
+    pub fn has_safe_moves(&mut self, color: Color) -> Result<bool, Error> {
+        Ok(self.position_for(color)?.has_legal_moves())
+    }
+
+    /// Check if it stalemate (no more possible moves)
+    pub fn is_stalemate(&mut self, color: Color) -> Result<bool, Error> {
+        if self.is_king_checked(color)? { return Ok(false) }
+        if self.has_safe_moves(color)? { return Ok(false) }
+        Ok(true)
+    }
+
+    /// Check if fifty moves by each side were made without a pawn move or a capture
+    pub fn is_fifty_move_rule(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    /// Check if the current position has occurred three times
+    ///
+    /// Only the positions since the last pawn move or capture are
+    /// compared, earlier positions can't occur again.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// for _ in 0..2 {
+    ///     board.moves_piece("g1", "f3", None)?;
+    ///     board.moves_piece("g8", "f6", None)?;
+    ///     board.moves_piece("f3", "g1", None)?;
+    ///     board.moves_piece("f6", "g8", None)?;
+    /// }
+    ///
+    /// assert!(board.is_threefold_repetition());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn is_threefold_repetition(&self) -> bool {
+        let current = self.position_hash();
+        let reversible = self.halfmove_clock as usize;
+        let mut count = self.history.iter()
+            .rev()
+            .take(reversible + 1)
+            .filter(|his| his.position == current)
+            .count();
+
+        // the starting position counts when no irreversible move was made since
+        if self.history.len() <= reversible {
+            let start = match &self.start_fen {
+                Some(fen) => Board::from_fen(fen).map(|board| board.position_hash()).ok(),
+                None => Some(Board::new().position_hash())
+            };
+            if start == Some(current) {
+                count += 1;
+            }
+        }
+
+        count >= 3
+    }
+
+    /// Check if neither side has enough pieces left to checkmate
+    ///
+    /// That is king against king, king and bishop against king, king
+    /// and knight against king, or kings and bishops all on the same
+    /// colored cells.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::from_fen("8/8/4k3/8/8/2B5/8/4K3 w - - 0 1")?;
+    /// assert!(board.is_insufficient_material());
+    ///
+    /// let board = Board::from_fen("8/8/4k3/8/8/2R5/8/4K3 w - - 0 1")?;
+    /// assert!(!board.is_insufficient_material());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut minors = vec![];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                match cell {
+                    Some(piece) if piece.level == Level::King => (),
+                    Some(piece) if matches!(piece.level, Level::Bishop | Level::Knight) => {
+                        minors.push((piece.level, (x + y) % 2));
+                    },
+                    Some(_) => return false,
+                    None => ()
+                }
+            }
+        }
+
+        match minors.as_slice() {
+            [] | [_] => true,
+            [(_, shade), ..] => minors.iter().all(|(level, s)| *level == Level::Bishop && s == shade)
+        }
+    }
+
+    /// Check if the pieces of `color` alone aren't enough to checkmate
+    ///
+    /// That is a lone king, or a king with a single bishop or knight.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::from_fen("4k3/4p3/8/8/8/8/8/1N2K3 w - - 0 1")?;
+    /// assert!(board.has_insufficient_material(Color::White));
+    /// assert!(!board.has_insufficient_material(Color::Black));
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn has_insufficient_material(&self, color: Color) -> bool {
+        let pieces = self.cells.iter()
+            .flatten()
+            .flatten()
+            .filter(|piece| piece.color == color && piece.level != Level::King)
+            .collect::<Vec<&Piece>>();
+
+        match pieces.as_slice() {
+            [] => true,
+            [piece] => matches!(piece.level, Level::Bishop | Level::Knight),
+            _ => false
+        }
+    }
+
+    /// Get the reason the game is drawn for the `color` to move, if any
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1")?;
+    /// assert_eq!(board.draw_reason(Color::Black)?, Some(DrawReason::Stalemate));
+    ///
+    /// let mut board = Board::from_fen("7k/8/6K1/8/8/8/8/8 b - - 0 1")?;
+    /// assert_eq!(board.draw_reason(Color::Black)?, Some(DrawReason::InsufficientMaterial));
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn draw_reason(&mut self, color: Color) -> Result<Option<DrawReason>, Error> {
+        if self.is_stalemate(color)? {
+            Ok(Some(DrawReason::Stalemate))
+        } else if self.is_insufficient_material() {
+            Ok(Some(DrawReason::InsufficientMaterial))
+        } else if self.is_threefold_repetition() {
+            Ok(Some(DrawReason::ThreefoldRepetition))
+        } else if self.is_fifty_move_rule() {
+            Ok(Some(DrawReason::FiftyMoveRule))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Check if it draw, see [`Board::draw_reason`]
+    pub fn is_draw(&mut self, color: Color) -> Result<bool, Error> {
+        Ok(self.draw_reason(color)?.is_some())
+    }
+
+}
+
+/// Destination cells of the moves from the square, a promotion is listed
+/// once for all its levels
+fn targets(moves: Vec<Move>, from: u8) -> Vec<String> {
+    let mut targets = moves.into_iter()
+        .filter(|mv| mv.from() == from)
+        .map(|mv| mv.cells().1)
+        .collect::<Vec<String>>();
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+/// Destination cells of the moves by source cell
+fn targets_by_cell(moves: Vec<Move>) -> HashMap<String, Vec<String>> {
+    let mut targets: HashMap<String, Vec<String>> = HashMap::new();
+    for mv in moves {
+        let (from, to) = mv.cells();
+        let cells = targets.entry(from).or_default();
+        if !cells.contains(&to) {
+            cells.push(to);
+        }
+    }
+    for cells in targets.values_mut() {
+        cells.sort();
+    }
+    targets
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn board_possible_moves_pawn() -> Result<(), Error> {
+        let board = Board::new();
+        let moves = board.get_possible_moves("a2");
+        assert_eq!(moves?, ["a3", "a4"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_pawn_blocked() -> Result<(), Error> {
+        // the pawn can't jump over a piece on its second cell
+        let mut board = Board::new();
+        board.set("h4", Some(Piece::new(Level::Queen, Color::Black)))?;
+        assert_eq!(board.get_possible_moves("h2")?, ["h3"]);
+        assert!(board.moves_piece("h2", "h4", None).is_err());
+
+        board.set("h3", Some(Piece::new(Level::Knight, Color::White)))?;
+        assert!(board.get_possible_moves("h2")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_knight() -> Result<(), Error> {
+        let board = Board::new();
+        let moves = board.get_possible_moves("b1");
+        assert_eq!(moves?, ["a3", "c3"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_rook() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("a2", "a4", None)?;
+        let moves = board.get_possible_moves("a1");
+        assert_eq!(moves?, ["a2", "a3"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_bishop() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        let moves = board.get_possible_moves("f1");
+        assert_eq!(moves?, ["a6", "b5", "c4", "d3", "e2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_queen() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("d2", "d4", None)?;
+        board.moves_piece("e2", "e4", None)?;
+        let moves = board.get_possible_moves("d1");
+        assert_eq!(moves?, ["d2", "d3", "e2", "f3", "g4", "h5"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_king() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("d2", "d4", None)?;
+        board.moves_piece("e2", "e4", None)?;
+        let moves = board.get_possible_moves("e1");
+        assert_eq!(moves?, ["d2", "e2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_by_color() -> Result<(), Error>  {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        let paz = board.get_possible_moves_by_color(Color::White)?;
+        assert_eq!(*paz.get("c2").unwrap(), ["c3", "c4"]);
+        assert_eq!(*paz.get("d1").unwrap(), ["e2", "f3", "g4", "h5"]);
+        assert_eq!(*paz.get("e1").unwrap(), ["e2"]);
+        assert_eq!(*paz.get("f1").unwrap(), ["a6", "b5", "c4", "d3", "e2"]);
+        assert_eq!(*paz.get("g1").unwrap(), ["e2", "f3", "h3"]);
+        assert_eq!(paz.get("h1"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn board_legal_moves_pinned() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("d2", "d4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        board.moves_piece("b1", "d2", None)?;
+        board.moves_piece("f8", "b4", None)?;
+        assert_eq!(board.get_possible_moves("d2")?, ["b1", "b3", "c4", "e4", "f3"]);
+        assert!(board.legal_moves("d2")?.is_empty());
+        assert_eq!(board.legal_moves("c2")?, ["c3", "c4"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_legal_moves_king() -> Result<(), Error> {
+        let board = Board::from_fen("4k3/8/8/8/8/8/3r4/4K3 w - - 0 1")?;
+        assert_eq!(board.get_possible_moves("e1")?, ["d1", "d2", "e2", "f1", "f2"]);
+        assert_eq!(board.legal_moves("e1")?, ["d2", "f1"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_legal_moves_castling() -> Result<(), Error> {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")?;
+        assert_eq!(board.legal_moves("e1")?, ["c1", "d1", "d2", "e2", "f1", "f2", "g1"]);
+        assert_eq!(board.get_possible_moves("e1")?, board.legal_moves("e1")?);
+
+        // can't castle through an attacked cell
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1")?;
+        assert!(!board.legal_moves("e1")?.contains(&"g1".to_owned()));
+        assert!(!board.get_possible_moves("e1")?.contains(&"g1".to_owned()));
+
+        // can't castle out of check
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1")?;
+        assert!(!board.legal_moves("e1")?.contains(&"c1".to_owned()));
+
+        // the rook may pass through an attacked cell
+        let board = Board::from_fen("1r2k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1")?;
+        assert!(board.legal_moves("e1")?.contains(&"c1".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn board_legal_moves_by_color() -> Result<(), Error> {
+        let board = Board::from_fen("3qk3/3p4/8/8/8/8/8/4R1K1 b - - 0 1")?;
+        let lm = board.legal_moves_by_color(Color::Black)?;
+        assert_eq!(lm.len(), 2);
+        assert_eq!(*lm.get("e8").unwrap(), ["f7", "f8"]);
+        assert_eq!(*lm.get("d8").unwrap(), ["e7"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_fifty_move_rule() -> Result<(), Error> {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80")?;
+        assert!(!board.is_fifty_move_rule());
+        board.moves_piece("a1", "a2", None)?;
+        assert!(board.is_fifty_move_rule());
+        assert_eq!(board.draw_reason(Color::Black)?, Some(DrawReason::FiftyMoveRule));
+        board.undo_moves()?;
+        board.moves_piece("e2", "e4", None)?;
+        assert!(!board.is_fifty_move_rule());
+        Ok(())
+    }
+
+    #[test]
+    fn board_threefold_repetition() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        for _ in 0..2 {
+            assert!(!board.is_threefold_repetition());
+            board.moves_piece("g1", "f3", None)?;
+            board.moves_piece("b8", "c6", None)?;
+            board.moves_piece("f3", "g1", None)?;
+            board.moves_piece("c6", "b8", None)?;
+        }
+        assert!(board.is_threefold_repetition());
+        assert_eq!(board.draw_reason(Color::White)?, Some(DrawReason::ThreefoldRepetition));
+        board.undo_moves()?;
+        assert!(!board.is_threefold_repetition());
+        Ok(())
+    }
+
+    #[test]
+    fn board_repetition_castling_rights() -> Result<(), Error> {
+        // the rooks return to their cells but the castling rights are lost
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")?;
+        board.moves_piece("h1", "g1", None)?;
+        board.moves_piece("h8", "g8", None)?;
+        board.moves_piece("g1", "h1", None)?;
+        board.moves_piece("g8", "h8", None)?;
+        for _ in 0..2 {
+            assert!(!board.is_threefold_repetition());
+            board.moves_piece("h1", "g1", None)?;
+            board.moves_piece("h8", "g8", None)?;
+            board.moves_piece("g1", "h1", None)?;
+            board.moves_piece("g8", "h8", None)?;
+        }
+        assert!(board.is_threefold_repetition());
+        Ok(())
+    }
+
+    #[test]
+    fn board_insufficient_material() -> Result<(), Error> {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1",
+            "4k3/8/8/8/8/4B3/8/2B1K3 b - - 0 1",
+        ] {
+            assert!(Board::from_fen(fen)?.is_insufficient_material(), "{}", fen);
+        }
+        for fen in [
+            crate::notation::fen::START_FEN,
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+            "1b2k3/8/8/8/8/8/8/3BK3 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            assert!(!Board::from_fen(fen)?.is_insufficient_material(), "{}", fen);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn board_moves_piece() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("a2", "a4", None)?;
+        assert_eq!(board.get("a4")?.unwrap().level, Level::Pawn);
+        assert_eq!(board.get("a4")?.unwrap().color, Color::White);
+        assert!(board.get("a2")?.is_none());
+        assert_eq!(board.history.len(), 1);
+        assert_eq!(board.history[0].from, "a2");
+        assert_eq!(board.history[0].to, "a4");
+        assert!(board.history[0].captured.is_none());
+        assert!(board.history[0].has_moved.unwrap());
+
+        // the pinned knight can't leave its king in check
+        let mut board = Board::from_fen("4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1")?;
+        assert!(matches!(board.moves_piece("d2", "f3", None), Err(IllegalMoves(_))));
+        assert!(board.history.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn board_side_to_move() -> Result<(), Error> {
+        let mut board = Board::new();
+        assert_eq!(board.side_to_move(), Color::White);
+        board.moves_piece("e2", "e4", None)?;
+        assert_eq!(board.side_to_move(), Color::Black);
+        board.moves_piece("e7", "e5", None)?;
+        assert_eq!(board.side_to_move(), Color::White);
+        board.undo_moves()?;
+        assert_eq!(board.side_to_move(), Color::Black);
+        Ok(())
+    }
+
+    #[test]
+    fn board_move_clocks() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("g1", "f3", None)?;
+        assert_eq!((board.halfmove_clock, board.fullmove_number), (1, 1));
+        board.moves_piece("g8", "f6", None)?;
+        assert_eq!((board.halfmove_clock, board.fullmove_number), (2, 2));
+        board.moves_piece("e2", "e4", None)?;
+        assert_eq!((board.halfmove_clock, board.fullmove_number), (0, 2));
+        board.undo_moves()?;
+        board.undo_moves()?;
+        assert_eq!((board.halfmove_clock, board.fullmove_number), (1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn board_undo_moves() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("a2", "a4", None)?;
+        board.undo_moves()?;
+        let piece = board.get("a2")?.unwrap();
+        assert_eq!(piece.level, Level::Pawn);
+        assert_eq!(piece.color, Color::White);
+        assert_eq!(piece.moved, Some(false));
+        assert!(board.get("a4")?.is_none());
+        assert_eq!(board.history.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn board_en_passant_target() -> Result<(), Error> {
+        let mut board = Board::new();
+        assert_eq!(board.get_en_passant_target()?, None);
+        board.moves_piece("e2", "e4", None)?;
+        assert_eq!(board.get_en_passant_target()?, Some("e3".to_owned()));
+        board.moves_piece("d7", "d5", None)?;
+        assert_eq!(board.get_en_passant_target()?, Some("d6".to_owned()));
+        board.moves_piece("g1", "f3", None)?;
+        assert_eq!(board.get_en_passant_target()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_moves_en_passant() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("a7", "a6", None)?;
+        board.moves_piece("e4", "e5", None)?;
+        board.moves_piece("d7", "d5", None)?;
+        assert_eq!(board.get_possible_moves("e5")?, ["d6", "e6"]);
+
+        // the right to capture en passant expires after one move
+        board.moves_piece("a2", "a3", None)?;
+        board.moves_piece("a6", "a5", None)?;
+        assert_eq!(board.get_possible_moves("e5")?, ["e6"]);
+        Ok(())
+    }
+
+    #[test]
+    fn board_moves_piece_en_passant() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("a7", "a6", None)?;
+        board.moves_piece("e4", "e5", None)?;
+        board.moves_piece("d7", "d5", None)?;
+        board.moves_piece("e5", "d6", None)?;
+        assert_eq!(board.get("d6")?.unwrap().level, Level::Pawn);
+        assert_eq!(board.get("d6")?.unwrap().color, Color::White);
+        assert!(board.get("d5")?.is_none());
+        assert!(board.get("e5")?.is_none());
+
+        let his = board.history.last().unwrap();
+        assert_eq!(his.captured.as_ref().unwrap().level, Level::Pawn);
+        assert_eq!(his.captured.as_ref().unwrap().color, Color::Black);
+        assert_eq!(his.captured_at, Some("d5".to_owned()));
+        assert_eq!(board.get_captured(Color::Black)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn board_undo_moves_en_passant() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("a7", "a6", None)?;
+        board.moves_piece("e4", "e5", None)?;
+        board.moves_piece("d7", "d5", None)?;
+        board.moves_piece("e5", "d6", None)?;
+        board.undo_moves()?;
+        assert_eq!(board.get("e5")?.unwrap().color, Color::White);
+        assert_eq!(board.get("d5")?.unwrap().level, Level::Pawn);
+        assert_eq!(board.get("d5")?.unwrap().color, Color::Black);
+        assert!(board.get("d6")?.is_none());
+        assert_eq!(board.get_en_passant_target()?, Some("d6".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn board_possible_attack_by_color() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        let paz = board.get_possible_attack_by_color(Color::White)?;
+        assert_eq!(*paz.get("c2").unwrap(), ["b3", "d3"]);
+        assert_eq!(*paz.get("d1").unwrap(), ["e2", "f3", "g4", "h5"]);
+        assert_eq!(*paz.get("e1").unwrap(), ["e2"]);
+        assert_eq!(*paz.get("f1").unwrap(), ["a6", "b5", "c4", "d3", "e2"]);
+        assert_eq!(*paz.get("g1").unwrap(), ["e2", "f3", "h3"]);
+        assert_eq!(paz.get("h1"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn board_get_king_position() -> Result<(), Error> {
+        let board = Board::new();
+        assert_eq!(board.get_king_position(Color::White)?, "e1");
+        assert_eq!(board.get_king_position(Color::Black)?, "e8");
+        Ok(())
+    }
+
+    #[test]
+    fn board_is_king_checked() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("f7", "f6", None)?;
+        board.moves_piece("d1", "h5", None)?;
+        assert!(board.is_king_checked(Color::Black)?);
+        assert!(!board.is_king_checked(Color::White)?);
+        Ok(())
+    }
+
+    #[test]
+    fn board_is_checkmate() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        board.moves_piece("d1", "f3", None)?;
+        board.moves_piece("b8", "c6", None)?;
+        board.moves_piece("f1", "c4", None)?;
+        board.moves_piece("f8", "c5", None)?;
+        assert!(!board.is_checkmate(Color::Black)?);
+        board.moves_piece("f3", "f7", None)?;
+        assert!(board.is_checkmate(Color::Black)?);
+        Ok(())
+    }
+
+    #[test]
+    fn board_castling() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        board.moves_piece("g1", "f3", None)?;
+        board.moves_piece("b8", "c6", None)?;
+        board.moves_piece("f1", "c4", None)?;
+        board.moves_piece("f8", "c5", None)?;
+        board.castling("e1", "h1")?;
+        assert!(board.get("e1")?.is_none());
+        assert_eq!(board.get("f1")?.unwrap().level, Level::Rook);
+        assert_eq!(board.get("g1")?.unwrap().level, Level::King);
+        assert!(board.get("h1")?.is_none());
+        assert!(matches!(board.castling("e8", "h8"), Err(CastlingError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn board_undo_castling() -> Result<(), Error> {
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1")?;
+        let fen = board.to_fen();
+        board.moves_piece("e8", "c8", None)?;
+        assert_eq!(board.get("c8")?.unwrap().level, Level::King);
+        assert_eq!(board.get("d8")?.unwrap().moved, Some(true));
+        assert!(board.get("a8")?.is_none());
+        let his = board.history.last().unwrap().castling.clone().unwrap();
+        assert_eq!((his.rook_from.as_str(), his.rook_to.as_str()), ("a8", "d8"));
+
+        board.undo_moves()?;
+        assert_eq!(board.get("e8")?.unwrap().moved, Some(false));
+        assert_eq!(board.get("a8")?.unwrap().moved, Some(false));
+        assert!(board.get("c8")?.is_none());
+        assert!(board.get("d8")?.is_none());
+        assert_eq!(board.to_fen(), fen);
+        assert!(board.legal_moves("e8")?.contains(&"c8".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn board_position_at() -> Result<(), Error> {
+        let mut board = Board::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1")?;
+        let start = board.clone();
+        board.moves_piece("e5", "d6", None)?;
+        board.moves_piece("e8", "g8", None)?;
+        board.moves_piece("b7", "a8", Some(Level::Knight))?;
+
+        assert_eq!(board.position_at(0)?, start);
+        assert_eq!(board.position_at(2)?.get("f8")?.unwrap().level, Level::Rook);
+        assert!(board.position_at(2)?.get("d5")?.is_none());
+        assert_eq!(board.position_at(3)?, board);
+        Ok(())
+    }
+
+    #[test]
+    fn board_promote() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("h2", "h4", None)?;
+        board.moves_piece("h4", "h5", None)?;
+        board.moves_piece("h5", "h6", None)?;
+        board.moves_piece("h6", "g7", None)?;
+        assert!(matches!(board.moves_piece("g7", "f8", None), Err(PromotionError(_))));
+        assert!(matches!(board.moves_piece("g7", "f8", Some(Level::King)), Err(PromotionError(_))));
+        assert!(matches!(board.moves_piece("a7", "a6", Some(Level::Queen)), Err(PromotionError(_))));
+        assert_eq!(board.get("g7")?.unwrap().level, Level::Pawn);
+
+        board.moves_piece("g7", "f8", Some(Level::Queen))?;
+        assert_eq!(board.get("f8")?.unwrap().level, Level::Queen);
+        assert_eq!(board.get("f8")?.unwrap().color, Color::White);
+        assert_eq!(board.history.last().unwrap().promotion, Some(Level::Queen));
+        assert_eq!(board.side_to_move(), Color::Black);
+        Ok(())
+    }
+
+    #[test]
+    fn board_promote_checkmate() -> Result<(), Error> {
+        let mut board = Board::from_fen("k7/7P/1K6/8/8/8/8/8 w - - 0 1")?;
+        board.moves_piece("h7", "h8", Some(Level::Queen))?;
+        assert!(board.is_checkmate(Color::Black)?);
+
+        board.undo_moves()?;
+        board.moves_piece("h7", "h8", Some(Level::Knight))?;
+        assert!(!board.is_checkmate(Color::Black)?);
+        assert_eq!(board.draw_reason(Color::Black)?, Some(DrawReason::InsufficientMaterial));
+        Ok(())
+    }
+
+    #[test]
+    fn board_undo_promote() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("h2", "h4", None)?;
+        board.moves_piece("h4", "h5", None)?;
+        board.moves_piece("h5", "h6", None)?;
+        board.moves_piece("h6", "g7", None)?;
+        board.moves_piece("g7", "f8", Some(Level::Knight))?;
+        board.undo_moves()?;
+        assert_eq!(board.get("g7")?.unwrap().level, Level::Pawn);
+        assert_eq!(board.get("g7")?.unwrap().moved, Some(true));
+        assert_eq!(board.get("f8")?.unwrap().level, Level::Bishop);
+        assert_eq!(board.get("f8")?.unwrap().color, Color::Black);
+        Ok(())
+    }
+
+}
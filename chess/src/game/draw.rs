@@ -0,0 +1,38 @@
+//! Draw Reasons
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Reasons for a game to be drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DrawReason {
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+}
+
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DrawReason::Stalemate => write!(f, "stalemate"),
+            DrawReason::FiftyMoveRule => write!(f, "fifty-move rule"),
+            DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn print_draw_reason() {
+        assert_eq!(DrawReason::Stalemate.to_string(), "stalemate");
+        assert_eq!(DrawReason::FiftyMoveRule.to_string(), "fifty-move rule");
+        assert_eq!(DrawReason::ThreefoldRepetition.to_string(), "threefold repetition");
+        assert_eq!(DrawReason::InsufficientMaterial.to_string(), "insufficient material");
+    }
+}
@@ -0,0 +1,71 @@
+//! Moves History
+
+use std::fmt::Display;
+use super::{Piece, Level};
+use serde::{Deserialize, Serialize};
+
+/// Moves History
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct History {
+    pub from: String,
+    pub to: String,
+    pub captured: Option<Piece>,
+    pub has_moved: Option<bool>,
+    /// Cell of the captured piece when it isn't `to` (en passant)
+    #[serde(default)]
+    pub captured_at: Option<String>,
+    /// Halfmove clock before the move was made
+    #[serde(default)]
+    pub halfmove_clock: u32,
+    /// Level the pawn was promoted to
+    #[serde(default)]
+    pub promotion: Option<Level>,
+    /// Hash of the position after the move, used to detect repetitions
+    #[serde(default)]
+    pub position: u64,
+    /// Rook relocation when the move is a castling
+    #[serde(default)]
+    pub castling: Option<Castling>
+}
+
+/// Rook relocation of a castling, the king's move is the `History` itself
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Castling {
+    pub rook_from: String,
+    pub rook_to: String
+}
+
+impl Display for History {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.captured {
+            Some(ref piece) => write!(f, "moves {} to {}, {}  captured", self.from, self.to, piece),
+            None => write!(f, "moves {} to {}", self.from, self.to)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::prelude::Color;
+    use super::*;
+
+    #[test]
+    fn print_history() {
+        let mut his = History {
+            from: "a2".to_string(),
+            to: "a4".to_string(),
+            captured: None,
+            has_moved: Some(true),
+            captured_at: None,
+            halfmove_clock: 0,
+            promotion: None,
+            position: 0,
+            castling: None
+        };
+        assert_eq!(his.to_string(), "moves a2 to a4");
+
+        let piece = Piece::new(Level::Pawn, Color::Black);
+        his.captured = Some(piece);
+        assert_eq!(his.to_string(), "moves a2 to a4, ♟  captured");
+    }
+}
\ No newline at end of file
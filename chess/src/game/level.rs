@@ -18,6 +18,33 @@ pub enum Level {
     Knight,
 }
 
+impl Level {
+    /// Get the letter used for the level in chess notations, e.g. `'N'` for a knight
+    pub fn as_char(&self) -> char {
+        match self {
+            Level::Pawn => 'P',
+            Level::Rook => 'R',
+            Level::Knight => 'N',
+            Level::Bishop => 'B',
+            Level::Queen => 'Q',
+            Level::King => 'K'
+        }
+    }
+
+    /// Parse the level from its letter, ignoring case
+    pub fn from_char(c: char) -> Option<Level> {
+        match c.to_ascii_uppercase() {
+            'P' => Some(Level::Pawn),
+            'R' => Some(Level::Rook),
+            'N' => Some(Level::Knight),
+            'B' => Some(Level::Bishop),
+            'Q' => Some(Level::Queen),
+            'K' => Some(Level::King),
+            _ => None
+        }
+    }
+}
+
 impl Display for Level {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -44,4 +71,12 @@ mod test {
         assert_eq!(Level::Queen.to_string(), "Queen");
         assert_eq!(Level::King.to_string(), "King");
     }
+
+    #[test]
+    fn level_char() {
+        assert_eq!(Level::Knight.as_char(), 'N');
+        assert_eq!(Level::from_char('n'), Some(Level::Knight));
+        assert_eq!(Level::from_char('Q'), Some(Level::Queen));
+        assert_eq!(Level::from_char('x'), None);
+    }
 }
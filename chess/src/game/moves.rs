@@ -0,0 +1,147 @@
+//! Compact Move
+//!
+//! A move fits in 16 bits: the source square, the destination square and
+//! four bits of flags telling captures, castlings, double pawn pushes,
+//! en passant captures and promotions apart.
+
+use std::fmt::Display;
+use super::{Level, invert_square};
+
+/// Move of a piece between two squares, from `0` for a1 to `63` for h8
+///
+/// ### Examples
+///
+/// ```
+/// # use chess::game::prelude::*;
+/// use chess::game::convert_square;
+/// let mv = Move::new(convert_square("e2")?, convert_square("e4")?, Move::DOUBLE_PUSH);
+///
+/// assert_eq!(mv.to_string(), "e2e4");
+/// assert!(!mv.is_capture());
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    pub const QUIET: u8 = 0;
+    pub const DOUBLE_PUSH: u8 = 1;
+    pub const KING_CASTLE: u8 = 2;
+    pub const QUEEN_CASTLE: u8 = 3;
+    pub const CAPTURE: u8 = 4;
+    pub const EN_PASSANT: u8 = 5;
+    /// Promotion to a knight, `+ 1` for a bishop, `+ 2` for a rook and `+ 3`
+    /// for a queen, with the `CAPTURE` bit when the pawn captures
+    pub const PROMOTION: u8 = 8;
+
+    /// Create new `Move` from the squares and the flags
+    pub const fn new(from: u8, to: u8, flags: u8) -> Move {
+        Move((from as u16 & 0x3f) | (to as u16 & 0x3f) << 6 | (flags as u16 & 0xf) << 12)
+    }
+
+    /// Create new promotion `Move` of a pawn to the `level`
+    pub fn promotion_to(from: u8, to: u8, level: Level, capture: bool) -> Move {
+        let kind = match level {
+            Level::Bishop => 1,
+            Level::Rook => 2,
+            Level::Queen => 3,
+            _ => 0
+        };
+        let capture = if capture { Move::CAPTURE } else { 0 };
+        Move::new(from, to, Move::PROMOTION | capture | kind)
+    }
+
+    /// Get the source square
+    pub fn from(&self) -> u8 {
+        (self.0 & 0x3f) as u8
+    }
+
+    /// Get the destination square, the king's destination for a castling
+    pub fn to(&self) -> u8 {
+        (self.0 >> 6 & 0x3f) as u8
+    }
+
+    /// Get the flags
+    pub fn flags(&self) -> u8 {
+        (self.0 >> 12) as u8
+    }
+
+    /// Check if the move captures a piece, en passant included
+    pub fn is_capture(&self) -> bool {
+        self.flags() & Move::CAPTURE != 0
+    }
+
+    /// Check if the move is a pawn's two-square advance
+    pub fn is_double_push(&self) -> bool {
+        self.flags() == Move::DOUBLE_PUSH
+    }
+
+    /// Check if the move captures a pawn en passant
+    pub fn is_en_passant(&self) -> bool {
+        self.flags() == Move::EN_PASSANT
+    }
+
+    /// Check if the move is a castling
+    pub fn is_castling(&self) -> bool {
+        matches!(self.flags(), Move::KING_CASTLE | Move::QUEEN_CASTLE)
+    }
+
+    /// Get the level the pawn is promoted to
+    pub fn promotion(&self) -> Option<Level> {
+        if self.flags() & Move::PROMOTION == 0 {
+            return None;
+        }
+        match self.flags() & 3 {
+            0 => Some(Level::Knight),
+            1 => Some(Level::Bishop),
+            2 => Some(Level::Rook),
+            _ => Some(Level::Queen)
+        }
+    }
+
+    /// Get the cells of the move as the `Board` API writes it
+    pub fn cells(&self) -> (String, String) {
+        (cell(self.from()), cell(self.to()))
+    }
+}
+
+/// Square in chess notation, the squares of a `Move` are always on the board
+fn cell(square: u8) -> String {
+    invert_square(square).unwrap_or_default()
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", cell(self.from()), cell(self.to()))?;
+        if let Some(level) = self.promotion() {
+            write!(f, "{}", level.as_char().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn move_encoding() {
+        let mv = Move::new(12, 28, Move::DOUBLE_PUSH);
+        assert_eq!((mv.from(), mv.to()), (12, 28));
+        assert!(mv.is_double_push());
+        assert_eq!(mv.promotion(), None);
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+
+        let mv = Move::promotion_to(49, 56, Level::Queen, true);
+        assert!(mv.is_capture());
+        assert_eq!(mv.promotion(), Some(Level::Queen));
+        assert_eq!(mv.to_string(), "b7a8q");
+    }
+
+    #[test]
+    fn move_cells() {
+        assert_eq!(Move::new(4, 6, Move::KING_CASTLE).cells(), ("e1".to_owned(), "g1".to_owned()));
+        assert_eq!(Move::new(60, 58, Move::QUEEN_CASTLE).cells(), ("e8".to_owned(), "c8".to_owned()));
+        assert_eq!(Move::new(6, 21, Move::QUIET).cells(), ("g1".to_owned(), "f3".to_owned()));
+    }
+}
@@ -0,0 +1,158 @@
+//! Performance Test (perft)
+//!
+//! Counts the leaf nodes of the tree of legal moves to a given depth, to
+//! compare the move generation with the published results of standard
+//! positions. `divide` splits the count by the first move to find which
+//! move differs.
+
+use super::{Board, Piece, Position};
+use crate::error::Error;
+
+impl Position {
+    /// Count the positions reached after `depth` legal moves
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut position = self.clone();
+        moves.into_iter()
+            .map(|mv| {
+                let undo = position.make(mv);
+                let nodes = position.perft(depth - 1);
+                position.unmake(mv, undo);
+                nodes
+            })
+            .sum()
+    }
+}
+
+impl Board<Piece> {
+    /// Count the positions reached after `depth` legal moves
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::new();
+    /// assert_eq!(board.perft(1)?, 20);
+    /// assert_eq!(board.perft(2)?, 400);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn perft(&self, depth: u32) -> Result<u64, Error> {
+        Ok(Position::from_board(self)?.perft(depth))
+    }
+
+    /// Count the positions reached after `depth` legal moves for each
+    /// first move, written from and to like `e2e4` or `e7e8q`
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::new();
+    /// let divide = board.divide(2)?;
+    ///
+    /// assert_eq!(divide.len(), 20);
+    /// assert!(divide.contains(&("e2e4".to_owned(), 20)));
+    /// for (mv, nodes) in divide.iter() {
+    ///     println!("{mv}: {nodes}");
+    /// }
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn divide(&self, depth: u32) -> Result<Vec<(String, u64)>, Error> {
+        let mut position = Position::from_board(self)?;
+        let mut divide = position.legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let undo = position.make(mv);
+                let nodes = position.perft(depth.saturating_sub(1));
+                position.unmake(mv, undo);
+                (mv.to_string(), nodes)
+            })
+            .collect::<Vec<(String, u64)>>();
+
+        divide.sort();
+        Ok(divide)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::prelude::*;
+
+    /// Standard perft positions and their node counts by depth
+    const POSITIONS: [(&str, &[u64]); 6] = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281]),
+        // Kiwipete, castling, en passant and promotions all around
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]),
+        // en passant discovering a check along the rank
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238]),
+        // promotions with captures, castling rights of one side
+        ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+        ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]),
+        ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890])
+    ];
+
+    /// Count the positions by playing the moves through the string API of `Board`
+    fn perft_board(board: &Board<Piece>, depth: u32) -> Result<u64, Error> {
+        let color = board.side_to_move();
+        let mut nodes = 0;
+        for (from, moves) in board.legal_moves_by_color(color)? {
+            for to in moves {
+                let promotions = match board.get(&from)? {
+                    Some(piece) if piece.level == Level::Pawn && (to.ends_with('8') || to.ends_with('1')) =>
+                        vec![Some(Level::Queen), Some(Level::Rook), Some(Level::Bishop), Some(Level::Knight)],
+                    _ => vec![None]
+                };
+                for promotion in promotions {
+                    if depth <= 1 {
+                        nodes += 1;
+                        continue;
+                    }
+                    let mut next = board.clone();
+                    next.moves_piece(&from, &to, promotion)?;
+                    nodes += perft_board(&next, depth - 1)?;
+                }
+            }
+        }
+        Ok(nodes)
+    }
+
+    #[test]
+    fn perft_positions() -> Result<(), Error> {
+        for (fen, counts) in POSITIONS {
+            let board = Board::from_fen(fen)?;
+            for (depth, count) in counts.iter().enumerate() {
+                assert_eq!(board.perft(depth as u32 + 1)?, *count, "{} at depth {}", fen, depth + 1);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn perft_board_api() -> Result<(), Error> {
+        // the moves played on the board lead to the same positions
+        for (fen, counts) in POSITIONS {
+            let board = Board::from_fen(fen)?;
+            assert_eq!(perft_board(&board, 2)?, counts[1], "{}", fen);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn perft_divide() -> Result<(), Error> {
+        let board = Board::from_fen(POSITIONS[1].0)?;
+        let divide = board.divide(2)?;
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_mv, nodes)| nodes).sum::<u64>(), 2039);
+        assert!(divide.contains(&("e1g1".to_owned(), 43)));
+        assert!(divide.contains(&("d5e6".to_owned(), 46)));
+        Ok(())
+    }
+}
@@ -48,6 +48,20 @@ impl Piece {
 
         Self { level, color, icon, mv_unit, moved }
     }
+
+    /// Get the FEN letter of the piece, uppercase for White and lowercase for Black
+    pub fn as_fen_char(&self) -> char {
+        match self.color {
+            Color::White => self.level.as_char(),
+            Color::Black => self.level.as_char().to_ascii_lowercase()
+        }
+    }
+
+    /// Create new Piece instance from its FEN letter
+    pub fn from_fen_char(c: char) -> Option<Self> {
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        Level::from_char(c).map(|level| Self::new(level, color))
+    }
 }
 
 impl Display for Piece {
@@ -91,6 +105,14 @@ mod test {
         assert_eq!(p.mv_unit, None);
     }
 
+    #[test]
+    fn piece_fen_char() {
+        assert_eq!(Piece::new(Level::Knight, Color::White).as_fen_char(), 'N');
+        assert_eq!(Piece::new(Level::Queen, Color::Black).as_fen_char(), 'q');
+        assert_eq!(Piece::from_fen_char('k'), Some(Piece::new(Level::King, Color::Black)));
+        assert_eq!(Piece::from_fen_char('x'), None);
+    }
+
     #[test]
     fn piece_new_bishop() {
         let p = Piece::new(Level::Bishop, Color::White);
@@ -0,0 +1,562 @@
+//! Bitboard Position
+//!
+//! Compact representation of a board for the move generation: a bitboard
+//! of the squares of each color and level, where bit `0` is a1 and bit `63`
+//! is h8. Moves are made and unmade in place, so searching a position
+//! doesn't clone it or allocate strings.
+
+use super::{Board, Color, Level, Move, Piece, convert_square};
+use crate::error::Error;
+
+/// Levels in the order of their bitboards
+const LEVELS: [Level; 6] = [Level::Pawn, Level::Knight, Level::Bishop, Level::Rook, Level::Queen, Level::King];
+
+pub const WHITE_KING_SIDE: u8 = 1;
+pub const WHITE_QUEEN_SIDE: u8 = 2;
+pub const BLACK_KING_SIDE: u8 = 4;
+pub const BLACK_QUEEN_SIDE: u8 = 8;
+
+/// Castling rights lost when a piece moves from or to the square:
+/// (square, rights), the kings and the rooks' corners
+const CASTLING_SQUARES: [(u8, u8); 6] = [
+    (4, WHITE_KING_SIDE | WHITE_QUEEN_SIDE),
+    (0, WHITE_QUEEN_SIDE),
+    (7, WHITE_KING_SIDE),
+    (60, BLACK_KING_SIDE | BLACK_QUEEN_SIDE),
+    (56, BLACK_QUEEN_SIDE),
+    (63, BLACK_KING_SIDE)
+];
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_OFFSETS: [(i8, i8); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+const KNIGHT_ATTACKS: [u64; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [u64; 64] = leaper_attacks(&KING_OFFSETS);
+/// Squares attacked by a pawn, by color
+const PAWN_ATTACKS: [[u64; 64]; 2] = [leaper_attacks(&[(1, 1), (1, -1)]), leaper_attacks(&[(-1, 1), (-1, -1)])];
+
+/// Squares reached from every square by the (row, col) offsets
+const fn leaper_attacks(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let (row, col) = ((square / 8) as i8, (square % 8) as i8);
+        let mut i = 0;
+        while i < offsets.len() {
+            let (r, c) = (row + offsets[i].0, col + offsets[i].1);
+            if r >= 0 && r < 8 && c >= 0 && c < 8 {
+                attacks[square] |= 1 << (r * 8 + c);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    attacks
+}
+
+/// Squares reached by a sliding piece until the first occupied square
+fn sliding_attacks(square: u8, occupied: u64, directions: &[(i8, i8)]) -> u64 {
+    let mut attacks = 0;
+    for (dr, dc) in directions {
+        let (mut row, mut col) = ((square / 8) as i8, (square % 8) as i8);
+        loop {
+            row += dr;
+            col += dc;
+            if !(0..8).contains(&row) || !(0..8).contains(&col) {
+                break;
+            }
+            let bit = 1 << (row * 8 + col);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+        }
+    }
+    attacks
+}
+
+/// Iterate the squares of a bitboard
+pub(crate) fn squares(mut bitboard: u64) -> impl Iterator<Item = u8> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let square = bitboard.trailing_zeros() as u8;
+        bitboard &= bitboard - 1;
+        Some(square)
+    })
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1
+    }
+}
+
+fn level_index(level: Level) -> usize {
+    match level {
+        Level::Pawn => 0,
+        Level::Knight => 1,
+        Level::Bishop => 2,
+        Level::Rook => 3,
+        Level::Queen => 4,
+        Level::King => 5
+    }
+}
+
+fn opposite(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Black => Color::White
+    }
+}
+
+/// State of a position before a move, to unmake it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Undo {
+    captured: Option<Level>,
+    castling: u8,
+    en_passant: Option<u8>,
+    halfmove_clock: u32
+}
+
+/// Chess position on bitboards
+///
+/// ### Examples
+///
+/// ```
+/// # use chess::game::prelude::*;
+/// let mut position = Position::from_board(&Board::new())?;
+/// let moves = position.legal_moves();
+/// assert_eq!(moves.len(), 20);
+///
+/// let undo = position.make(moves[0]);
+/// assert_eq!(position.side_to_move, Color::Black);
+/// position.unmake(moves[0], undo);
+/// assert_eq!(position, Position::from_board(&Board::new())?);
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// Squares of the pieces by color and level
+    pieces: [[u64; 6]; 2],
+    /// Squares of the pieces by color
+    colors: [u64; 2],
+    pub side_to_move: Color,
+    /// Castling rights, see `WHITE_KING_SIDE` and the other flags
+    pub castling: u8,
+    /// Square skipped by the last two-square pawn advance
+    pub en_passant: Option<u8>,
+    /// Moves since the last capture or pawn advance
+    pub halfmove_clock: u32
+}
+
+impl Position {
+    /// Create new `Position` of the board
+    ///
+    /// A king and a rook which haven't moved from their starting squares
+    /// keep the castling right.
+    pub fn from_board(board: &Board<Piece>) -> Result<Position, Error> {
+        let mut position = Position {
+            pieces: [[0; 6]; 2],
+            colors: [0; 2],
+            side_to_move: board.side_to_move(),
+            castling: 0,
+            en_passant: board.get_en_passant_target()?
+                .map(|cell| convert_square(&cell))
+                .transpose()?,
+            halfmove_clock: board.halfmove_clock
+        };
+
+        for (row, cells) in board.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if let Some(piece) = cell {
+                    position.put(piece.color, piece.level, (row * 8 + col) as u8);
+                }
+            }
+        }
+
+        let unmoved = |cell: &str, level: Level, color: Color| matches!(
+            board.get(cell),
+            Ok(Some(piece)) if piece.level == level && piece.color == color && piece.moved == Some(false)
+        );
+        for (right, color, king, rook) in [
+            (WHITE_KING_SIDE, Color::White, "e1", "h1"),
+            (WHITE_QUEEN_SIDE, Color::White, "e1", "a1"),
+            (BLACK_KING_SIDE, Color::Black, "e8", "h8"),
+            (BLACK_QUEEN_SIDE, Color::Black, "e8", "a8")
+        ] {
+            if unmoved(king, Level::King, color) && unmoved(rook, Level::Rook, color) {
+                position.castling |= right;
+            }
+        }
+
+        Ok(position)
+    }
+
+    fn put(&mut self, color: Color, level: Level, square: u8) {
+        self.pieces[color_index(color)][level_index(level)] |= 1 << square;
+        self.colors[color_index(color)] |= 1 << square;
+    }
+
+    fn remove(&mut self, color: Color, level: Level, square: u8) {
+        self.pieces[color_index(color)][level_index(level)] &= !(1 << square);
+        self.colors[color_index(color)] &= !(1 << square);
+    }
+
+    /// Get the bitboard of the pieces of the color and level
+    pub fn pieces(&self, color: Color, level: Level) -> u64 {
+        self.pieces[color_index(color)][level_index(level)]
+    }
+
+    /// Get the bitboard of all the pieces
+    pub fn occupied(&self) -> u64 {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// Get the bitboard of the pieces of the color
+    pub fn occupied_by(&self, color: Color) -> u64 {
+        self.colors[color_index(color)]
+    }
+
+    /// Get the color and level of the piece on the square
+    pub fn piece_at(&self, square: u8) -> Option<(Color, Level)> {
+        let bit = 1 << square;
+        let color = match (self.colors[0] & bit != 0, self.colors[1] & bit != 0) {
+            (true, _) => Color::White,
+            (_, true) => Color::Black,
+            _ => return None
+        };
+        LEVELS.iter()
+            .find(|level| self.pieces(color, **level) & bit != 0)
+            .map(|level| (color, *level))
+    }
+
+    /// Get the square of the king of the color
+    pub fn king(&self, color: Color) -> Option<u8> {
+        squares(self.pieces(color, Level::King)).next()
+    }
+
+    /// Check if the square is attacked by a piece of the color
+    pub fn is_attacked(&self, square: u8, by: Color) -> bool {
+        let square = square as usize;
+        let occupied = self.occupied();
+        let queens = self.pieces(by, Level::Queen);
+
+        PAWN_ATTACKS[color_index(opposite(by))][square] & self.pieces(by, Level::Pawn) != 0
+        || KNIGHT_ATTACKS[square] & self.pieces(by, Level::Knight) != 0
+        || KING_ATTACKS[square] & self.pieces(by, Level::King) != 0
+        || sliding_attacks(square as u8, occupied, &ROOK_DIRECTIONS) & (self.pieces(by, Level::Rook) | queens) != 0
+        || sliding_attacks(square as u8, occupied, &BISHOP_DIRECTIONS) & (self.pieces(by, Level::Bishop) | queens) != 0
+    }
+
+    /// Get the squares attacked by the piece on the square, whatever
+    /// stands on them, none for an empty square
+    pub fn attacks(&self, square: u8) -> u64 {
+        let occupied = self.occupied();
+        let (color, level) = match self.piece_at(square) {
+            Some(piece) => piece,
+            None => return 0
+        };
+        match level {
+            Level::Pawn => PAWN_ATTACKS[color_index(color)][square as usize],
+            Level::Knight => KNIGHT_ATTACKS[square as usize],
+            Level::King => KING_ATTACKS[square as usize],
+            Level::Bishop => sliding_attacks(square, occupied, &BISHOP_DIRECTIONS),
+            Level::Rook => sliding_attacks(square, occupied, &ROOK_DIRECTIONS),
+            Level::Queen => sliding_attacks(square, occupied, &ROOK_DIRECTIONS)
+                | sliding_attacks(square, occupied, &BISHOP_DIRECTIONS)
+        }
+    }
+
+    /// Check if the king of the color is attacked, a missing king never is
+    pub fn is_check(&self, color: Color) -> bool {
+        self.king(color).is_some_and(|king| self.is_attacked(king, opposite(color)))
+    }
+
+    /// Get the moves of the side to move, including those leaving its king in check
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let us = self.side_to_move;
+        let own = self.colors[color_index(us)];
+        let enemy = self.colors[color_index(opposite(us))];
+        let occupied = own | enemy;
+        let mut moves = Vec::with_capacity(48);
+
+        let add = |moves: &mut Vec<Move>, from: u8, targets: u64| {
+            for to in squares(targets) {
+                let flags = if enemy & 1 << to != 0 { Move::CAPTURE } else { Move::QUIET };
+                moves.push(Move::new(from, to, flags));
+            }
+        };
+
+        // pawns advance when the squares ahead are empty and capture diagonally
+        let (forward, start_row, last_row): (i8, u8, u8) = match us {
+            Color::White => (8, 1, 7),
+            Color::Black => (-8, 6, 0)
+        };
+        for from in squares(self.pieces(us, Level::Pawn)) {
+            let mut targets = vec![];
+            let one = (from as i8 + forward) as u8;
+            if occupied & 1 << one == 0 {
+                targets.push((one, false));
+                let two = (one as i8 + forward) as u8;
+                if from / 8 == start_row && occupied & 1 << two == 0 {
+                    moves.push(Move::new(from, two, Move::DOUBLE_PUSH));
+                }
+            }
+            let attacks = PAWN_ATTACKS[color_index(us)][from as usize];
+            for to in squares(attacks & enemy) {
+                targets.push((to, true));
+            }
+            if let Some(target) = self.en_passant.filter(|target| attacks & 1 << target != 0) {
+                moves.push(Move::new(from, target, Move::EN_PASSANT));
+            }
+
+            for (to, capture) in targets {
+                if to / 8 == last_row {
+                    for level in [Level::Queen, Level::Knight, Level::Rook, Level::Bishop] {
+                        moves.push(Move::promotion_to(from, to, level, capture));
+                    }
+                } else {
+                    moves.push(Move::new(from, to, if capture { Move::CAPTURE } else { Move::QUIET }));
+                }
+            }
+        }
+
+        for from in squares(self.pieces(us, Level::Knight)) {
+            add(&mut moves, from, KNIGHT_ATTACKS[from as usize] & !own);
+        }
+        for from in squares(self.pieces(us, Level::Bishop)) {
+            add(&mut moves, from, sliding_attacks(from, occupied, &BISHOP_DIRECTIONS) & !own);
+        }
+        for from in squares(self.pieces(us, Level::Rook)) {
+            add(&mut moves, from, sliding_attacks(from, occupied, &ROOK_DIRECTIONS) & !own);
+        }
+        for from in squares(self.pieces(us, Level::Queen)) {
+            let attacks = sliding_attacks(from, occupied, &ROOK_DIRECTIONS) | sliding_attacks(from, occupied, &BISHOP_DIRECTIONS);
+            add(&mut moves, from, attacks & !own);
+        }
+        for from in squares(self.pieces(us, Level::King)) {
+            add(&mut moves, from, KING_ATTACKS[from as usize] & !own);
+        }
+
+        // castling: the king and the rook haven't moved, the cells between them
+        // are empty, and the king isn't in check nor passes through an attacked cell
+        let (king_side, queen_side, king) = match us {
+            Color::White => (WHITE_KING_SIDE, WHITE_QUEEN_SIDE, 4),
+            Color::Black => (BLACK_KING_SIDE, BLACK_QUEEN_SIDE, 60)
+        };
+        let them = opposite(us);
+        if self.castling & (king_side | queen_side) != 0 && !self.is_attacked(king, them) {
+            let empty = |squares: &[u8]| squares.iter().all(|square| occupied & 1 << square == 0);
+            let safe = |squares: &[u8]| squares.iter().all(|square| !self.is_attacked(*square, them));
+            if self.castling & king_side != 0 && empty(&[king + 1, king + 2]) && safe(&[king + 1, king + 2]) {
+                moves.push(Move::new(king, king + 2, Move::KING_CASTLE));
+            }
+            if self.castling & queen_side != 0 && empty(&[king - 1, king - 2, king - 3]) && safe(&[king - 1, king - 2]) {
+                moves.push(Move::new(king, king - 2, Move::QUEEN_CASTLE));
+            }
+        }
+
+        moves
+    }
+
+    /// Get the legal moves of the side to move
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut position = self.clone();
+        let us = self.side_to_move;
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                let undo = position.make(*mv);
+                let legal = !position.is_check(us);
+                position.unmake(*mv, undo);
+                legal
+            })
+            .collect()
+    }
+
+    /// Check if the side to move has a legal move
+    pub fn has_legal_moves(&self) -> bool {
+        let mut position = self.clone();
+        let us = self.side_to_move;
+        self.pseudo_legal_moves().into_iter().any(|mv| {
+            let undo = position.make(mv);
+            let legal = !position.is_check(us);
+            position.unmake(mv, undo);
+            legal
+        })
+    }
+
+    /// Make a move of the side to move, the move must come from
+    /// [`Position::pseudo_legal_moves`] of this position
+    pub fn make(&mut self, mv: Move) -> Undo {
+        let us = self.side_to_move;
+        let them = opposite(us);
+        let (from, to) = (mv.from(), mv.to());
+        let mut undo = Undo {
+            captured: None,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock
+        };
+        let level = match self.piece_at(from) {
+            Some((_color, level)) => level,
+            None => return undo
+        };
+
+        if mv.is_en_passant() {
+            let square = if us == Color::White { to - 8 } else { to + 8 };
+            self.remove(them, Level::Pawn, square);
+            undo.captured = Some(Level::Pawn);
+        } else if mv.is_capture() {
+            undo.captured = self.piece_at(to).map(|(_color, level)| level);
+            if let Some(captured) = undo.captured {
+                self.remove(them, captured, to);
+            }
+        }
+
+        self.remove(us, level, from);
+        self.put(us, mv.promotion().unwrap_or(level), to);
+
+        match mv.flags() {
+            Move::KING_CASTLE => {
+                self.remove(us, Level::Rook, to + 1);
+                self.put(us, Level::Rook, to - 1);
+            },
+            Move::QUEEN_CASTLE => {
+                self.remove(us, Level::Rook, to - 2);
+                self.put(us, Level::Rook, to + 1);
+            },
+            _ => ()
+        }
+
+        self.en_passant = mv.is_double_push().then_some((from + to) / 2);
+        for (square, rights) in CASTLING_SQUARES {
+            if square == from || square == to {
+                self.castling &= !rights;
+            }
+        }
+        if level == Level::Pawn || undo.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.side_to_move = them;
+        undo
+    }
+
+    /// Unmake the last move made with [`Position::make`]
+    pub fn unmake(&mut self, mv: Move, undo: Undo) {
+        let them = self.side_to_move;
+        let us = opposite(them);
+        let (from, to) = (mv.from(), mv.to());
+        let level = match self.piece_at(to) {
+            Some((_color, level)) => level,
+            None => return
+        };
+
+        match mv.flags() {
+            Move::KING_CASTLE => {
+                self.remove(us, Level::Rook, to - 1);
+                self.put(us, Level::Rook, to + 1);
+            },
+            Move::QUEEN_CASTLE => {
+                self.remove(us, Level::Rook, to + 1);
+                self.put(us, Level::Rook, to - 2);
+            },
+            _ => ()
+        }
+
+        self.remove(us, level, to);
+        self.put(us, if mv.promotion().is_some() { Level::Pawn } else { level }, from);
+
+        if let Some(captured) = undo.captured {
+            let square = match (mv.is_en_passant(), us) {
+                (true, Color::White) => to - 8,
+                (true, Color::Black) => to + 8,
+                (false, _) => to
+            };
+            self.put(them, captured, square);
+        }
+
+        self.side_to_move = us;
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::convert_square;
+    use super::*;
+
+    fn from_fen(fen: &str) -> Result<Position, Error> {
+        Position::from_board(&Board::from_fen(fen)?)
+    }
+
+    #[test]
+    fn position_from_board() -> Result<(), Error> {
+        let position = Position::from_board(&Board::new())?;
+        assert_eq!(position.piece_at(convert_square("e1")?), Some((Color::White, Level::King)));
+        assert_eq!(position.piece_at(convert_square("d8")?), Some((Color::Black, Level::Queen)));
+        assert_eq!(position.piece_at(convert_square("e4")?), None);
+        assert_eq!(position.castling, 15);
+        assert_eq!(position.occupied().count_ones(), 32);
+
+        let position = from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 0 1")?;
+        assert_eq!(position.castling, WHITE_KING_SIDE | BLACK_QUEEN_SIDE);
+        assert_eq!(position.en_passant, Some(convert_square("d6")?));
+        Ok(())
+    }
+
+    #[test]
+    fn position_legal_moves() -> Result<(), Error> {
+        // Kiwipete
+        let kiwipete = from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")?;
+        assert_eq!(kiwipete.legal_moves().len(), 48);
+
+        // the pawn can't jump over the blocking queen
+        let blocked = from_fen("4k3/8/8/8/8/7q/7P/4K3 w - - 0 1")?;
+        assert!(!blocked.legal_moves().iter().any(|mv| mv.to_string() == "h2h4"));
+
+        // checkmated
+        let mated = from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1")?;
+        assert!(mated.is_check(Color::Black));
+        assert!(!mated.has_legal_moves());
+        Ok(())
+    }
+
+    #[test]
+    fn position_make_unmake() -> Result<(), Error> {
+        let start = from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1")?;
+        let mut position = start.clone();
+        for mv in start.legal_moves() {
+            let undo = position.make(mv);
+            position.unmake(mv, undo);
+            assert_eq!(position, start, "{}", mv);
+        }
+
+        // en passant removes the pawn beside
+        let mv = Move::new(convert_square("e5")?, convert_square("d6")?, Move::EN_PASSANT);
+        position.make(mv);
+        assert_eq!(position.piece_at(convert_square("d5")?), None);
+
+        // castling moves the rook and loses the rights
+        let mut position = start.clone();
+        position.make(Move::new(4, 6, Move::KING_CASTLE));
+        assert_eq!(position.piece_at(5), Some((Color::White, Level::Rook)));
+        assert_eq!(position.castling, BLACK_KING_SIDE | BLACK_QUEEN_SIDE);
+
+        // capturing the rook on a8 with a promotion takes the black right
+        let mut position = start.clone();
+        position.make(Move::promotion_to(convert_square("b7")?, 56, Level::Queen, true));
+        assert_eq!(position.piece_at(56), Some((Color::White, Level::Queen)));
+        assert_eq!(position.castling & BLACK_QUEEN_SIDE, 0);
+        Ok(())
+    }
+}
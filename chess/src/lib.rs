@@ -0,0 +1,16 @@
+//! Chess rules, board and notations
+//!
+//! ```
+//! use chess::game::prelude::*;
+//!
+//! let mut board = Board::new();
+//! board.moves_piece("e2", "e4", None)?;
+//!
+//! assert_eq!(board.side_to_move(), Color::Black);
+//! assert_eq!(board.get("e4")?.unwrap().level, Level::Pawn);
+//! # Ok::<(), Error>(())
+//! ```
+pub mod engine;
+pub mod game;
+pub mod notation;
+pub mod error;
@@ -0,0 +1,5 @@
+//! Chess Notations
+//!
+pub mod fen;
+pub mod pgn;
+pub mod san;
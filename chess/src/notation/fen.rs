@@ -0,0 +1,307 @@
+//! Forsyth–Edwards Notation (FEN)
+//!
+//! A FEN record describes a position in six fields separated by spaces:
+//! piece placement, side to move, castling rights, en passant target,
+//! halfmove clock and fullmove number.
+
+use crate::game::prelude::*;
+use crate::game::convert;
+use crate::error::Error::{self, *};
+
+/// FEN of the standard starting position
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Castling rights in FEN order: (letter, color, king cell, rook cell)
+const CASTLING_RIGHTS: [(char, Color, &str, &str); 4] = [
+    ('K', Color::White, "e1", "h1"),
+    ('Q', Color::White, "e1", "a1"),
+    ('k', Color::Black, "e8", "h8"),
+    ('q', Color::Black, "e8", "a8"),
+];
+
+fn invalid(msg: &str) -> Error {
+    InvalidNotation(format!("invalid FEN, {msg}"))
+}
+
+impl Board<Piece> {
+    /// Create a chess board from a FEN record
+    ///
+    /// The halfmove clock and fullmove number may be omitted,
+    /// they default to `0` and `1`.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1")?;
+    ///
+    /// assert_eq!(board.get("e2")?.unwrap().level, Level::Pawn);
+    /// assert_eq!(board.side_to_move(), Color::Black);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn from_fen(fen: &str) -> Result<Self, Error> {
+        let fields = fen.split_whitespace().collect::<Vec<&str>>();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(invalid("expected 4 to 6 fields"));
+        }
+
+        let mut board = Board {
+            cells: vec![vec![None; 8]; 8],
+            history: vec![],
+            turn: Color::White,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            en_passant: None,
+            start_fen: Some(fen.split_whitespace().collect::<Vec<&str>>().join(" "))
+        };
+
+        // piece placement, from the eighth rank down to the first
+        let ranks = fields[0].split('/').collect::<Vec<&str>>();
+        if ranks.len() != 8 {
+            return Err(invalid("piece placement must have 8 ranks"));
+        }
+        for (i, rank) in ranks.iter().enumerate() {
+            let row = 7 - i;
+            let mut col = 0;
+            for c in rank.chars() {
+                match c.to_digit(10) {
+                    Some(n) if (1..=8).contains(&n) => col += n as usize,
+                    Some(_) => return Err(invalid("empty squares must be between 1 and 8")),
+                    None => {
+                        let mut piece = Piece::from_fen_char(c)
+                            .ok_or_else(|| invalid(&format!("unknown piece '{c}'")))?;
+                        if col > 7 {
+                            return Err(invalid(&format!("rank {} is too long", row + 1)));
+                        }
+                        match piece.level {
+                            Level::Pawn if row == 0 || row == 7 =>
+                                return Err(invalid("pawns can't be on the first or last rank")),
+                            Level::Pawn => {
+                                let start_row = if piece.color == Color::White { 1 } else { 6 };
+                                piece.moved = Some(row != start_row);
+                            },
+                            Level::Rook | Level::King => piece.moved = Some(true),
+                            _ => ()
+                        }
+                        board.cells[row][col] = Some(piece);
+                        col += 1;
+                    }
+                }
+            }
+            if col != 8 {
+                return Err(invalid(&format!("rank {} must have 8 squares", row + 1)));
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let kings = board.get_pieces_positions_by_color(color)?
+                .iter()
+                .filter(|pos| matches!(board.get(pos), Ok(Some(p)) if p.level == Level::King))
+                .count();
+            if kings != 1 {
+                return Err(invalid(&format!("{color} must have exactly one king")));
+            }
+        }
+
+        // side to move
+        board.turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(invalid("side to move must be 'w' or 'b'"))
+        };
+
+        // castling rights, the king and rook keep their unmoved state
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let (_, color, king_cell, rook_cell) = CASTLING_RIGHTS.iter()
+                    .find(|right| right.0 == c)
+                    .ok_or_else(|| invalid(&format!("unknown castling right '{c}'")))?;
+                for (cell, level) in [(king_cell, Level::King), (rook_cell, Level::Rook)] {
+                    match board.cells_mut(cell)? {
+                        Some(piece) if piece.level == level && piece.color == *color =>
+                            piece.moved = Some(false),
+                        _ => return Err(invalid(&format!("castling right '{c}' needs a {color} {level} on {cell}")))
+                    }
+                }
+            }
+        }
+
+        // en passant target
+        if fields[3] != "-" {
+            if fields[3].len() != 2 {
+                return Err(invalid("en passant target must be a cell"));
+            }
+            let (row, _col) = convert(fields[3])?;
+            let expected_row = if board.turn == Color::White { 5 } else { 2 };
+            if row != expected_row {
+                return Err(invalid("en passant target is not on the right rank"));
+            }
+            board.en_passant = Some(fields[3].to_owned());
+        }
+
+        // move clocks
+        if let Some(halfmove) = fields.get(4) {
+            board.halfmove_clock = halfmove.parse()
+                .map_err(|_| invalid("halfmove clock must be a number"))?;
+        }
+        if let Some(fullmove) = fields.get(5) {
+            board.fullmove_number = match fullmove.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(invalid("fullmove number must be a positive number"))
+            };
+        }
+
+        Ok(board)
+    }
+
+    /// Export the chess board as a FEN record
+    ///
+    /// Castling rights are derived from the `moved` state of the kings and rooks.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    ///
+    /// assert_eq!(board.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn to_fen(&self) -> String {
+        let mut placement = vec![];
+        for row in self.cells.iter().rev() {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for cell in row.iter() {
+                match cell {
+                    Some(piece) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        rank.push(piece.as_fen_char());
+                    },
+                    None => empty += 1
+                }
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            placement.push(rank);
+        }
+
+        let turn = match self.turn {
+            Color::White => "w",
+            Color::Black => "b"
+        };
+
+        let castling = CASTLING_RIGHTS.iter()
+            .filter(|(_, color, king_cell, rook_cell)| {
+                self.is_unmoved(king_cell, Level::King, *color)
+                && self.is_unmoved(rook_cell, Level::Rook, *color)
+            })
+            .map(|right| right.0)
+            .collect::<String>();
+        let castling = if castling.is_empty() { "-".to_owned() } else { castling };
+
+        let en_passant = self.get_en_passant_target().ok().flatten().unwrap_or_else(|| "-".to_owned());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement.join("/"), turn, castling, en_passant, self.halfmove_clock, self.fullmove_number
+        )
+    }
+
+    /// Get a mutable reference to the content of a cell
+    fn cells_mut(&mut self, cell: &str) -> Result<&mut Option<Piece>, Error> {
+        let (row, col) = convert(cell)?;
+        Ok(&mut self.cells[row as usize][col as usize])
+    }
+
+    /// Check if an unmoved piece of the level and color stands on the cell
+    fn is_unmoved(&self, cell: &str, level: Level, color: Color) -> bool {
+        matches!(
+            self.get(cell),
+            Ok(Some(piece)) if piece.level == level && piece.color == color && piece.moved == Some(false)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fen_start_position() -> Result<(), Error> {
+        let board = Board::from_fen(START_FEN)?;
+        assert_eq!(board.cells, Board::new().cells);
+        assert_eq!(Board::new().to_fen(), START_FEN);
+        Ok(())
+    }
+
+    #[test]
+    fn fen_round_trip() -> Result<(), Error> {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "4k3/8/8/8/8/8/8/4K2R b K - 12 40",
+        ];
+        for fen in fens {
+            assert_eq!(Board::from_fen(fen)?.to_fen(), fen);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fen_castling_rights() -> Result<(), Error> {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1")?;
+        assert_eq!(board.get("e1")?.unwrap().moved, Some(false));
+        assert_eq!(board.get("h1")?.unwrap().moved, Some(false));
+        assert_eq!(board.get("a1")?.unwrap().moved, Some(true));
+        assert_eq!(board.get("a8")?.unwrap().moved, Some(false));
+        assert_eq!(board.get("h8")?.unwrap().moved, Some(true));
+
+        let mut board = Board::new();
+        board.moves_piece("h2", "h4", None)?;
+        board.moves_piece("a7", "a5", None)?;
+        board.moves_piece("h1", "h3", None)?;
+        board.moves_piece("a8", "a6", None)?;
+        assert_eq!(board.to_fen(), "1nbqkbnr/1ppppppp/r7/p7/7P/7R/PPPPPPP1/RNBQKBN1 w Qk - 2 3");
+        Ok(())
+    }
+
+    #[test]
+    fn fen_en_passant() -> Result<(), Error> {
+        let mut board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1")?;
+        assert_eq!(board.get_possible_moves("e5")?, ["d6", "e6"]);
+        board.moves_piece("e5", "d6", None)?;
+        assert!(board.get("d5")?.is_none());
+        assert_eq!(board.to_fen(), "4k3/8/3P4/8/8/8/8/4K3 b - - 0 1");
+        board.undo_moves()?;
+        assert_eq!(board.to_fen(), "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        Ok(())
+    }
+
+    #[test]
+    fn fen_invalid() {
+        let fens = [
+            "",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+            "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnP/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNR w KQkq - 0 1",
+        ];
+        for fen in fens {
+            assert!(matches!(Board::from_fen(fen), Err(InvalidNotation(_))), "{fen}");
+        }
+    }
+}
@@ -0,0 +1,422 @@
+//! Portable Game Notation (PGN)
+//!
+//! A PGN record is a list of tag pairs followed by the movetext,
+//! the moves are written in Standard Algebraic Notation (SAN).
+
+use std::fmt::Display;
+use crate::game::prelude::*;
+use crate::error::Error::{self, *};
+use super::san::{parse_san, to_san};
+
+/// Tags of the Seven Tag Roster, in the order they are written
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// Game termination markers
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Chess game in Portable Game Notation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pgn {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>
+}
+
+impl Pgn {
+    /// Create a PGN from the tags and the moves history of the board
+    ///
+    /// Missing tags of the Seven Tag Roster are filled with `"?"`
+    /// (`"*"` for `Result`), and the `SetUp` and `FEN` tags are added
+    /// when the board didn't start from the standard starting position.
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// # use chess::notation::pgn::Pgn;
+    /// let mut board = Board::new();
+    /// board.moves_piece("e2", "e4", None)?;
+    /// board.moves_piece("e7", "e5", None)?;
+    /// board.moves_piece("g1", "f3", None)?;
+    /// let pgn = Pgn::new(vec![("White".to_owned(), "Alice".to_owned())], &board)?;
+    ///
+    /// assert_eq!(pgn.tag("White"), Some("Alice"));
+    /// assert_eq!(pgn.tag("Result"), Some("*"));
+    /// assert_eq!(pgn.moves, ["e4", "e5", "Nf3"]);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn new(tags: Vec<(String, String)>, board: &Board<Piece>) -> Result<Pgn, Error> {
+        let mut pgn = Pgn { tags, moves: vec![] };
+
+        for name in SEVEN_TAG_ROSTER {
+            if pgn.tag(name).is_none() {
+                let value = if name == "Result" { "*" } else { "?" };
+                pgn.set_tag(name, value);
+            }
+        }
+
+        let mut replay = match board.start_fen {
+            Some(ref fen) => {
+                pgn.set_tag("SetUp", "1");
+                pgn.set_tag("FEN", fen);
+                Board::from_fen(fen)?
+            },
+            None => Board::new()
+        };
+
+        for his in board.get_history().iter() {
+            pgn.moves.push(to_san(&replay, &his.from, &his.to, his.promotion)?);
+            replay.moves_piece(&his.from, &his.to, his.promotion)?;
+        }
+
+        Ok(pgn)
+    }
+
+    /// Parse a PGN record, the moves are not checked until [`Pgn::to_board`]
+    pub fn parse(text: &str) -> Result<Pgn, Error> {
+        let mut pgn = Pgn::default();
+        let mut movetext = String::new();
+        let mut in_tags = true;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if in_tags && line.starts_with('[') {
+                let (name, value) = parse_tag(line)?;
+                pgn.set_tag(&name, &value);
+            } else if in_tags && line.is_empty() {
+                continue;
+            } else {
+                in_tags = false;
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        let mut result = None;
+        for token in tokenize(&movetext)? {
+            if result.is_some() {
+                return Err(InvalidNotation(format!("unexpected '{token}' after the game result")));
+            }
+            if RESULTS.contains(&token.as_str()) {
+                result = Some(token);
+            } else {
+                pgn.moves.push(token);
+            }
+        }
+
+        if let Some(result) = result {
+            if pgn.tag("Result").is_none() {
+                pgn.set_tag("Result", &result);
+            }
+        }
+
+        Ok(pgn)
+    }
+
+    /// Get the value of a tag
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set the value of a tag, adding the tag if it doesn't exist
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(key, _)| key == name) {
+            Some(tag) => tag.1 = value.to_owned(),
+            None => self.tags.push((name.to_owned(), value.to_owned()))
+        }
+    }
+
+    /// Replay the moves on a new board
+    ///
+    /// ### Examples
+    ///
+    /// ```
+    /// # use chess::game::prelude::*;
+    /// # use chess::notation::pgn::Pgn;
+    /// let pgn = Pgn::parse("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0")?;
+    /// let board = pgn.to_board()?;
+    ///
+    /// assert_eq!(board.get("f7")?.unwrap().level, Level::Queen);
+    /// assert!(board.clone().is_checkmate(Color::Black)?);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn to_board(&self) -> Result<Board<Piece>, Error> {
+        let mut board = match self.tag("FEN") {
+            Some(fen) => Board::from_fen(fen)?,
+            None => Board::new()
+        };
+
+        for (i, san) in self.moves.iter().enumerate() {
+            parse_san(&board, san)
+                .and_then(|(from, to, promotion)| board.moves_piece(&from, &to, promotion))
+                .map_err(|err| {
+                    IllegalMoves(format!("move {} '{}' can't be played, {}", i / 2 + 1, san, err))
+                })?;
+        }
+
+        Ok(board)
+    }
+}
+
+impl Display for Pgn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in self.tags.iter() {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        // the starting position decides the first move number
+        let fen_fields = self.tag("FEN")
+            .map(|fen| fen.split_whitespace().collect::<Vec<&str>>())
+            .unwrap_or_default();
+        let mut black_to_move = fen_fields.get(1) == Some(&"b");
+        let mut number = fen_fields.get(5)
+            .and_then(|n| n.parse::<u32>().ok())
+            .unwrap_or(1);
+
+        let mut tokens = vec![];
+        for (i, san) in self.moves.iter().enumerate() {
+            if !black_to_move {
+                tokens.push(format!("{}.", number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", number));
+            }
+            tokens.push(san.to_owned());
+            if black_to_move {
+                number += 1;
+            }
+            black_to_move = !black_to_move;
+        }
+        tokens.push(self.tag("Result").unwrap_or("*").to_owned());
+
+        // wrap the movetext before 80 columns
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + token.len() + 1 > 79 {
+                writeln!(f)?;
+                line_len = 0;
+            }
+            if line_len > 0 {
+                write!(f, " ")?;
+                line_len += 1;
+            }
+            write!(f, "{}", token)?;
+            line_len += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+/// Parse a tag pair line, e.g. `[Event "Casual Game"]`
+fn parse_tag(line: &str) -> Result<(String, String), Error> {
+    let invalid = || InvalidNotation(format!("invalid PGN tag {line}"));
+    let inner = line.strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(invalid)?
+        .trim();
+    let (name, rest) = inner.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let quoted = rest.trim()
+        .strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next().ok_or_else(invalid)?),
+            _ => value.push(c)
+        }
+    }
+
+    Ok((name.to_owned(), value))
+}
+
+/// Split the movetext into SAN moves and the result,
+/// skipping comments, variations, NAGs and move numbers
+fn tokenize(movetext: &str) -> Result<Vec<String>, Error> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut chars = movetext.chars();
+    let mut depth = 0;
+
+    let mut flush = |token: &mut String| {
+        // strip the move number, e.g. "12." or "12..."
+        let word = match token.rfind('.') {
+            Some(i) if token[..i].chars().all(|c| c.is_ascii_digit() || c == '.') => &token[i + 1..],
+            _ => token.as_str()
+        };
+        if !word.is_empty() && !word.starts_with('$') {
+            tokens.push(word.to_owned());
+        }
+        token.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                flush(&mut token);
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err(InvalidNotation("unterminated PGN comment".to_owned()));
+                }
+            },
+            ';' => {
+                flush(&mut token);
+                chars.by_ref().find(|c| *c == '\n');
+            },
+            '(' => {
+                flush(&mut token);
+                depth += 1;
+            },
+            ')' => {
+                flush(&mut token);
+                if depth == 0 {
+                    return Err(InvalidNotation("unbalanced PGN variation".to_owned()));
+                }
+                depth -= 1;
+            },
+            _ if depth > 0 => (),
+            _ if c.is_whitespace() => flush(&mut token),
+            _ => token.push(c)
+        }
+    }
+    flush(&mut token);
+
+    if depth != 0 {
+        return Err(InvalidNotation("unbalanced PGN variation".to_owned()));
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pgn_write_moves() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        board.moves_piece("g1", "f3", None)?;
+        board.moves_piece("b8", "c6", None)?;
+        board.moves_piece("f1", "c4", None)?;
+        board.moves_piece("g8", "f6", None)?;
+        board.moves_piece("f3", "g5", None)?;
+        board.moves_piece("d7", "d5", None)?;
+        board.moves_piece("e4", "d5", None)?;
+        board.moves_piece("f6", "d5", None)?;
+        board.moves_piece("g5", "f7", None)?;
+        let pgn = Pgn::new(vec![], &board)?;
+        assert_eq!(pgn.moves, ["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "Ng5", "d5", "exd5", "Nxd5", "Nxf7"]);
+        Ok(())
+    }
+
+    #[test]
+    fn pgn_write_castling_and_checkmate() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        board.moves_piece("g1", "f3", None)?;
+        board.moves_piece("b8", "c6", None)?;
+        board.moves_piece("f1", "c4", None)?;
+        board.moves_piece("f8", "c5", None)?;
+        board.castling("e1", "h1")?;
+        board.moves_piece("d8", "h4", None)?;
+        board.moves_piece("f3", "h4", None)?;
+        let pgn = Pgn::new(vec![], &board)?;
+        assert_eq!(pgn.moves[6], "O-O");
+        assert_eq!(pgn.moves[8], "Nxh4");
+
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        board.moves_piece("d1", "f3", None)?;
+        board.moves_piece("b8", "c6", None)?;
+        board.moves_piece("f1", "c4", None)?;
+        board.moves_piece("f8", "c5", None)?;
+        board.moves_piece("f3", "f7", None)?;
+        let pgn = Pgn::new(vec![], &board)?;
+        assert_eq!(pgn.moves, ["e4", "e5", "Qf3", "Nc6", "Bc4", "Bc5", "Qxf7#"]);
+        Ok(())
+    }
+
+    #[test]
+    fn pgn_display() -> Result<(), Error> {
+        let mut board = Board::new();
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("e7", "e5", None)?;
+        board.moves_piece("g1", "f3", None)?;
+        let tags = vec![
+            ("White".to_owned(), "Alice".to_owned()),
+            ("Black".to_owned(), "Bob \"B\"".to_owned()),
+        ];
+        let pgn = Pgn::new(tags, &board)?;
+        assert_eq!(pgn.to_string(), concat!(
+            "[White \"Alice\"]\n",
+            "[Black \"Bob \\\"B\\\"\"]\n",
+            "[Event \"?\"]\n",
+            "[Site \"?\"]\n",
+            "[Date \"?\"]\n",
+            "[Round \"?\"]\n",
+            "[Result \"*\"]\n",
+            "\n",
+            "1. e4 e5 2. Nf3 *\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn pgn_parse() -> Result<(), Error> {
+        let text = r#"[Event "Casual \"Game\""]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Qh5?! (2. Nf3 Nc6) Nc6 $2 3. Bc4 Nf6??
+4. Qxf7# ; mate
+1-0"#;
+        let pgn = Pgn::parse(text)?;
+        assert_eq!(pgn.tag("Event"), Some("Casual \"Game\""));
+        assert_eq!(pgn.tag("Result"), Some("1-0"));
+        assert_eq!(pgn.moves, ["e4", "e5", "Qh5?!", "Nc6", "Bc4", "Nf6??", "Qxf7#"]);
+        Ok(())
+    }
+
+    #[test]
+    fn pgn_round_trip() -> Result<(), Error> {
+        let text = "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7 *";
+        let board = Pgn::parse(text)?.to_board()?;
+        let pgn = Pgn::new(vec![], &board)?;
+        assert_eq!(pgn.moves.join(" "), "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 d6 c3 O-O h3 Nb8 d4 Nbd7");
+        assert_eq!(Pgn::parse(&pgn.to_string())?.to_board()?.cells, board.cells);
+        Ok(())
+    }
+
+    #[test]
+    fn pgn_replay_special_moves() -> Result<(), Error> {
+        let board = Pgn::parse("1. e4 Nf6 2. e5 d5 3. exd6 Nc6 4. dxc7 Ne4 5. cxd8=Q+ Kxd8 6. Nf3 Nc5 7. Be2 Ne6 8. O-O *")?
+            .to_board()?;
+        assert_eq!(board.get("g1")?.unwrap().level, Level::King);
+        assert_eq!(board.get("f1")?.unwrap().level, Level::Rook);
+        assert!(board.get("d5")?.is_none());
+        assert_eq!(board.get_captured(Color::Black)?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn pgn_illegal_moves() {
+        let errors = [
+            "1. e5 *",
+            "1. e4 e5 2. Ke3 *",
+            "1. Nd2 *",
+            "1. e4 e5 2. Nf3 Nc6 3. Nf5 *",
+            "1. d4 e5 2. Nd2 Bb4 3. Nb3 *",
+            "1. e4 e5 2. Nf3 0-0 *",
+            "1. e4 e5 2. Zz9 *",
+            "1. e4 { unterminated",
+        ];
+        for text in errors {
+            assert!(Pgn::parse(text).and_then(|pgn| pgn.to_board()).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Standard Algebraic Notation (SAN)
+//!
+//! A move is written as the piece letter (none for pawns), the
+//! disambiguation of the source cell when needed, `x` for captures,
+//! the destination cell, `=Q` for promotions and `+` or `#` when the
+//! move gives check or checkmate. Castling is written `O-O` or `O-O-O`.
+
+use crate::game::prelude::*;
+use crate::game::{convert, invert};
+use crate::error::Error::{self, *};
+
+/// Cells of the pieces of `level` and `color` which can legally move to `to`
+fn legal_sources(board: &Board<Piece>, level: Level, color: Color, to: &str) -> Result<Vec<String>, Error> {
+    let mut sources = vec![];
+    for pos in board.get_pieces_positions_by_color(color)? {
+        if board.get(&pos)?.map(|p| p.level) != Some(level) {
+            continue;
+        }
+        // castling is written `O-O`, never as a king move
+        if is_castling(board, &pos, to)? {
+            continue;
+        }
+        if board.legal_moves(&pos)?.iter().any(|m| m == to) {
+            sources.push(pos);
+        }
+    }
+    Ok(sources)
+}
+
+/// Check and checkmate suffix for the side to move
+fn check_suffix(board: &mut Board<Piece>) -> Result<&'static str, Error> {
+    let color = board.side_to_move();
+    if board.is_checkmate(color)? {
+        Ok("#")
+    } else if board.is_king_checked(color)? {
+        Ok("+")
+    } else {
+        Ok("")
+    }
+}
+
+/// Check if the move is a castling, written as the king moving two cells
+fn is_castling(board: &Board<Piece>, from: &str, to: &str) -> Result<bool, Error> {
+    let (_from_row, from_col) = convert(from)?;
+    let (_to_row, to_col) = convert(to)?;
+    match board.get(from)? {
+        Some(king) => Ok(king.level == Level::King && (from_col - to_col).abs() == 2),
+        None => Ok(false)
+    }
+}
+
+/// Write a move of the side to move in SAN
+///
+/// ### Examples
+///
+/// ```
+/// # use chess::game::prelude::*;
+/// # use chess::notation::san;
+/// let mut board = Board::new();
+/// board.moves_piece("e2", "e4", None)?;
+/// board.moves_piece("f7", "f6", None)?;
+///
+/// assert_eq!(san::to_san(&board, "g1", "f3", None)?, "Nf3");
+/// assert_eq!(san::to_san(&board, "d1", "h5", None)?, "Qh5+");
+/// # Ok::<(), Error>(())
+/// ```
+pub fn to_san(board: &Board<Piece>, from: &str, to: &str, promotion: Option<Level>) -> Result<String, Error> {
+    let mut board = board.clone();
+    let piece = board.get(from)?
+        .ok_or_else(|| IllegalMoves(format!("there is no piece on {from}")))?;
+    let (from_row, from_col) = convert(from)?;
+    let (_to_row, to_col) = convert(to)?;
+
+    if is_castling(&board, from, to)? {
+        board.moves_piece(from, to, None)?;
+        let san = if to_col > from_col { "O-O" } else { "O-O-O" };
+        return Ok(format!("{}{}", san, check_suffix(&mut board)?));
+    }
+
+    // a pawn moving diagonally always captures, even en passant
+    let capture = board.get(to)?.is_some() || (piece.level == Level::Pawn && from_col != to_col);
+    let mut san = String::new();
+
+    if piece.level == Level::Pawn {
+        if capture {
+            san.push_str(&from[0..1]);
+        }
+    } else {
+        san.push(piece.level.as_char());
+
+        // disambiguate between pieces of the same level moving to the same cell
+        let others = legal_sources(&board, piece.level, piece.color, to)?
+            .into_iter()
+            .filter(|pos| pos != from)
+            .map(|pos| convert(&pos))
+            .collect::<Result<Vec<(i8, i8)>, Error>>()?;
+        if !others.is_empty() {
+            let from = invert(from_row, from_col)?;
+            if others.iter().all(|(_row, col)| *col != from_col) {
+                san.push_str(&from[0..1]);
+            } else if others.iter().all(|(row, _col)| *row != from_row) {
+                san.push_str(&from[1..2]);
+            } else {
+                san.push_str(&from);
+            }
+        }
+    }
+
+    if capture {
+        san.push('x');
+    }
+    san.push_str(to);
+    if let Some(level) = promotion {
+        san.push('=');
+        san.push(level.as_char());
+    }
+
+    board.moves_piece(from, to, promotion)?;
+    san.push_str(check_suffix(&mut board)?);
+    Ok(san)
+}
+
+/// Resolve a move written in SAN into a legal `(from, to, promotion)`
+/// for the side to move
+///
+/// Castling resolves to the king moving two cells. The `+`, `#`,
+/// `!` and `?` suffixes are ignored.
+///
+/// ### Examples
+///
+/// ```
+/// # use chess::game::prelude::*;
+/// # use chess::notation::san;
+/// let board = Board::new();
+///
+/// assert_eq!(san::parse_san(&board, "e4")?, ("e2".to_owned(), "e4".to_owned(), None));
+/// assert_eq!(san::parse_san(&board, "Nc3")?, ("b1".to_owned(), "c3".to_owned(), None));
+/// assert!(san::parse_san(&board, "Nd2").is_err());
+/// # Ok::<(), Error>(())
+/// ```
+pub fn parse_san(board: &Board<Piece>, san: &str) -> Result<(String, String, Option<Level>), Error> {
+    let invalid = || InvalidNotation(format!("'{san}' is not a valid SAN move"));
+    let text = san.trim_end_matches(['+', '#', '!', '?']);
+    let color = board.side_to_move();
+
+    // castling
+    let rook_col = match text {
+        "O-O" | "0-0" => Some("h"),
+        "O-O-O" | "0-0-0" => Some("a"),
+        _ => None
+    };
+    if let Some(col) = rook_col {
+        let king = board.get_king_position(color)?;
+        let rook = format!("{}{}", col, &king[1..2]);
+        let (to, _rook_to) = board.castling_cells(&king, &rook)
+            .map_err(|_| CastlingError(format!("{color} can't castle with {rook}")))?;
+        return Ok((king, to, None));
+    }
+
+    if !text.is_ascii() || text.len() < 2 {
+        return Err(invalid());
+    }
+
+    // promotion, e.g. "e8=Q" or "e8Q"
+    let (text, promotion) = match text.char_indices().last() {
+        Some((i, c)) if i >= 2 && "NBRQ".contains(c) => {
+            let rest = text[..i].strip_suffix('=').unwrap_or(&text[..i]);
+            (rest, Level::from_char(c))
+        },
+        _ => (text, None)
+    };
+
+    // piece level, pawns have no letter
+    let (level, text) = match text.chars().next() {
+        Some(c) if "NBRQK".contains(c) => (Level::from_char(c).ok_or_else(invalid)?, &text[1..]),
+        _ => (Level::Pawn, text)
+    };
+
+    if text.len() < 2 {
+        return Err(invalid());
+    }
+    let to = &text[text.len() - 2..];
+    convert(to).map_err(|_| invalid())?;
+
+    // disambiguation of the source cell
+    let (mut from_col, mut from_row) = (None, None);
+    for c in text[..text.len() - 2].chars() {
+        match c {
+            'a'..='h' => from_col = Some(c),
+            '1'..='8' => from_row = Some(c),
+            'x' => (),
+            _ => return Err(invalid())
+        }
+    }
+
+    let sources = legal_sources(board, level, color, to)?
+        .into_iter()
+        .filter(|pos| from_col.is_none_or(|c| pos.starts_with(c)))
+        .filter(|pos| from_row.is_none_or(|r| pos.ends_with(r)))
+        .collect::<Vec<String>>();
+
+    let from = match sources.len() {
+        1 => sources[0].to_owned(),
+        0 => return Err(IllegalMoves(format!("no {color} {level} can move to {to}"))),
+        _ => return Err(InvalidNotation(format!("'{san}' is ambiguous")))
+    };
+
+    let (to_row, _to_col) = convert(to)?;
+    let last_rank = if color == Color::White { 7 } else { 0 };
+    match (level == Level::Pawn && to_row == last_rank, promotion) {
+        (true, None) => return Err(PromotionError(format!("'{san}' must choose a promotion piece"))),
+        (false, Some(_)) => return Err(PromotionError(format!("'{san}' is not a promotion"))),
+        _ => ()
+    }
+
+    Ok((from, to.to_owned(), promotion))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn san_moves() -> Result<(), Error> {
+        let mut board = Board::new();
+        assert_eq!(to_san(&board, "e2", "e4", None)?, "e4");
+        assert_eq!(to_san(&board, "g1", "f3", None)?, "Nf3");
+        board.moves_piece("e2", "e4", None)?;
+        board.moves_piece("d7", "d5", None)?;
+        assert_eq!(to_san(&board, "e4", "d5", None)?, "exd5");
+        assert_eq!(to_san(&board, "f1", "b5", None)?, "Bb5+");
+        Ok(())
+    }
+
+    #[test]
+    fn san_castling_and_checkmate() -> Result<(), Error> {
+        let mut board = Board::new();
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("g1", "f3"), ("b8", "c6"), ("f1", "c4"), ("f8", "c5")] {
+            board.moves_piece(from, to, None)?;
+        }
+        assert_eq!(to_san(&board, "e1", "g1", None)?, "O-O");
+        assert_eq!(parse_san(&board, "O-O")?, ("e1".to_owned(), "g1".to_owned(), None));
+        assert!(parse_san(&board, "O-O-O").is_err());
+
+        board.moves_piece("d2", "d3", None)?;
+        board.moves_piece("g8", "f6", None)?;
+        board.moves_piece("c4", "f7", None)?;
+        board.moves_piece("e8", "e7", None)?;
+        assert_eq!(to_san(&board, "f7", "d5", None)?, "Bd5");
+
+        let mut board = Board::new();
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("d1", "f3"), ("b8", "c6"), ("f1", "c4"), ("f8", "c5")] {
+            board.moves_piece(from, to, None)?;
+        }
+        assert_eq!(to_san(&board, "f3", "f7", None)?, "Qxf7#");
+        Ok(())
+    }
+
+    #[test]
+    fn san_disambiguation() -> Result<(), Error> {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w - - 0 1")?;
+        assert_eq!(to_san(&board, "a1", "d1", None)?, "Rd1");
+
+        let board = Board::from_fen("4k3/8/8/8/7R/8/8/3RK3 w - - 0 1")?;
+        assert_eq!(to_san(&board, "h4", "d4", None)?, "Rhd4");
+        assert_eq!(parse_san(&board, "Rhd4")?, ("h4".to_owned(), "d4".to_owned(), None));
+        assert!(matches!(parse_san(&board, "Rd4"), Err(InvalidNotation(_))));
+
+        let board = Board::from_fen("4k3/8/8/8/3R4/8/8/3RK3 w - - 0 1")?;
+        assert_eq!(to_san(&board, "d4", "d3", None)?, "R4d3");
+        assert_eq!(parse_san(&board, "R1d3")?, ("d1".to_owned(), "d3".to_owned(), None));
+
+        let board = Board::from_fen("k7/8/8/8/8/2Q1Q3/8/4Q1K1 w - - 0 1")?;
+        assert_eq!(to_san(&board, "e3", "d2", None)?, "Qe3d2");
+        assert_eq!(parse_san(&board, "Qe3d2")?, ("e3".to_owned(), "d2".to_owned(), None));
+        Ok(())
+    }
+
+    #[test]
+    fn san_promotion() -> Result<(), Error> {
+        let board = Board::from_fen("r6k/1P6/8/8/8/8/8/7K w - - 0 1")?;
+        assert_eq!(to_san(&board, "b7", "b8", Some(Level::Queen))?, "b8=Q+");
+        assert_eq!(to_san(&board, "b7", "a8", Some(Level::Knight))?, "bxa8=N");
+        assert_eq!(parse_san(&board, "b8=Q")?, ("b7".to_owned(), "b8".to_owned(), Some(Level::Queen)));
+        assert_eq!(parse_san(&board, "bxa8N")?, ("b7".to_owned(), "a8".to_owned(), Some(Level::Knight)));
+        assert!(matches!(parse_san(&board, "b8"), Err(PromotionError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn san_en_passant() -> Result<(), Error> {
+        let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1")?;
+        assert_eq!(to_san(&board, "e5", "d6", None)?, "exd6");
+        assert_eq!(parse_san(&board, "exd6")?, ("e5".to_owned(), "d6".to_owned(), None));
+        Ok(())
+    }
+
+    #[test]
+    fn san_illegal() -> Result<(), Error> {
+        let mut board = Board::new();
+        assert!(parse_san(&board, "e5").is_err());
+        assert!(parse_san(&board, "Ke2").is_err());
+        assert!(parse_san(&board, "Zz9").is_err());
+        assert!(parse_san(&board, "♘f3").is_err());
+        assert!(parse_san(&board, "").is_err());
+
+        // a pinned knight can't move
+        for (from, to) in [("d2", "d4"), ("e7", "e5"), ("b1", "d2"), ("f8", "b4")] {
+            board.moves_piece(from, to, None)?;
+        }
+        assert!(matches!(parse_san(&board, "Nb3"), Err(IllegalMoves(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+//! Player Accounts
+//!
+//! Players register with a username and a password hashed with argon2, and
+//! log in for a session kept in the `session` cookie. A signed-in player
+//! holds its seats with its secret seat token, so its games follow it from
+//! one browser to another and its display name is shown to the opponents.
+
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use actix_web::{
+    get, post, web, error,
+    cookie::{time, Cookie, SameSite},
+    HttpRequest, HttpResponse
+};
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{SaltString, rand_core::OsRng}
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use chess::error::Error::{self, *};
+use crate::{
+    api::error_response,
+    model::Category,
+    rating::Rating,
+    storage::AccountStorage
+};
+
+/// Cookie of the login session
+pub const SESSION_COOKIE: &str = "session";
+
+/// How long a login session lasts, in days
+const SESSION_DAYS: i64 = 30;
+
+const MIN_PASSWORD: usize = 8;
+const MAX_PASSWORD: usize = 128;
+const MAX_DISPLAY_NAME: usize = 30;
+
+/// Players shown on a leaderboard
+const LEADERBOARD_SIZE: usize = 50;
+
+/// Registered player
+#[derive(Clone)]
+pub struct User {
+    pub id: Uuid,
+    /// Unique name to log in, case insensitive
+    pub username: String,
+    /// Name shown to the opponents
+    pub display_name: String,
+    /// Argon2 hash of the password in the PHC string format
+    pub password_hash: String,
+    pub created_at: DateTime<Utc>,
+    /// Secret player token holding the seats of the user, unlike its id it
+    /// is never shown, and only a request of the user's session can use it
+    pub seat_token: Uuid
+}
+
+/// Public part of a user account
+#[derive(Debug, Serialize)]
+pub struct Profile {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: String
+}
+
+impl From<&User> for Profile {
+    fn from(user: &User) -> Profile {
+        Profile { id: user.id, username: user.username.clone(), display_name: user.display_name.clone() }
+    }
+}
+
+/// Player of a leaderboard
+#[derive(Debug, Serialize)]
+pub struct Standing {
+    pub username: String,
+    pub display_name: String,
+    pub rating: u32,
+    pub deviation: u32,
+    /// Rated games played in the category
+    pub games: u32
+}
+
+/// Hash the password with a random salt
+fn hash_password(password: &str) -> Result<String, Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| AccountError(err.to_string()))
+}
+
+/// Check the password against its hash
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// Hash of no password, checked for an unknown username so the response
+/// takes as long as for a registered one
+fn dummy_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash_password("the password of nobody").unwrap_or_default())
+}
+
+/// Check a username, 3 to 20 letters, digits, `_` or `-`
+fn validate_username(username: &str) -> Result<(), Error> {
+    let valid = (3..=20).contains(&username.len())
+        && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(AccountError("The username must be 3 to 20 letters, digits, '_' or '-'.".to_owned()));
+    }
+    Ok(())
+}
+
+/// Accounts of the players, in their own storage connection so the
+/// password hashing runs on the blocking threads without the lobby
+#[derive(Debug)]
+pub struct Accounts {
+    storage: Mutex<Box<dyn AccountStorage>>
+}
+
+impl Accounts {
+    /// Create new `Accounts` kept in the storage
+    pub fn new(storage: Box<dyn AccountStorage>) -> Accounts {
+        Accounts { storage: Mutex::new(storage) }
+    }
+
+    /// Create new `Accounts` kept in an in-memory database, for the tests
+    #[cfg(test)]
+    pub fn in_memory() -> Accounts {
+        Accounts::new(Box::new(crate::storage::SqliteStorage::open(":memory:").unwrap()))
+    }
+
+    fn storage(&self) -> Result<MutexGuard<'_, Box<dyn AccountStorage>>, Error> {
+        self.storage.lock().map_err(|err| StorageError(err.to_string()))
+    }
+
+    /// Register a user, shown to the opponents by its username unless
+    /// a display name is given
+    pub fn register(&self, username: &str, password: &str, display_name: Option<&str>) -> Result<User, Error> {
+        validate_username(username)?;
+        if !(MIN_PASSWORD..=MAX_PASSWORD).contains(&password.chars().count()) {
+            return Err(AccountError(format!("The password must be {MIN_PASSWORD} to {MAX_PASSWORD} characters.")));
+        }
+        let display_name = display_name.map(str::trim).filter(|name| !name.is_empty()).unwrap_or(username);
+        if display_name.chars().count() > MAX_DISPLAY_NAME {
+            return Err(AccountError(format!("The display name must be at most {MAX_DISPLAY_NAME} characters.")));
+        }
+
+        let user = User {
+            id: Uuid::new_v4(),
+            username: username.to_owned(),
+            display_name: display_name.to_owned(),
+            password_hash: hash_password(password)?,
+            created_at: Utc::now(),
+            seat_token: Uuid::new_v4()
+        };
+        if !self.storage()?.create_user(&user)? {
+            return Err(AccountError(format!("The username '{username}' is taken.")));
+        }
+        Ok(user)
+    }
+
+    /// Open a session for the user when the password matches, `None` otherwise
+    pub fn login(&self, username: &str, password: &str) -> Result<Option<(Uuid, User)>, Error> {
+        // the storage is unlocked while the password is checked
+        let user = self.storage()?.load_user(username)?;
+        let hash = user.as_ref().map_or(dummy_hash(), |user| user.password_hash.as_str());
+        let verified = verify_password(password, hash);
+        match user {
+            Some(user) if verified => Ok(Some((self.open_session(&user)?, user))),
+            _ => Ok(None)
+        }
+    }
+
+    /// Open a session for the user
+    pub fn open_session(&self, user: &User) -> Result<Uuid, Error> {
+        let session = Uuid::new_v4();
+        self.storage()?.create_session(&session, &user.id)?;
+        Ok(session)
+    }
+
+    /// Close a session
+    pub fn logout(&self, session: &Uuid) -> Result<(), Error> {
+        self.storage()?.delete_session(session)
+    }
+
+    /// Get the user of a session, `None` when it's unknown or expired
+    pub fn authenticate(&self, session: &Uuid) -> Result<Option<User>, Error> {
+        self.storage()?.session_user(session, Utc::now() - Duration::days(SESSION_DAYS))
+    }
+
+    /// Get the user holding seats with the player token, `None` for a guest
+    pub fn seat_user(&self, token: &Uuid) -> Result<Option<User>, Error> {
+        self.storage()?.seat_user(token)
+    }
+
+    /// Get the rating of a user in the category, the default one before
+    /// its first rated game
+    pub fn rating(&self, user_id: &Uuid, category: Category) -> Result<Rating, Error> {
+        Ok(self.storage()?.load_rating(user_id, category)?.unwrap_or_default())
+    }
+
+    /// Update the ratings of both players of a finished game with the score
+    /// of White, returns their new ratings
+    pub fn rate_game(&self, white: &Uuid, black: &Uuid, category: Category, score: f64) -> Result<(Rating, Rating), Error> {
+        let storage = self.storage()?;
+        let white_rating = storage.load_rating(white, category)?.unwrap_or_default();
+        let black_rating = storage.load_rating(black, category)?.unwrap_or_default();
+
+        let new_white = white_rating.update(&[(black_rating, score)]);
+        let new_black = black_rating.update(&[(white_rating, 1.0 - score)]);
+        storage.save_rating(white, category, &new_white)?;
+        storage.save_rating(black, category, &new_black)?;
+        Ok((new_white, new_black))
+    }
+
+    /// Best rated players of the category
+    pub fn leaderboard(&self, category: Category) -> Result<Vec<Standing>, Error> {
+        self.storage()?.leaderboard(category, LEADERBOARD_SIZE)
+    }
+}
+
+/// Session id of the request cookie
+fn session_id(request: &HttpRequest) -> Option<Uuid> {
+    request.cookie(SESSION_COOKIE).and_then(|cookie| Uuid::parse_str(cookie.value()).ok())
+}
+
+/// Get the signed-in user of the request, `None` for a guest
+pub async fn current_user(request: &HttpRequest, accounts: &web::Data<Accounts>) -> Result<Option<User>, actix_web::Error> {
+    let session = match session_id(request) {
+        Some(session) => session,
+        None => return Ok(None)
+    };
+
+    let accounts = accounts.clone();
+    web::block(move || accounts.authenticate(&session)).await?
+        .map_err(error::ErrorInternalServerError)
+}
+
+/// Whether a player token sent without a session is the seat token of an
+/// account, which only the signed-in user can play with
+pub async fn is_account_token(token: Uuid, accounts: &web::Data<Accounts>) -> Result<bool, actix_web::Error> {
+    let accounts = accounts.clone();
+    let user = web::block(move || accounts.seat_user(&token)).await?
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(user.is_some())
+}
+
+/// Response opening the login session of the user
+fn session_response(mut response: actix_web::HttpResponseBuilder, session: Uuid, user: &User) -> HttpResponse {
+    let cookie = Cookie::build(SESSION_COOKIE, session.to_string())
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::days(SESSION_DAYS))
+        .finish();
+    response.cookie(cookie).json(Profile::from(user))
+}
+
+/// Registration form, e.g. `{"username":"alice","password":"...","display_name":"Alice"}`
+#[derive(Debug, Deserialize)]
+pub struct Registration {
+    username: String,
+    password: String,
+    display_name: Option<String>
+}
+
+/// Login form
+#[derive(Debug, Deserialize)]
+pub struct Credentials {
+    username: String,
+    password: String
+}
+
+/// Register a user and log it in
+#[post("/api/register")]
+pub async fn register(
+    form: web::Json<Registration>,
+    accounts: web::Data<Accounts>
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    let registered = web::block(move || {
+        let user = accounts.register(&form.username, &form.password, form.display_name.as_deref())?;
+        Ok::<(Uuid, User), Error>((accounts.open_session(&user)?, user))
+    }).await?;
+
+    Ok(match registered {
+        Ok((session, user)) => session_response(HttpResponse::Created(), session, &user),
+        Err(err) => error_response(err)
+    })
+}
+
+/// Log in with a username and a password
+#[post("/api/login")]
+pub async fn login(
+    form: web::Json<Credentials>,
+    accounts: web::Data<Accounts>
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    let logged = web::block(move || accounts.login(&form.username, &form.password)).await?;
+
+    Ok(match logged {
+        Ok(Some((session, user))) => session_response(HttpResponse::Ok(), session, &user),
+        Ok(None) => HttpResponse::Unauthorized().json(json!({ "error": "Invalid username or password." })),
+        Err(err) => error_response(err)
+    })
+}
+
+/// Log out of the session of the cookie
+#[post("/api/logout")]
+pub async fn logout(request: HttpRequest, accounts: web::Data<Accounts>) -> Result<HttpResponse, actix_web::Error> {
+    if let Some(session) = session_id(&request) {
+        web::block(move || accounts.logout(&session)).await?
+            .map_err(error::ErrorInternalServerError)?;
+    }
+
+    let mut cookie = Cookie::build(SESSION_COOKIE, "").path("/").finish();
+    cookie.make_removal();
+    Ok(HttpResponse::NoContent().cookie(cookie).finish())
+}
+
+/// Category of the leaderboard, e.g. `/api/leaderboard?category=rapid`,
+/// blitz by default
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    pub category: Category
+}
+
+/// Best rated players of a category
+#[get("/api/leaderboard")]
+pub async fn leaderboard(
+    query: web::Query<LeaderboardQuery>,
+    accounts: web::Data<Accounts>
+) -> Result<HttpResponse, actix_web::Error> {
+    let category = query.category;
+    let standings = web::block(move || accounts.leaderboard(category)).await?;
+    Ok(match standings {
+        Ok(standings) => HttpResponse::Ok().json(standings),
+        Err(err) => error_response(err)
+    })
+}
+
+/// Get the signed-in user
+#[get("/api/me")]
+pub async fn me(request: HttpRequest, accounts: web::Data<Accounts>) -> Result<HttpResponse, actix_web::Error> {
+    Ok(match current_user(&request, &accounts).await? {
+        Some(user) => HttpResponse::Ok().json(Profile::from(&user)),
+        None => HttpResponse::Unauthorized().json(json!({ "error": "Not logged in." }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App, http::StatusCode};
+    use serde_json::Value;
+    use super::*;
+
+    #[actix_web::test]
+    async fn accounts_register_and_login() -> Result<(), Error> {
+        let accounts = Accounts::in_memory();
+        let user = accounts.register("alice", "correct horse", Some(" Alice "))?;
+        assert_eq!(user.display_name, "Alice");
+        assert!(user.password_hash.starts_with("$argon2"));
+
+        assert!(accounts.register("ALICE", "battery staple", None).is_err());
+        assert!(accounts.register("bo", "battery staple", None).is_err());
+        assert!(accounts.register("bob bob", "battery staple", None).is_err());
+        assert!(accounts.register("bob", "short", None).is_err());
+        assert_eq!(accounts.register("bob", "battery staple", None)?.display_name, "bob");
+
+        assert!(accounts.login("alice", "wrong password")?.is_none());
+        assert!(accounts.login("carol", "correct horse")?.is_none());
+        let (session, logged) = accounts.login("alice", "correct horse")?.unwrap();
+        assert_eq!(logged.id, user.id);
+        assert_eq!(accounts.authenticate(&session)?.map(|user| user.id), Some(user.id));
+
+        accounts.logout(&session)?;
+        assert!(accounts.authenticate(&session)?.is_none());
+        assert!(accounts.authenticate(&Uuid::new_v4())?.is_none());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn accounts_rate_game() -> Result<(), Error> {
+        let accounts = Accounts::in_memory();
+        let alice = accounts.register("alice", "correct horse", Some("Alice"))?;
+        let bob = accounts.register("bob", "battery staple", None)?;
+        assert_eq!(accounts.rating(&alice.id, Category::Rapid)?, Rating::default());
+
+        let (white, black) = accounts.rate_game(&alice.id, &bob.id, Category::Rapid, 1.0)?;
+        assert!(white.rating > black.rating);
+        assert_eq!(accounts.rating(&alice.id, Category::Rapid)?, white);
+        assert_eq!(accounts.rating(&bob.id, Category::Rapid)?, black);
+        assert_eq!(accounts.rating(&alice.id, Category::Blitz)?, Rating::default());
+
+        let (black, white) = accounts.rate_game(&bob.id, &alice.id, Category::Rapid, 0.5)?;
+        let standings = accounts.leaderboard(Category::Rapid)?;
+        assert_eq!(standings.iter().map(|standing| standing.username.as_str()).collect::<Vec<&str>>(), ["alice", "bob"]);
+        assert_eq!((standings[0].rating, standings[0].games), (white.rounded(), 2));
+        assert_eq!(standings[1].rating, black.rounded());
+        assert!(accounts.leaderboard(Category::Blitz)?.is_empty());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn accounts_api() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Accounts::in_memory()))
+                .service(register)
+                .service(login)
+                .service(logout)
+                .service(me)
+                .service(leaderboard)
+        ).await;
+
+        let body = json!({ "username": "alice", "password": "correct horse", "display_name": "Alice" });
+        let req = test::TestRequest::post().uri("/api/register").set_json(&body).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert!(resp.response().cookies().any(|cookie| cookie.name() == SESSION_COOKIE));
+        let req = test::TestRequest::post().uri("/api/register").set_json(&body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post().uri("/api/login")
+            .set_json(json!({ "username": "alice", "password": "wrong password" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post().uri("/api/login")
+            .set_json(json!({ "username": "alice", "password": "correct horse" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let session = resp.response().cookies().find(|cookie| cookie.name() == SESSION_COOKIE).unwrap().into_owned();
+
+        let req = test::TestRequest::get().uri("/api/me").cookie(session.clone()).to_request();
+        let profile: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(profile["display_name"], "Alice");
+        assert!(profile.get("password_hash").is_none());
+
+        let req = test::TestRequest::get().uri("/api/leaderboard?category=bullet").to_request();
+        let standings: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(standings, json!([]));
+
+        let req = test::TestRequest::post().uri("/api/logout").cookie(session.clone()).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+        let req = test::TestRequest::get().uri("/api/me").cookie(session).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,393 @@
+use std::time::Instant;
+use actix::Addr;
+use actix_web::{get, post, web, error, http::StatusCode, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use chess::{
+    game::prelude::{Board, Piece, Level, History},
+    error::Error::{self, *}
+};
+use crate::{
+    accounts::{current_user, is_account_token, Accounts},
+    lobby::Lobby,
+    message::{CreateGame, GetGame, GetPosition, ListGames, PlayMove},
+    model::{Game, Bot, Color, Status, Winner, Termination, TimeControl, ClockState},
+    ws::{bot_engine, PLAYER_TOKEN_COOKIE}
+};
+
+/// State of a game, with the board, the history and the clocks
+#[derive(Clone, Debug, Serialize)]
+pub struct GameView {
+    pub id: Uuid,
+    pub status: Status,
+    pub winner: Winner,
+    pub termination: Option<Termination>,
+    pub time_control: String,
+    pub turn: Color,
+    pub fen: String,
+    pub board: Board<Piece>,
+    pub history: Vec<History>,
+    pub clock: Option<ClockState>,
+    pub bot: Option<Bot>,
+    pub rated: bool,
+    pub created_at: DateTime<Utc>
+}
+
+impl GameView {
+    /// Create new `GameView` of the game in the room
+    pub fn new(id: Uuid, game: &Game) -> GameView {
+        GameView {
+            id,
+            status: game.status.clone(),
+            winner: game.winner.clone(),
+            termination: game.termination,
+            time_control: game.time_control.to_string(),
+            turn: game.side_to_move(),
+            fen: game.board.to_fen(),
+            board: game.board.clone(),
+            history: game.board.get_history().clone(),
+            clock: game.clock_state(Instant::now()),
+            bot: game.bot,
+            rated: game.rated,
+            created_at: game.created_at
+        }
+    }
+}
+
+/// Board of a game after the first `ply` moves, to review the game
+#[derive(Clone, Debug, Serialize)]
+pub struct PositionView {
+    pub id: Uuid,
+    pub ply: usize,
+    /// Number of moves played in the game
+    pub plies: usize,
+    /// Last move in SAN, `None` at the starting position
+    pub san: Option<String>,
+    pub turn: Color,
+    pub fen: String,
+    pub board: Board<Piece>
+}
+
+impl PositionView {
+    /// Create new `PositionView` of the game in the room
+    pub fn new(id: Uuid, game: &Game, ply: usize) -> Result<PositionView, Error> {
+        let (board, san) = game.position(ply)?;
+        Ok(PositionView {
+            id,
+            ply,
+            plies: game.board.get_history().len(),
+            san,
+            turn: Color::from_color(board.side_to_move()),
+            fen: board.to_fen(),
+            board
+        })
+    }
+}
+
+/// Options of a new room, the same as the websocket query
+#[derive(Debug, Deserialize)]
+pub struct NewGame {
+    tc: Option<String>,
+    opponent: Option<String>,
+    depth: Option<u32>,
+    time: Option<u64>,
+    rated: Option<bool>
+}
+
+/// Move given by its cells, e.g. `{"from":"e7","to":"e8","promotion":"Queen"}`,
+/// or in SAN, e.g. `{"san":"e8=Q"}`
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum MoveInput {
+    Cells { from: String, to: String, #[serde(default)] promotion: Option<Level> },
+    San { san: String }
+}
+
+/// Move of a player, who is the signed-in user, or the holder of the player
+/// `token` or of the cookie
+#[derive(Debug, Deserialize)]
+pub struct NewMove {
+    token: Option<Uuid>,
+    #[serde(flatten)]
+    input: MoveInput
+}
+
+/// JSON error response with the status matching the error
+pub fn error_response(err: Error) -> HttpResponse {
+    let status = match err {
+        StorageError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        GameError(_) | TurnError(_) => StatusCode::CONFLICT,
+        _ => StatusCode::BAD_REQUEST
+    };
+    HttpResponse::build(status).json(json!({ "error": err.to_string() }))
+}
+
+/// Response to invalid options
+fn bad_request(message: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({ "error": message }))
+}
+
+/// Response to a missing room
+fn not_found(id: Uuid) -> HttpResponse {
+    HttpResponse::NotFound().json(json!({ "error": format!("The game {} doesn't exist.", id) }))
+}
+
+/// Create a room, e.g. `{"tc":"5+3","opponent":"bot","depth":3}`, the players
+/// join it through the websocket
+#[post("/api/games")]
+pub async fn create_game(
+    options: web::Json<NewGame>,
+    lobby: web::Data<Addr<Lobby>>
+) -> Result<HttpResponse, actix_web::Error> {
+    let time_control = match &options.tc {
+        Some(tc) => match TimeControl::parse(tc) {
+            Ok(time_control) => time_control,
+            Err(err) => return Ok(bad_request(err.to_string()))
+        },
+        None => TimeControl::default()
+    };
+    let bot = match bot_engine(options.opponent.as_deref(), options.depth, options.time) {
+        Ok(bot) => bot,
+        Err(message) => return Ok(bad_request(message))
+    };
+
+    let rated = options.rated.unwrap_or(true);
+    let view = lobby.send(CreateGame { time_control, bot, rated }).await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created()
+        .insert_header(("Location", format!("/api/games/{}", view.id)))
+        .json(view))
+}
+
+/// Filter of the room listing, e.g. `/api/games?joinable=true`
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    #[serde(default)]
+    joinable: bool
+}
+
+/// List the open rooms, or only the ones waiting for a player
+#[get("/api/games")]
+pub async fn list_games(
+    query: web::Query<ListQuery>,
+    lobby: web::Data<Addr<Lobby>>
+) -> Result<HttpResponse, actix_web::Error> {
+    let games = lobby.send(ListGames { joinable: query.joinable }).await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(games))
+}
+
+/// Get the state of a game, open or saved
+#[get("/api/games/{id}")]
+pub async fn get_game(
+    id: web::Path<Uuid>,
+    lobby: web::Data<Addr<Lobby>>
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    let view = lobby.send(GetGame { room_id: id }).await.map_err(error::ErrorInternalServerError)?;
+    Ok(match view {
+        Ok(Some(view)) => HttpResponse::Ok().json(view),
+        Ok(None) => not_found(id),
+        Err(err) => error_response(err)
+    })
+}
+
+/// Get the board of a game, open or saved, after the first `ply` moves
+#[get("/api/games/{id}/positions/{ply}")]
+pub async fn get_position(
+    path: web::Path<(Uuid, usize)>,
+    lobby: web::Data<Addr<Lobby>>
+) -> Result<HttpResponse, actix_web::Error> {
+    let (id, ply) = path.into_inner();
+    let view = lobby.send(GetPosition { room_id: id, ply }).await.map_err(error::ErrorInternalServerError)?;
+    Ok(match view {
+        Ok(Some(view)) => HttpResponse::Ok().json(view),
+        Ok(None) => not_found(id),
+        Err(err) => error_response(err)
+    })
+}
+
+/// Play a move in an open room for the player holding the seat of the token
+#[post("/api/games/{id}/moves")]
+pub async fn play_move(
+    request: HttpRequest,
+    id: web::Path<Uuid>,
+    body: web::Json<NewMove>,
+    lobby: web::Data<Addr<Lobby>>,
+    accounts: web::Data<Accounts>
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    let body = body.into_inner();
+    let forbidden = |message: &str| HttpResponse::Forbidden().json(json!({ "error": message }));
+    let token = match current_user(&request, &accounts).await? {
+        // a signed-in player only plays its own seats
+        Some(user) => match body.token {
+            Some(token) if token != user.seat_token => {
+                return Ok(forbidden("The player token isn't the one of the signed-in user."));
+            },
+            _ => user.seat_token
+        },
+        None => {
+            let token = body.token
+                .or_else(|| request.cookie(PLAYER_TOKEN_COOKIE).and_then(|cookie| Uuid::parse_str(cookie.value()).ok()));
+            match token {
+                Some(token) if is_account_token(token, &accounts).await? => {
+                    return Ok(forbidden("The player token belongs to an account, log in to use it."));
+                },
+                Some(token) => token,
+                None => return Ok(forbidden("A player token is required."))
+            }
+        }
+    };
+
+    let view = lobby.send(PlayMove { room_id: id, token, input: body.input }).await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(match view {
+        Ok(Some(view)) => HttpResponse::Ok().json(view),
+        Ok(None) => not_found(id),
+        Err(err) => error_response(err)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+    use actix_web::{test, App};
+    use serde_json::Value;
+    use crate::accounts::SESSION_COOKIE;
+    use super::*;
+
+    #[actix_web::test]
+    async fn api_games() {
+        let lobby = Lobby::default().start();
+        let accounts = Accounts::in_memory();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(lobby.clone()))
+                .app_data(web::Data::new(accounts))
+                .service(create_game)
+                .service(list_games)
+                .service(get_game)
+                .service(get_position)
+                .service(play_move)
+        ).await;
+
+        let req = test::TestRequest::post().uri("/api/games")
+            .set_json(json!({ "tc": "5+3", "opponent": "bot", "depth": 2 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let created: Value = test::read_body_json(resp).await;
+        assert_eq!(created["status"], "waiting");
+        assert_eq!(created["time_control"], "5+3");
+        assert_eq!(created["bot"]["engine"]["depth"], 2);
+        let id = created["id"].as_str().unwrap().to_owned();
+
+        let req = test::TestRequest::get().uri("/api/games").to_request();
+        let games: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(games[0]["id"], id.as_str());
+        assert_eq!(games[0]["players"], 1);
+        assert_eq!(games[0]["status"], "waiting");
+
+        let req = test::TestRequest::get().uri("/api/games?joinable=true").to_request();
+        let games: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(games[0]["id"], id.as_str());
+
+        let req = test::TestRequest::get().uri(&format!("/api/games/{}", id)).to_request();
+        let game: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(game["fen"], "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(game["turn"], "white");
+
+        let req = test::TestRequest::get().uri(&format!("/api/games/{}/positions/0", id)).to_request();
+        let position: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(position["fen"], game["fen"]);
+        assert_eq!((&position["plies"], &position["san"]), (&json!(0), &Value::Null));
+        let req = test::TestRequest::get().uri(&format!("/api/games/{}/positions/1", id)).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+
+        let req = test::TestRequest::get().uri(&format!("/api/games/{}", Uuid::new_v4())).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+        let req = test::TestRequest::get().uri(&format!("/api/games/{}/positions/0", Uuid::new_v4())).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn api_bad_requests() {
+        let lobby = Lobby::default().start();
+        let accounts = Accounts::in_memory();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(lobby.clone()))
+                .app_data(web::Data::new(accounts))
+                .service(create_game)
+                .service(play_move)
+        ).await;
+
+        for body in [json!({ "tc": "fast" }), json!({ "opponent": "bot", "depth": 9 })] {
+            let req = test::TestRequest::post().uri("/api/games").set_json(body).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let req = test::TestRequest::post().uri("/api/games").set_json(json!({ "rated": false })).to_request();
+        let created: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(created["rated"], false);
+        let uri = format!("/api/games/{}/moves", created["id"].as_str().unwrap());
+
+        let req = test::TestRequest::post().uri(&uri).set_json(json!({ "san": "e4" })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+        let body = json!({ "token": Uuid::new_v4(), "from": "e2", "to": "e4" });
+        let req = test::TestRequest::post().uri(&uri).set_json(body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+
+        let body = json!({ "token": Uuid::new_v4(), "san": "e4" });
+        let req = test::TestRequest::post().uri(&format!("/api/games/{}/moves", Uuid::new_v4())).set_json(body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn api_account_tokens() {
+        let lobby = Lobby::default().start();
+        let accounts = web::Data::new(Accounts::in_memory());
+        let alice = accounts.register("alice", "correct horse", None).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(lobby.clone()))
+                .app_data(accounts.clone())
+                .service(create_game)
+                .service(play_move)
+        ).await;
+        let req = test::TestRequest::post().uri("/api/games").set_json(json!({})).to_request();
+        let created: Value = test::call_and_read_body_json(&app, req).await;
+        let uri = format!("/api/games/{}/moves", created["id"].as_str().unwrap());
+
+        // a guest can't play with the seat token of an account
+        let body = json!({ "token": alice.seat_token, "san": "e4" });
+        let req = test::TestRequest::post().uri(&uri).set_json(body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+        let cookie = actix_web::cookie::Cookie::new(PLAYER_TOKEN_COOKIE, alice.seat_token.to_string());
+        let req = test::TestRequest::post().uri(&uri).cookie(cookie).set_json(json!({ "san": "e4" })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+        // and the public id of the account holds no seat
+        let body = json!({ "token": alice.id, "san": "e4" });
+        let req = test::TestRequest::post().uri(&uri).set_json(body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+
+        // a signed-in player can't act for the seat of another token
+        let session = actix_web::cookie::Cookie::new(SESSION_COOKIE, accounts.open_session(&alice).unwrap().to_string());
+        let bob = accounts.register("bob", "battery staple", None).unwrap();
+        for token in [Uuid::new_v4(), bob.seat_token, alice.id] {
+            let body = json!({ "token": token, "san": "e4" });
+            let req = test::TestRequest::post().uri(&uri).cookie(session.clone()).set_json(body).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+        }
+        let body = json!({ "token": alice.seat_token, "san": "e4" });
+        let req = test::TestRequest::post().uri(&uri).cookie(session.clone()).set_json(body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+        let req = test::TestRequest::post().uri(&uri).cookie(session).set_json(json!({ "san": "e4" })).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+    }
+}
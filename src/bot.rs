@@ -0,0 +1,20 @@
+use actix::{Actor, Handler, SyncContext};
+use chess::{engine::BestMove, error::Error};
+use crate::message::Think;
+
+/// Worker searching the moves of the computer opponents, started in a
+/// `SyncArbiter` so a search doesn't block the lobby
+#[derive(Debug, Default)]
+pub struct BotWorker;
+
+impl Actor for BotWorker {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Think> for BotWorker {
+    type Result = Result<Option<BestMove>, Error>;
+
+    fn handle(&mut self, msg: Think, _: &mut SyncContext<Self>) -> Self::Result {
+        msg.engine.best_move(&msg.board)
+    }
+}
@@ -1,104 +1,864 @@
-use actix::{Recipient, Actor, Handler, Context};
+use actix::{
+    Recipient, Actor, Addr, Handler, Context, AsyncContext, SpawnHandle, MessageResult,
+    SyncArbiter, WrapFuture, ActorFutureExt, ContextFutureSpawner, fut
+};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use actix_web::web;
 use uuid::Uuid;
+use chess::{
+    engine::{BestMove, Engine},
+    game::prelude::{Level, Board},
+    notation::san,
+    error::Error::{self, *}
+};
 use crate::{
-    message::{WsMessage, Disconnect, Connect, ClientActorMessage},
-    model::{Game, Color, Status, Winner},
-    game::{color, prelude::Level}
+    accounts::Accounts,
+    api::{GameView, MoveInput, PositionView},
+    bot::BotWorker,
+    matchmaking::{Queue, Seeker},
+    message::{
+        WsMessage, Disconnect, Connect, ClientActorMessage, Think,
+        CreateGame, GetGame, GetPosition, ListGames, PlayMove, Seek, CancelSeek
+    },
+    model::{Game, GameSummary, Bot, Category, Color, Status, Winner, Termination, TimeControl},
+    protocol::{Protocol, Request, Response},
+    storage::Storage
 };
 
 type Socket = Recipient<WsMessage>;
 
-#[derive(Debug)]
+/// Rating in the category of the account holding the seat token, `None`
+/// for a guest or an empty seat
+fn seat_rating(accounts: &Accounts, token: Option<Uuid>, category: Category) -> Option<u32> {
+    let rating = accounts.seat_user(&token?)
+        .and_then(|user| user.map(|user| accounts.rating(&user.id, category)).transpose());
+    match rating {
+        Ok(rating) => rating.map(|rating| rating.rounded()),
+        Err(err) => {
+            println!("failed to load the rating of a seat: {}", err);
+            None
+        }
+    }
+}
+
+/// How long the seat of a disconnected player is held for its reconnection
+const GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Threads searching the moves of the computer opponents
+const BOT_WORKERS: usize = 2;
+
+/// How often the matchmaking queue is paired, so the waiting players
+/// accept wider rating ranges
+const MATCHMAKING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Connected client and the protocol it speaks
+struct Session {
+    socket: Socket,
+    protocol: Protocol,
+    /// Player token presented by the client to reclaim its seat
+    token: Uuid
+}
+
+impl std::fmt::Debug for Session {
+    // the player token is secret, it would give the seat away
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Session")
+            .field("socket", &self.socket)
+            .field("protocol", &self.protocol)
+            .finish_non_exhaustive()
+    }
+}
+
 pub struct Lobby {
-    sessions: HashMap<Uuid, Socket>,
-    rooms: HashMap<Uuid, Game>
+    sessions: HashMap<Uuid, Session>,
+    rooms: HashMap<Uuid, Game>,
+    /// Flag fall timers of the rooms with a running clock
+    timers: HashMap<Uuid, SpawnHandle>,
+    /// Release timers of the seats held for a reconnection, by player token
+    seat_timers: HashMap<Uuid, SpawnHandle>,
+    grace_period: Duration,
+    /// Where the games are saved, `None` to keep them only in memory
+    storage: Option<Box<dyn Storage>>,
+    /// Workers of the computer opponents, started with the lobby
+    bot_workers: Option<Addr<BotWorker>>,
+    /// Players seeking an opponent
+    queue: Queue,
+    /// Accounts of the signed-in players, whose ratings the rated games update
+    accounts: Option<Arc<Accounts>>
 }
 
 impl Default for Lobby {
-    fn default() -> Self {
+    fn default() -> Lobby {
         Lobby {
             sessions: HashMap::new(),
-            rooms: HashMap::new()
+            rooms: HashMap::new(),
+            timers: HashMap::new(),
+            seat_timers: HashMap::new(),
+            grace_period: GRACE_PERIOD,
+            storage: None,
+            bot_workers: None,
+            queue: Queue::default(),
+            accounts: None
         }
     }
 }
 
+impl std::fmt::Debug for Lobby {
+    // the held seats are keyed by their secret player tokens, only
+    // their number is shown
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Lobby")
+            .field("sessions", &self.sessions)
+            .field("rooms", &self.rooms)
+            .field("timers", &self.timers)
+            .field("held_seats", &self.seat_timers.len())
+            .field("grace_period", &self.grace_period)
+            .field("storage", &self.storage)
+            .field("bot_workers", &self.bot_workers)
+            .field("queue", &self.queue)
+            .field("accounts", &self.accounts)
+            .finish()
+    }
+}
+
 impl Lobby {
-    /// Send message to an actor given the Uuid
-    fn send_message(&self, message: &str, id_to: &Uuid) {
-        if let Some(socket_recipient) = self.sessions.get(id_to) {
-            let _ = socket_recipient.do_send(WsMessage(message.to_owned()));
+    /// Create new `Lobby` saving the games in the storage, with the rooms
+    /// in progress loaded back
+    pub fn with_storage(storage: Box<dyn Storage>) -> Result<Lobby, Error> {
+        let rooms = storage.load_in_progress()?
+            .into_iter()
+            .map(|(id, game)| (id, Lobby::reopen(game)))
+            .collect();
+
+        Ok(Lobby { rooms, storage: Some(storage), ..Lobby::default() })
+    }
+
+    /// Rate the games of the signed-in players in the accounts
+    pub fn with_accounts(self, accounts: Arc<Accounts>) -> Lobby {
+        Lobby { accounts: Some(accounts), ..self }
+    }
+
+    /// Create the game of a new room, the computer plays Black when `bot` is set
+    fn new_game(time_control: TimeControl, bot: Option<Engine>, rated: bool) -> Game {
+        let game = match bot {
+            Some(engine) => Game::with_bot(time_control, Bot { color: Color::Black, engine }),
+            None => Game::with_time_control(time_control)
+        };
+        Game { rated, ..game }
+    }
+
+    /// Reopen a saved game, its players have to reconnect to their seats
+    fn reopen(mut game: Game) -> Game {
+        game.players.clear();
+        game.spectators.clear();
+        if game.status == Status::Playing {
+            game.status = Status::Waiting;
+        }
+        game.stop_clock(Instant::now());
+        game
+    }
+
+    /// Save the game of the room, unless nothing has happened in it yet
+    fn save(&self, room_id: &Uuid) {
+        let (storage, game) = match (&self.storage, self.rooms.get(room_id)) {
+            (Some(storage), Some(game)) => (storage, game),
+            _ => return
+        };
+
+        if game.board.get_history().is_empty()
+        && game.board.start_fen.is_none()
+        && game.status != Status::GameOver {
+            return;
+        }
+
+        if let Err(err) = storage.save_game(room_id, game) {
+            println!("failed to save the game {}: {}", room_id, err);
+        }
+    }
+
+    /// Close the room when nobody is left in it and no seat is held, its
+    /// game stays saved
+    fn close_if_empty(&mut self, room_id: &Uuid) {
+        let empty = self.rooms.get(room_id).is_some_and(|game| {
+            game.sessions().next().is_none()
+            && !game.seats.keys().any(|token| self.seat_timers.contains_key(token))
+        });
+        if empty {
+            self.rooms.remove(room_id);
+        }
+    }
+
+    /// Hold the seat of the player token for the grace period
+    fn hold_seat(&mut self, room_id: Uuid, token: Uuid, ctx: &mut Context<Self>) {
+        if let Some(handle) = self.seat_timers.remove(&token) {
+            ctx.cancel_future(handle);
+        }
+
+        let handle = ctx.run_later(self.grace_period, move |act, ctx| {
+            act.seat_timers.remove(&token);
+            act.release_seat(room_id, token, ctx);
+        });
+        self.seat_timers.insert(token, handle);
+    }
+
+    /// Release the seat of a player who didn't reconnect in time
+    fn release_seat(&mut self, room_id: Uuid, token: Uuid, ctx: &mut Context<Self>) {
+        let released = self.rooms.get_mut(&room_id)
+            .is_some_and(|game| game.release_seat(&token, Instant::now()));
+
+        if released {
+            let status = self.rooms[&room_id].status.clone();
+            self.broadcast(&Response::Status { status }, &room_id);
+            self.save(&room_id);
+            self.schedule_flag(room_id, ctx);
+        }
+        self.close_if_empty(&room_id);
+    }
+
+    /// Load a saved game of the room if it isn't open
+    fn load(&mut self, room_id: &Uuid, ctx: &mut Context<Self>) {
+        if self.rooms.contains_key(room_id) {
+            return;
+        }
+
+        match self.storage.as_ref().map(|storage| storage.load_game(room_id)) {
+            Some(Ok(Some(game))) => {
+                let tokens = game.seats.keys().copied().collect::<Vec<Uuid>>();
+                self.rooms.insert(*room_id, Lobby::reopen(game));
+                for token in tokens {
+                    self.hold_seat(*room_id, token, ctx);
+                }
+            },
+            Some(Err(err)) => println!("failed to load the game {}: {}", room_id, err),
+            _ => ()
+        }
+    }
+
+    /// Send response to an actor given the Uuid, rendered for its protocol
+    fn send_response(&self, response: &Response, id_to: &Uuid) {
+        if let Some(session) = self.sessions.get(id_to) {
+            session.socket.do_send(WsMessage(response.render(session.protocol)));
         } else {
             println!("attempting to send message but couldn't find user id.");
         }
     }
+
+    /// Send response to all players and spectators in the room
+    fn broadcast(&self, response: &Response, room_id: &Uuid) {
+        if let Some(game) = self.rooms.get(room_id) {
+            for pid in game.sessions() {
+                self.send_response(response, pid);
+            }
+        }
+    }
+
+    /// Display names of the players of the game, with the ratings of the
+    /// signed-in ones
+    fn players_response(game: &Game, white_rating: Option<u32>, black_rating: Option<u32>) -> Response {
+        Response::Players {
+            white: game.player_name(Color::White).map(str::to_owned),
+            black: game.player_name(Color::Black).map(str::to_owned),
+            white_rating,
+            black_rating,
+            rated: game.rated
+        }
+    }
+
+    /// Show the players of the room with their ratings in its category
+    ///
+    /// The accounts are looked up on the blocking threads, the players are
+    /// only shown if they are still seated when the ratings come back.
+    fn broadcast_players(&self, room_id: Uuid, ctx: &mut Context<Self>) {
+        let game = match self.rooms.get(&room_id) {
+            Some(game) => game,
+            None => return
+        };
+        let accounts = match &self.accounts {
+            Some(accounts) => accounts.clone(),
+            None => return self.broadcast(&Lobby::players_response(game, None, None), &room_id)
+        };
+
+        let seated = |game: &Game| (game.signed_in_seat(Color::White), game.signed_in_seat(Color::Black));
+        let seats = seated(game);
+        let category = game.time_control.category();
+        web::block(move || (seat_rating(&accounts, seats.0, category), seat_rating(&accounts, seats.1, category)))
+            .into_actor(self)
+            .then(move |res, act, _ctx| {
+                match (res, act.rooms.get(&room_id)) {
+                    (Ok((white, black)), Some(game)) if seated(game) == seats => {
+                        act.broadcast(&Lobby::players_response(game, white, black), &room_id);
+                    },
+                    (Err(err), _) => println!("failed to look up the ratings of the room {}: {}", room_id, err),
+                    _ => ()
+                }
+                fut::ready(())
+            })
+            .spawn(ctx);
+    }
+
+    /// Update the ratings of the players of a rated game which has just
+    /// ended on the blocking threads, and show them the new ones
+    fn rate(&self, room_id: Uuid, ctx: &mut Context<Self>) {
+        let (accounts, game) = match (&self.accounts, self.rooms.get(&room_id)) {
+            (Some(accounts), Some(game)) => (accounts.clone(), game),
+            _ => return
+        };
+        let (white, black, score) = match game.rated_result() {
+            Some(result) => result,
+            None => return
+        };
+
+        let category = game.time_control.category();
+        let rate_game = move || match (accounts.seat_user(&white)?, accounts.seat_user(&black)?) {
+            (Some(white), Some(black)) => accounts.rate_game(&white.id, &black.id, category, score).map(Some),
+            _ => Ok(None)
+        };
+        web::block(rate_game)
+            .into_actor(self)
+            .then(move |res, act, ctx| {
+                match res {
+                    Ok(Ok(Some(_ratings))) => act.broadcast_players(room_id, ctx),
+                    Ok(Ok(None)) => (),
+                    Ok(Err(err)) => println!("failed to rate the game {}: {}", room_id, err),
+                    Err(err) => println!("the rating of the game {} was cancelled: {}", room_id, err)
+                }
+                fut::ready(())
+            })
+            .spawn(ctx);
+    }
+
+    /// Look at the game of an open room, or a saved one, `None` when
+    /// there is neither
+    fn find_game<T>(&self, room_id: &Uuid, f: impl FnOnce(&Game) -> T) -> Result<Option<T>, Error> {
+        if let Some(game) = self.rooms.get(room_id) {
+            return Ok(Some(f(game)));
+        }
+
+        match &self.storage {
+            Some(storage) => Ok(storage.load_game(room_id)?.map(|game| f(&game))),
+            None => Ok(None)
+        }
+    }
+
+    /// Get the game of the room
+    fn room(&self, room_id: &Uuid) -> Result<&Game, Error> {
+        self.rooms.get(room_id).ok_or_else(|| GameError("The room doesn't exist.".to_owned()))
+    }
+
+    /// Get the mutable game of the room
+    fn room_mut(&mut self, room_id: &Uuid) -> Result<&mut Game, Error> {
+        self.rooms.get_mut(room_id).ok_or_else(|| GameError("The room doesn't exist.".to_owned()))
+    }
+
+    /// Board response of the room with the remaining time of both players
+    fn board_response(game: &Game) -> Response {
+        Response::Board {
+            board: game.board.clone(),
+            clock: game.clock_state(Instant::now())
+        }
+    }
+
+    /// Game state response with the full history of the game
+    fn game_state_response(game: &Game) -> Response {
+        Response::GameState {
+            status: game.status.clone(),
+            winner: game.winner.clone(),
+            termination: game.termination,
+            time_control: game.time_control.to_string(),
+            history: game.board.get_history().clone(),
+            spectators: game.spectators.len()
+        }
+    }
+
+    /// Result response of a finished game
+    fn result_response(game: &Game) -> Response {
+        Response::Result { winner: game.winner.clone(), termination: game.termination }
+    }
+
+    /// Schedule the flag fall of the side to move in the room
+    fn schedule_flag(&mut self, room_id: Uuid, ctx: &mut Context<Self>) {
+        if let Some(handle) = self.timers.remove(&room_id) {
+            ctx.cancel_future(handle);
+        }
+
+        let time_to_flag = self.rooms.get(&room_id)
+            .and_then(|game| game.time_to_flag(Instant::now()));
+        if let Some(remaining) = time_to_flag {
+            let handle = ctx.run_later(remaining, move |act, ctx| {
+                act.timers.remove(&room_id);
+                act.check_flag(room_id, ctx);
+            });
+            self.timers.insert(room_id, handle);
+        }
+    }
+
+    /// End the game in the room if the side to move has run out of time
+    fn check_flag(&mut self, room_id: Uuid, ctx: &mut Context<Self>) -> bool {
+        let game = match self.rooms.get_mut(&room_id) {
+            Some(game) => game,
+            None => return false
+        };
+
+        match game.check_flag(Instant::now()) {
+            Some(color) => {
+                let game = &self.rooms[&room_id];
+                let responses = [
+                    Response::Flagged { color },
+                    Lobby::result_response(game),
+                    Response::Status { status: game.status.clone() },
+                    Lobby::board_response(game)
+                ];
+                for response in responses.iter() {
+                    self.broadcast(response, &room_id);
+                }
+                self.save(&room_id);
+                self.rate(room_id, ctx);
+                true
+            },
+            None => {
+                self.schedule_flag(room_id, ctx);
+                false
+            }
+        }
+    }
+
+    /// Play a move of the player in the room and broadcast it
+    ///
+    /// A castling is the king moving two cells towards its rook.
+    fn play_move(
+        &mut self,
+        id: &Uuid,
+        room_id: &Uuid,
+        from: &str,
+        to: &str,
+        promotion: Option<Level>,
+        ctx: &mut Context<Self>
+    ) -> Result<(), Error> {
+        if self.check_flag(*room_id, ctx) {
+            return Ok(());
+        }
+
+        let color = self.room(room_id)?.validate_move(id, from)?;
+        self.play_legal_move(room_id, color, from, to, promotion, ctx)
+    }
+
+    /// Play a move of the color in the room when it's legal
+    fn play_legal_move(
+        &mut self,
+        room_id: &Uuid,
+        color: Color,
+        from: &str,
+        to: &str,
+        promotion: Option<Level>,
+        ctx: &mut Context<Self>
+    ) -> Result<(), Error> {
+        if !self.room(room_id)?.board.legal_moves(from)?.iter().any(|m| m == to) {
+            return Err(IllegalMoves(format!("can't move {} to {}", from, to)));
+        }
+
+        self.apply_move(room_id, color, from, to, promotion, ctx)
+    }
+
+    /// Apply a validated move of the color in the room, broadcast it, and
+    /// let the computer opponent answer
+    fn apply_move(
+        &mut self,
+        room_id: &Uuid,
+        color: Color,
+        from: &str,
+        to: &str,
+        promotion: Option<Level>,
+        ctx: &mut Context<Self>
+    ) -> Result<(), Error> {
+        let game = self.room_mut(room_id)?;
+        let notation = san::to_san(&game.board, from, to, promotion).ok();
+        let description = game.board.moves_piece(from, to, promotion)?;
+        game.press_clock(color, Instant::now());
+
+        // moving instead of answering declines the opponent's draw offer
+        if game.draw_offer == Some(color.opposite()) {
+            game.draw_offer = None;
+        }
+
+        let enemy_color = color.opposite().as_color();
+        let checkmate = game.board.is_checkmate(enemy_color)?;
+        let check = game.board.is_king_checked(enemy_color)?;
+        let mut draw = None;
+        if checkmate {
+            game.finish(Winner::from_color(color), Termination::Checkmate);
+        } else if let Some(reason) = game.board.draw_reason(enemy_color)? {
+            game.finish(Winner::Draw, Termination::from_draw_reason(reason));
+            draw = Some(reason);
+        }
+
+        let game = self.room(room_id)?;
+        let mut responses = vec![
+            Response::Moved { description, san: notation, check, checkmate, draw },
+            Lobby::board_response(game)
+        ];
+        let over = game.status == Status::GameOver;
+        if over {
+            responses.push(Lobby::result_response(game));
+        }
+        for response in responses.iter() {
+            self.broadcast(response, room_id);
+        }
+        self.save(room_id);
+        if over {
+            self.rate(*room_id, ctx);
+        }
+        self.schedule_flag(*room_id, ctx);
+        self.bot_turn(*room_id, ctx);
+        Ok(())
+    }
+
+    /// Ask the workers for the move of the computer opponent when it's to move
+    ///
+    /// The lobby keeps handling the other rooms while the engine thinks.
+    fn bot_turn(&mut self, room_id: Uuid, ctx: &mut Context<Self>) {
+        let (game, workers) = match (self.rooms.get(&room_id), &self.bot_workers) {
+            (Some(game), Some(workers)) => (game, workers),
+            _ => return
+        };
+        let bot = match game.bot_to_move() {
+            Some(bot) => bot,
+            None => return
+        };
+
+        let ply = game.board.get_history().len();
+        workers.send(Think { engine: bot.engine, board: game.board.clone() })
+            .into_actor(self)
+            .then(move |res, act, ctx| {
+                match res {
+                    Ok(Ok(Some(best))) => act.play_bot_move(room_id, ply, best, ctx),
+                    Ok(Ok(None)) => (),
+                    Ok(Err(err)) => println!("the engine failed in the room {}: {}", room_id, err),
+                    Err(err) => println!("the engine is unreachable: {}", err)
+                }
+                fut::ready(())
+            })
+            .spawn(ctx);
+    }
+
+    /// Play the move found by the engine, unless the position has changed
+    /// while it was thinking
+    fn play_bot_move(&mut self, room_id: Uuid, ply: usize, best: BestMove, ctx: &mut Context<Self>) {
+        let bot = match self.rooms.get(&room_id) {
+            Some(game) if game.board.get_history().len() == ply => game.bot_to_move(),
+            _ => None
+        };
+        let bot = match bot {
+            Some(bot) if !self.check_flag(room_id, ctx) => bot,
+            _ => return
+        };
+
+        if let Err(err) = self.apply_move(&room_id, bot.color, &best.from, &best.to, best.promotion, ctx) {
+            println!("failed to play the engine move in the room {}: {}", room_id, err);
+        }
+    }
+
+    /// Open a room for each pair of seekers, with their seats held until
+    /// they join it, and send them its id
+    fn match_seekers(&mut self, ctx: &mut Context<Self>) {
+        for (white, black) in self.queue.pair(Instant::now()) {
+            let room_id = Uuid::new_v4();
+            let mut game = Game::with_time_control(white.time_control);
+            game.seats.insert(white.token, Color::White);
+            game.seats.insert(black.token, Color::Black);
+            self.rooms.insert(room_id, game);
+
+            for (seeker, color) in [(white, Color::White), (black, Color::Black)] {
+                self.hold_seat(room_id, seeker.token, ctx);
+                self.send_response(&Response::Matched { room: room_id, color }, &seeker.id);
+                self.sessions.remove(&seeker.id);
+            }
+        }
+    }
+
+    /// Handle a request of the player in the room
+    fn handle_request(
+        &mut self,
+        id: Uuid,
+        room_id: Uuid,
+        request: Request,
+        ctx: &mut Context<Self>
+    ) -> Result<(), Error> {
+        if request.is_player_action() && self.room(&room_id)?.is_spectator(&id) {
+            return Err(GameError("Spectators can only watch the game.".to_owned()));
+        }
+
+        match request {
+            Request::Whisper { to, message } => {
+                self.send_response(&Response::Chat { from: id, message }, &to);
+            },
+
+            Request::Chat { message } => {
+                let game = self.room(&room_id)?;
+                let response = Response::Chat { from: id, message };
+                game.sessions()
+                    .filter(|pid| **pid != id)
+                    .for_each(|pid| self.send_response(&response, pid));
+            },
+
+            Request::GetLobby => {
+                let response = Response::Lobby { dump: format!("{:#?}", &self) };
+                self.send_response(&response, &id);
+            },
+
+            Request::GetAvailableGames => {
+                let mut rooms = self.rooms.iter()
+                    .filter(|(rid, game)| **rid != room_id && game.is_joinable())
+                    .map(|(rid, game)| GameSummary::new(*rid, game))
+                    .collect::<Vec<GameSummary>>();
+                rooms.sort_by_key(|room| room.created_at);
+                self.send_response(&Response::Rooms { rooms }, &id);
+            },
+
+            Request::GetGameState => {
+                let response = Lobby::game_state_response(self.room(&room_id)?);
+                self.send_response(&response, &id);
+            },
+
+            Request::GetColor => {
+                let color = *self.room(&room_id)?.players.get(&id)
+                    .ok_or_else(|| GameError("You are not a player in this game.".to_owned()))?;
+                self.send_response(&Response::Color { color }, &id);
+            },
+
+            Request::GetBoard => {
+                let response = Lobby::board_response(self.room(&room_id)?);
+                self.send_response(&response, &id);
+            },
+
+            Request::GetStatus => {
+                let status = self.room(&room_id)?.status.clone();
+                self.broadcast(&Response::Status { status }, &room_id);
+            },
+
+            Request::GetFen => {
+                let fen = self.room(&room_id)?.board.to_fen();
+                self.send_response(&Response::Fen { fen }, &id);
+            },
+
+            Request::LoadFen { fen } => {
+                let game = self.room_mut(&room_id)?;
+                if game.rated {
+                    return Err(GameError("Can't load a position in a rated game.".to_owned()));
+                }
+                if !game.players.contains_key(&id)
+                || !game.board.get_history().is_empty()
+                || game.status == Status::GameOver {
+                    return Err(GameError("Can't load a position after the game has started.".to_owned()));
+                }
+                game.board = Board::from_fen(&fen)?;
+                game.start_clock(Instant::now());
+                let response = Lobby::board_response(game);
+                self.broadcast(&response, &room_id);
+                self.save(&room_id);
+                self.schedule_flag(room_id, ctx);
+                self.bot_turn(room_id, ctx);
+            },
+
+            Request::GetPgn => {
+                let pgn = self.room(&room_id)?.to_pgn()?;
+                self.send_response(&Response::Pgn { pgn }, &id);
+            },
+
+            Request::LoadPgn { pgn } => {
+                let game = self.room_mut(&room_id)?;
+                if game.rated {
+                    return Err(GameError("Can't load a game in a rated game.".to_owned()));
+                }
+                if !game.players.contains_key(&id)
+                || !game.board.get_history().is_empty()
+                || game.status == Status::GameOver {
+                    return Err(GameError("Can't load a game after the game has started.".to_owned()));
+                }
+                let loaded = Game::from_pgn(&pgn)?;
+                if loaded.status == Status::GameOver {
+                    return Err(GameError("Can't load a game which is over.".to_owned()));
+                }
+                game.board = loaded.board;
+                game.start_clock(Instant::now());
+                let response = Lobby::board_response(game);
+                self.broadcast(&response, &room_id);
+                self.save(&room_id);
+                self.schedule_flag(room_id, ctx);
+                self.bot_turn(room_id, ctx);
+            },
+
+            Request::Move { from, to, promotion } => {
+                self.play_move(&id, &room_id, &from, &to, promotion, ctx)?;
+            },
+
+            Request::MoveSan { san } => {
+                let (from, to, promotion) = san::parse_san(&self.room(&room_id)?.board, &san)?;
+                self.play_move(&id, &room_id, &from, &to, promotion, ctx)?;
+            },
+
+            Request::Castle { king, rook } => {
+                let (to, _rook_to) = self.room(&room_id)?.board.castling_cells(&king, &rook)?;
+                self.play_move(&id, &room_id, &king, &to, None, ctx)?;
+            },
+
+            Request::Resign => {
+                let game = self.room_mut(&room_id)?;
+                let color = game.resign(&id)?;
+                let responses = [
+                    Response::Resigned { color },
+                    Lobby::result_response(game),
+                    Response::Status { status: game.status.clone() }
+                ];
+                for response in responses.iter() {
+                    self.broadcast(response, &room_id);
+                }
+                self.save(&room_id);
+                self.rate(room_id, ctx);
+                self.schedule_flag(room_id, ctx);
+            },
+
+            Request::OfferDraw => {
+                let color = self.room_mut(&room_id)?.offer_draw(&id)?;
+                self.broadcast(&Response::DrawOffered { color }, &room_id);
+            },
+
+            Request::AcceptDraw => {
+                let game = self.room_mut(&room_id)?;
+                let color = game.accept_draw(&id)?;
+                let responses = [
+                    Response::DrawAccepted { color },
+                    Lobby::result_response(game),
+                    Response::Status { status: game.status.clone() }
+                ];
+                for response in responses.iter() {
+                    self.broadcast(response, &room_id);
+                }
+                self.save(&room_id);
+                self.rate(room_id, ctx);
+                self.schedule_flag(room_id, ctx);
+            },
+
+            Request::DeclineDraw => {
+                let color = self.room_mut(&room_id)?.decline_draw(&id)?;
+                self.broadcast(&Response::DrawDeclined { color }, &room_id);
+            },
+
+            Request::GetPossibleMoves { cell } => {
+                let moves = self.room(&room_id)?.board.legal_moves(&cell).unwrap_or_default();
+                self.send_response(&Response::PossibleMoves { cell, moves }, &id);
+            },
+
+            Request::GetCaptured { color } => {
+                let pieces = self.room(&room_id)?.board.get_captured(color.as_color())?;
+                self.broadcast(&Response::Captured { color, pieces }, &room_id);
+            },
+
+            Request::GetPosition { ply } => {
+                let (board, san) = self.room(&room_id)?.position(ply)?;
+                let fen = board.to_fen();
+                self.send_response(&Response::Position { ply, san, fen, board }, &id);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Actor for Lobby {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.bot_workers = Some(SyncArbiter::start(BOT_WORKERS, || BotWorker));
+
+        // the players of the reopened rooms have the grace period to reconnect
+        let seats = self.rooms.iter()
+            .flat_map(|(room_id, game)| game.seats.keys().map(|token| (*room_id, *token)))
+            .collect::<Vec<(Uuid, Uuid)>>();
+        for (room_id, token) in seats {
+            self.hold_seat(room_id, token, ctx);
+        }
+
+        ctx.run_interval(MATCHMAKING_INTERVAL, |act, ctx| act.match_seekers(ctx));
+    }
 }
 
 impl Handler<Connect> for Lobby {
     type Result = ();
 
-    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: Connect, ctx: &mut Context<Self>) -> Self::Result {
+        self.load(&msg.lobby_id, ctx);
         let game = self.rooms
             .entry(msg.lobby_id)
-            .or_insert_with(Game::new);
-
-        if game.players.len() == 0 {
-            game.players.insert(msg.self_id, Color::White);
-        } else if game.status == Status::GameOver {
-            let key = game.players.keys().collect::<Vec<_>>()[0];
-            let color = game.players.get(key).unwrap().opposite();
-            game.players.insert(msg.self_id, color);
-        } else if game.status == Status::Waiting {
-            let key = game.players.keys().collect::<Vec<_>>()[0];
-            let color = game.players.get(key).unwrap().opposite();
-            game.players.insert(msg.self_id, color);
-            game.status = Status::Playing;
-        } else {
-            return ();
+            .or_insert_with(|| Lobby::new_game(msg.time_control, msg.bot, msg.rated));
+
+        let reconnected = game.seats.contains_key(&msg.token);
+        let color = game.join(msg.self_id, msg.token, Instant::now());
+        if color.is_some() {
+            match msg.name {
+                Some(name) => game.names.insert(msg.token, name),
+                None => game.names.remove(&msg.token)
+            };
+        }
+        if let Some(handle) = self.seat_timers.remove(&msg.token) {
+            ctx.cancel_future(handle);
         }
 
-        self.rooms.get(&msg.lobby_id).unwrap()
-            .players
-            .iter()
-            .filter(|p| p.0 != &msg.self_id)
-            .for_each(|p| self.send_message(&format!("{} just joined!", msg.self_id), p.0));
+        let session = Session { socket: msg.addr, protocol: msg.protocol, token: msg.token };
+        self.sessions.insert(msg.self_id, session);
+        self.send_response(&Response::Session { id: msg.self_id, spectator: color.is_none() }, &msg.self_id);
 
-        self.sessions.insert(msg.self_id, msg.addr);
-        self.send_message(&format!("your session_id is {}", msg.self_id), &msg.self_id);
+        let game = &self.rooms[&msg.lobby_id];
+        match color {
+            None => {
+                self.broadcast(&Response::Spectators { count: game.spectators.len() }, &msg.lobby_id);
+            },
+            Some(color) => {
+                game.sessions()
+                    .filter(|id| *id != &msg.self_id)
+                    .for_each(|id| self.send_response(&Response::Joined { id: msg.self_id }, id));
+
+                // resync the reconnected player with the full state of the game
+                if reconnected {
+                    let responses = [
+                        Response::Color { color },
+                        Lobby::game_state_response(game),
+                        Lobby::board_response(game)
+                    ];
+                    for response in responses.iter() {
+                        self.send_response(response, &msg.self_id);
+                    }
+                }
+                self.broadcast_players(msg.lobby_id, ctx);
+            }
+        }
+        self.save(&msg.lobby_id);
+        self.schedule_flag(msg.lobby_id, ctx);
+        self.bot_turn(msg.lobby_id, ctx);
     }
 }
 
 impl Handler<Disconnect> for Lobby {
     type Result = ();
 
-    fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) {
-        if self.sessions.remove(&msg.id).is_some() {
-            self.rooms
-                .get_mut(&msg.room_id).unwrap()
-                .players.remove(&msg.id).unwrap();
+    fn handle(&mut self, msg: Disconnect, ctx: &mut Context<Self>) {
+        let session = match self.sessions.remove(&msg.id) {
+            Some(session) => session,
+            None => return
+        };
 
-            if self.rooms.get_mut(&msg.room_id).unwrap().status != Status::GameOver {
-                self.rooms.get_mut(&msg.room_id).unwrap().status = Status::Waiting;
-            }
-
-            let game = self.rooms.get(&msg.room_id).unwrap();
-            let total_players = game.players.len();
-
-            if total_players == 1 {
-                game.players
-                    .iter()
-                    .for_each(|p| {
-                        self.send_message(&format!("{} disconnected.", msg.id), p.0);
-                        self.send_message(&format!("status: {}", game.status.as_str()), p.0);
-                    });
-            } else if total_players == 0 {
-                self.rooms.remove(&msg.room_id);
+        if let Some(game) = self.rooms.get_mut(&msg.room_id) {
+            if game.spectators.remove(&msg.id) {
+                let response = Response::Spectators { count: game.spectators.len() };
+                self.broadcast(&response, &msg.room_id);
+            } else if game.players.remove(&msg.id).is_some() {
+                // the seat is held while the player reconnects
+                self.hold_seat(msg.room_id, session.token, ctx);
+                self.broadcast(&Response::Disconnected { id: msg.id }, &msg.room_id);
+                self.save(&msg.room_id);
             }
+            self.close_if_empty(&msg.room_id);
         }
     }
 }
@@ -106,172 +866,671 @@ impl Handler<Disconnect> for Lobby {
 impl Handler<ClientActorMessage> for Lobby {
     type Result = ();
 
-    fn handle(&mut self, msg: ClientActorMessage, _ctx: &mut Context<Self>) -> Self::Result {
-        if msg.msg.starts_with("\\w") {
-            if let Some(id_to) = msg.msg.split(' ').collect::<Vec<&str>>().get(1) {
-                self.send_message(&msg.msg, &Uuid::parse_str(id_to).unwrap());
+    fn handle(&mut self, msg: ClientActorMessage, ctx: &mut Context<Self>) -> Self::Result {
+        let protocol = self.sessions.get(&msg.id)
+            .map(|session| session.protocol)
+            .unwrap_or_default();
+
+        let result = Request::parse(&msg.msg, protocol)
+            .and_then(|request| self.handle_request(msg.id, msg.room_id, request, ctx));
+
+        if let Err(err) = result {
+            self.send_response(&Response::from(err), &msg.id);
+        }
+    }
+}
+
+impl Handler<CreateGame> for Lobby {
+    type Result = MessageResult<CreateGame>;
+
+    fn handle(&mut self, msg: CreateGame, ctx: &mut Context<Self>) -> Self::Result {
+        let room_id = Uuid::new_v4();
+        let game = Lobby::new_game(msg.time_control, msg.bot, msg.rated);
+        let view = GameView::new(room_id, &game);
+        self.rooms.insert(room_id, game);
+
+        // the room is closed when nobody has joined it in the grace period
+        ctx.run_later(self.grace_period, move |act, _ctx| act.close_if_empty(&room_id));
+        MessageResult(view)
+    }
+}
+
+impl Handler<GetGame> for Lobby {
+    type Result = Result<Option<GameView>, Error>;
+
+    fn handle(&mut self, msg: GetGame, _: &mut Context<Self>) -> Self::Result {
+        self.find_game(&msg.room_id, |game| GameView::new(msg.room_id, game))
+    }
+}
+
+impl Handler<GetPosition> for Lobby {
+    type Result = Result<Option<PositionView>, Error>;
+
+    fn handle(&mut self, msg: GetPosition, _: &mut Context<Self>) -> Self::Result {
+        self.find_game(&msg.room_id, |game| PositionView::new(msg.room_id, game, msg.ply))?
+            .transpose()
+    }
+}
+
+impl Handler<ListGames> for Lobby {
+    type Result = MessageResult<ListGames>;
+
+    fn handle(&mut self, msg: ListGames, _: &mut Context<Self>) -> Self::Result {
+        let mut games = self.rooms.iter()
+            .filter(|(_id, game)| game.status != Status::GameOver)
+            .filter(|(_id, game)| !msg.joinable || game.is_joinable())
+            .map(|(id, game)| GameSummary::new(*id, game))
+            .collect::<Vec<GameSummary>>();
+        games.sort_by_key(|game| game.created_at);
+        MessageResult(games)
+    }
+}
+
+impl Handler<PlayMove> for Lobby {
+    type Result = Result<Option<GameView>, Error>;
+
+    fn handle(&mut self, msg: PlayMove, ctx: &mut Context<Self>) -> Self::Result {
+        if !self.rooms.contains_key(&msg.room_id) {
+            return Ok(None);
+        }
+
+        self.check_flag(msg.room_id, ctx);
+        let game = self.room(&msg.room_id)?;
+        let (from, to, promotion) = match msg.input {
+            MoveInput::Cells { from, to, promotion } => (from, to, promotion),
+            MoveInput::San { san } => san::parse_san(&game.board, &san)?
+        };
+        let color = game.validate_seat_move(&msg.token, &from)?;
+        self.play_legal_move(&msg.room_id, color, &from, &to, promotion, ctx)?;
+        Ok(Some(GameView::new(msg.room_id, self.room(&msg.room_id)?)))
+    }
+}
+
+impl Handler<Seek> for Lobby {
+    type Result = ();
+
+    fn handle(&mut self, msg: Seek, ctx: &mut Context<Self>) {
+        let session = Session { socket: msg.addr, protocol: msg.protocol, token: msg.token };
+        self.sessions.insert(msg.id, session);
+        self.queue.push(Seeker {
+            id: msg.id,
+            token: msg.token,
+            time_control: msg.time_control,
+            rating: msg.rating,
+            since: Instant::now()
+        });
+
+        let response = Response::Seeking { time_control: msg.time_control.to_string(), rating: msg.rating };
+        self.send_response(&response, &msg.id);
+        self.match_seekers(ctx);
+    }
+}
+
+impl Handler<CancelSeek> for Lobby {
+    type Result = ();
+
+    fn handle(&mut self, msg: CancelSeek, _: &mut Context<Self>) {
+        if self.queue.remove(&msg.id).is_some() {
+            self.sessions.remove(&msg.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Addr;
+    use actix_web::rt::time::sleep;
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use crate::{model::{Category, TimeControl}, rating::Rating, storage::SqliteStorage};
+    use super::*;
+
+    const COMMANDS: [&str; 25] = [
+        "\\w", "\\get_lobby", "\\get_available_games", "\\get_game_state", "\\get_color",
+        "\\get_board", "\\get_status", "\\get_fen", "\\get_pgn", "\\get_possible_moves",
+        "\\get_captured", "\\get_position", "\\load_fen", "\\load_pgn", "\\move", "\\move_san", "\\castling",
+        "\\resign", "\\offer_draw", "\\accept_draw", "\\decline_draw",
+        "\\", "\\unknown", "hello", ""
+    ];
+
+    const TYPES: [&str; 26] = [
+        "whisper", "chat", "get_lobby", "get_available_games", "get_game_state", "get_color",
+        "get_board", "get_status", "get_fen", "get_pgn", "get_possible_moves", "get_captured", "get_position",
+        "load_fen", "load_pgn", "move", "move_san", "castle", "resign",
+        "offer_draw", "accept_draw", "decline_draw", "", "unknown", "moved", "error"
+    ];
+
+    const ARGS: [&str; 32] = [
+        "e2", "e4", "e1", "h1", "a1", "g1", "e7", "e5", "a7", "a8", "h8", "0", "2",
+        "", "e", "e22", "i9", "a0", "é1", "1e", "Nf3", "O-O", "exd6", "e8=Q",
+        "queen", "knight", "king", "white", "black", "8/8/8/8/8/8/8/8", "1. e4 e5 2. Nf3 *",
+        "4k3/P7/8/8/8/8/8/4K3 w - - 0 1"
+    ];
+
+    /// Client actor collecting every message the lobby sends to it
+    struct Client(Arc<Mutex<Vec<String>>>);
+
+    impl Actor for Client {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<WsMessage> for Client {
+        type Result = ();
+
+        fn handle(&mut self, msg: WsMessage, _: &mut Context<Self>) {
+            self.0.lock().unwrap().push(msg.0);
+        }
+    }
+
+    async fn connect(lobby: &Addr<Lobby>, room_id: Uuid, protocol: Protocol) -> (Uuid, Arc<Mutex<Vec<String>>>) {
+        connect_with_token(lobby, room_id, protocol, Uuid::new_v4()).await
+    }
+
+    async fn connect_with_token(
+        lobby: &Addr<Lobby>,
+        room_id: Uuid,
+        protocol: Protocol,
+        token: Uuid
+    ) -> (Uuid, Arc<Mutex<Vec<String>>>) {
+        connect_user(lobby, room_id, protocol, token, None).await
+    }
+
+    async fn connect_user(
+        lobby: &Addr<Lobby>,
+        room_id: Uuid,
+        protocol: Protocol,
+        token: Uuid,
+        name: Option<&str>
+    ) -> (Uuid, Arc<Mutex<Vec<String>>>) {
+        let received = Arc::new(Mutex::new(vec![]));
+        let id = Uuid::new_v4();
+        let addr = Client(received.clone()).start();
+        lobby.send(Connect {
+            addr: addr.recipient(),
+            lobby_id: room_id,
+            self_id: id,
+            token,
+            name: name.map(str::to_owned),
+            time_control: TimeControl::Correspondence,
+            bot: None,
+            rated: true,
+            protocol
+        }).await.expect("lobby is alive");
+        (id, received)
+    }
+
+    fn random_arg(rng: &mut StdRng) -> String {
+        match rng.gen_range(0..10) {
+            0 => Uuid::new_v4().to_string(),
+            1 => (0..rng.gen_range(0..12)).map(|_| rng.gen::<char>()).collect(),
+            _ => ARGS.choose(rng).unwrap().to_string()
+        }
+    }
+
+    fn random_text(rng: &mut StdRng) -> String {
+        let mut command = COMMANDS.choose(rng).unwrap().to_string();
+        for _ in 0..rng.gen_range(0..4) {
+            command.push(' ');
+            command.push_str(&random_arg(rng));
+        }
+        command
+    }
+
+    fn random_json(rng: &mut StdRng) -> String {
+        if rng.gen_bool(0.1) {
+            return random_text(rng);
+        }
+        let mut request = json!({ "type": TYPES.choose(rng).unwrap() });
+        if rng.gen_bool(0.9) {
+            request["version"] = json!(if rng.gen_bool(0.9) { 1 } else { rng.gen::<u32>() });
+        }
+        for field in ["to", "message", "cell", "color", "fen", "pgn", "from", "promotion", "san", "king", "rook", "ply"] {
+            if rng.gen_bool(0.5) {
+                request[field] = match rng.gen_range(0..8) {
+                    0 => json!(null),
+                    1 => json!(rng.gen::<i64>()),
+                    _ => json!(random_arg(rng))
+                };
             }
+        }
+        request.to_string()
+    }
 
-        } else if msg.msg.starts_with("\\get_lobby") {
-            let output = format!("{:#?}", &self);
-            self.send_message(&output, &msg.id);
-
-        } else if msg.msg.starts_with("\\get_available_games") {
-            let mut buf = [b'!'; 36];
-            let mut rooms = vec![];
-            for (room_id, _game) in self.rooms.iter() {
-                if room_id != &msg.room_id {
-                    let room_id_str = room_id.simple().encode_lower(&mut buf);
-                    rooms.push(room_id_str.to_string());
+    #[actix_web::test]
+    async fn lobby_survives_random_commands() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let (mut white, _) = connect(&lobby, room_id, Protocol::Text).await;
+        let (mut black, _) = connect(&lobby, room_id, Protocol::Json).await;
+
+        for seed in 0..8 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for _ in 0..500 {
+                if rng.gen_bool(0.01) {
+                    lobby.send(Disconnect { id: white, room_id }).await.expect("lobby is alive");
+                    white = connect(&lobby, room_id, Protocol::Text).await.0;
                 }
+                let (id, msg) = if rng.gen_bool(0.5) {
+                    (white, random_text(&mut rng))
+                } else {
+                    (black, random_json(&mut rng))
+                };
+                let room_id = if rng.gen_bool(0.05) { Uuid::new_v4() } else { room_id };
+                lobby.send(ClientActorMessage { id, msg, room_id }).await.expect("lobby is alive");
             }
-            if rooms.len() > 0 {
-                let message = format!("rooms: {}", rooms.join(" "));
-                self.send_message(&message, &msg.id);
-            }
+        }
 
-        } else if msg.msg.starts_with("\\get_game_stat") {
-            let game = self.rooms.get(&msg.room_id).unwrap();
-            let status = game.status.as_str();
-            let history = game.board.get_history();
-            let mut output = format!("game stat: {}\nturn: {}\nhistories:\n", status, history.len());
-            for his in history {
-                output.push_str(&format!("{}\n", his));
-            }
-            self.send_message(&output, &msg.id);
-
-        } else if msg.msg.starts_with("\\get_color") {
-            let color = self.rooms
-                .get(&msg.room_id).unwrap()
-                .players.get(&msg.id).unwrap();
-            let output = format!("color: {}", color.as_str());
-            self.send_message(&output, &msg.id);
-
-        } else if msg.msg.starts_with("\\get_board") {
-            let board = self.rooms.get(&msg.room_id).unwrap().board.to_string();
-            let output = format!("board:\n{}", board);
-            self.send_message(&output, &msg.id);
-
-        } else if msg.msg.starts_with("\\get_status") {
-            let status = self.rooms.get(&msg.room_id).unwrap().status.as_str();
-            let output = format!("status: {}", status);
-            for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                self.send_message(&output, pid);
-            }
+        lobby.send(Disconnect { id: black, room_id }).await.expect("lobby is alive");
+        let (id, received) = connect(&lobby, room_id, Protocol::Json).await;
+        black = id;
+        lobby.send(ClientActorMessage {
+            id: black,
+            msg: json!({ "version": 1, "type": "get_status" }).to_string(),
+            room_id
+        }).await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
 
-        } else if msg.msg.starts_with("\\move")
-        && self.rooms.get(&msg.room_id).unwrap().status == Status::Playing {
-            let moves = msg.msg.split(" ").collect::<Vec<&str>>();
-            let game = self.rooms.get_mut(&msg.room_id).unwrap();
-            let resp = game.board.moves_piece(moves[1], moves[2]).unwrap();
-            let mut output = format!("history: {}", resp);
-            let enemy_color = game.players.get(&msg.id).unwrap().opposite();
-
-            if game.board.is_king_checked(enemy_color.opposite().as_color()).unwrap() {
-                game.board.undo_moves().unwrap();
-                self.send_message("Error: Illegal Moves", &msg.id);
-                return ();
-            }
+        let received = received.lock().unwrap();
+        assert!(received.iter().any(|msg| msg.contains(r#""type":"status""#)));
+    }
 
-            if game.board.is_checkmate(enemy_color.as_color()).unwrap() {
-                game.status = Status::GameOver;
-                game.winner = Winner::from_color(enemy_color.opposite());
-                output.push_str(" Checkmate");
+    #[actix_web::test]
+    async fn lobby_invalid_arguments() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let (white, received) = connect(&lobby, room_id, Protocol::Text).await;
+        connect(&lobby, room_id, Protocol::Text).await;
 
-            } else if game.board.is_king_checked(enemy_color.as_color()).unwrap() {
-                output.push_str(" Check");
+        for msg in ["\\move e2", "\\move é2 e4", "\\move e2 e22", "\\castling e1 ", "\\move a7 a8 pawn", "\\get_captured red"] {
+            lobby.send(ClientActorMessage { id: white, msg: msg.to_owned(), room_id }).await.expect("lobby is alive");
+        }
+        sleep(Duration::from_millis(50)).await;
 
-            } else if game.board.is_draw(enemy_color.as_color()).unwrap() {
-                game.status = Status::GameOver;
-                game.winner = Winner::Draw;
-                output.push_str(" Draw");
-            }
+        let received = received.lock().unwrap();
+        assert_eq!(received.iter().filter(|msg| msg.starts_with("Error:")).count(), 6);
+    }
 
-            for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                self.send_message(&output, pid);
-            }
+    #[actix_web::test]
+    async fn lobby_reloads_saved_games() {
+        let path = std::env::temp_dir().join(format!("webactix-{}.db", Uuid::new_v4()));
+        let storage = || Box::new(SqliteStorage::open(path.to_str().unwrap()).unwrap());
+        let room_id = Uuid::new_v4();
 
-            let board = self.rooms.get(&msg.room_id).unwrap().board.to_string();
-            let output = format!("board:\n{}", board);
-            for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                self.send_message(&output, pid);
-            }
+        let (white_token, black_token) = (Uuid::new_v4(), Uuid::new_v4());
 
-        } else if msg.msg.starts_with("\\castling") {
-            let moves= msg.msg.split(" ").collect::<Vec<&str>>();
-            match self.rooms
-                .get_mut(&msg.room_id).unwrap()
-                .board.castling(moves[1], moves[2])
-            {
-                Ok(output) => {
-                    for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                        self.send_message(&format!("history: {}", output), pid);
-                    }
-                    let board = self.rooms.get(&msg.room_id).unwrap().board.to_string();
-                    let output = format!("board:\n{}", board);
-                    for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                        self.send_message(&output, pid);
-                    }
-                },
-                Err(err) => self.send_message(&err.to_string(), &msg.id)
-            };
+        let lobby = Lobby::with_storage(storage()).unwrap().start();
+        let (white, _) = connect_with_token(&lobby, room_id, Protocol::Text, white_token).await;
+        connect_with_token(&lobby, room_id, Protocol::Text, black_token).await;
+        lobby.send(ClientActorMessage { id: white, msg: "\\move e2 e4".to_owned(), room_id }).await.expect("lobby is alive");
 
-        } else if msg.msg.starts_with("\\promote") {
-            let cmd = msg.msg.split(" ").collect::<Vec<&str>>();
-            let promotion_level = match cmd[2] {
-                "queen" => Level::Queen,
-                "bishop" => Level::Bishop,
-                "knight" => Level::Knight,
-                "rook" => Level::Rook,
-                _ => Level::Pawn
-            };
-            match self.rooms
-                .get_mut(&msg.room_id).unwrap()
-                .board.promote(cmd[1], promotion_level)
-            {
-                Ok(output) => {
-                    for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                        self.send_message(&format!("{}", output), pid);
-                    }
-                    let board = self.rooms.get(&msg.room_id).unwrap().board.to_string();
-                    let output = format!("promoted board:\n{}", board);
-                    for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                        self.send_message(&output, pid);
-                    }
-                },
-                Err(err) => self.send_message(&err.to_string(), &msg.id)
-            }
+        // a restarted lobby reopens the room in progress with the seats held
+        let lobby = Lobby::with_storage(storage()).unwrap().start();
+        let (_, spectator) = connect(&lobby, room_id, Protocol::Text).await;
+        let (black, received) = connect_with_token(&lobby, room_id, Protocol::Text, black_token).await;
+        connect_with_token(&lobby, room_id, Protocol::Text, white_token).await;
+        lobby.send(ClientActorMessage { id: black, msg: "\\move e7 e5".to_owned(), room_id }).await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(spectator.lock().unwrap()[0].ends_with("you are spectating"));
+        let received = received.lock().unwrap();
+        assert!(received.contains(&"color: black".to_owned()));
+        assert!(received.iter().any(|msg| msg.starts_with("history: Moving Black Pawn from e7 to e5")));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn lobby_dump_hides_tokens() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let (white_token, black_token) = (Uuid::new_v4(), Uuid::new_v4());
+        let (white, _) = connect_with_token(&lobby, room_id, Protocol::Text, white_token).await;
+        let (black, received) = connect_with_token(&lobby, room_id, Protocol::Text, black_token).await;
+        lobby.send(Disconnect { id: white, room_id }).await.expect("lobby is alive");
+        lobby.send(ClientActorMessage { id: black, msg: "\\get_lobby".to_owned(), room_id }).await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
+
+        let received = received.lock().unwrap();
+        let dump = received.iter().find(|msg| msg.starts_with("Lobby {")).unwrap();
+        assert!(dump.contains("held_seats: 1"), "{}", dump);
+        for token in [white_token, black_token] {
+            assert!(!dump.contains(&token.to_string()), "{}", dump);
+        }
+    }
+
+    #[actix_web::test]
+    async fn lobby_spectators() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let (white, white_received) = connect(&lobby, room_id, Protocol::Text).await;
+        connect(&lobby, room_id, Protocol::Text).await;
+        let (spectator, received) = connect(&lobby, room_id, Protocol::Text).await;
+
+        for (id, msg) in [(spectator, "\\move e2 e4"), (spectator, "\\resign"), (white, "\\move e2 e4")] {
+            lobby.send(ClientActorMessage { id, msg: msg.to_owned(), room_id }).await.expect("lobby is alive");
+        }
+        sleep(Duration::from_millis(50)).await;
+
+        let received = received.lock().unwrap();
+        assert!(received[0].ends_with("you are spectating"));
+        assert_eq!(received.iter().filter(|msg| msg.starts_with("Error: Game Error: Spectators")).count(), 2);
+        assert!(received.iter().any(|msg| msg.starts_with("history: Moving White Pawn from e2 to e4")));
+        assert!(white_received.lock().unwrap().iter().any(|msg| msg == "spectators: 1"));
+    }
+
+    #[actix_web::test]
+    async fn lobby_player_names() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let alice = Uuid::new_v4();
+        let (_, white_received) = connect_user(&lobby, room_id, Protocol::Text, alice, Some("Alice")).await;
+        let (_, black_received) = connect_user(&lobby, room_id, Protocol::Json, Uuid::new_v4(), Some("Bob")).await;
+        let (_, spectator) = connect_user(&lobby, room_id, Protocol::Text, Uuid::new_v4(), Some("Carol")).await;
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(white_received.lock().unwrap().contains(&"players: Alice vs Bob".to_owned()));
+        let players = black_received.lock().unwrap().iter()
+            .find(|msg| msg.contains(r#""type":"players""#))
+            .cloned()
+            .unwrap();
+        assert!(players.contains(r#""white":"Alice","black":"Bob""#), "{}", players);
+        assert!(!spectator.lock().unwrap().iter().any(|msg| msg.contains("Carol")));
+
+        // the names are kept with the seats
+        let game = lobby.send(GetGame { room_id }).await.expect("lobby is alive").unwrap().unwrap();
+        assert_eq!(game.status, Status::Playing);
+        let (_, received) = connect_user(&lobby, room_id, Protocol::Text, alice, Some("Alice")).await;
+        sleep(Duration::from_millis(50)).await;
+        assert!(received.lock().unwrap().contains(&"players: Alice vs Bob".to_owned()));
+    }
+
+    #[actix_web::test]
+    async fn lobby_rated_game() -> Result<(), Error> {
+        let accounts = Arc::new(Accounts::in_memory());
+        let alice = accounts.register("alice", "correct horse", Some("Alice"))?;
+        let bob = accounts.register("bob", "battery staple", Some("Bob"))?;
+        let lobby = Lobby::default().with_accounts(accounts.clone()).start();
+
+        let room_id = Uuid::new_v4();
+        let (white, white_received) = connect_user(&lobby, room_id, Protocol::Text, alice.seat_token, Some("Alice")).await;
+        let (_, black_received) = connect_user(&lobby, room_id, Protocol::Text, bob.seat_token, Some("Bob")).await;
+        lobby.send(ClientActorMessage { id: white, msg: "\\resign".to_owned(), room_id }).await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
+
+        let white_rating = accounts.rating(&alice.id, Category::Correspondence)?.rounded();
+        let black_rating = accounts.rating(&bob.id, Category::Correspondence)?.rounded();
+        assert!(white_rating < 1500 && black_rating > 1500);
+        assert_eq!(accounts.rating(&alice.id, Category::Blitz)?, Rating::default());
+        let players = format!("players: Alice ({}) vs Bob ({})", white_rating, black_rating);
+        assert!(white_received.lock().unwrap().contains(&"players: Alice (1500) vs Bob (1500)".to_owned()));
+        assert!(black_received.lock().unwrap().contains(&players), "{:?}", black_received);
+
+        // a casual room leaves the ratings alone
+        let view = lobby.send(CreateGame { time_control: TimeControl::Correspondence, bot: None, rated: false }).await
+            .expect("lobby is alive");
+        assert!(!view.rated);
+        let room_id = view.id;
+        let (white, received) = connect_user(&lobby, room_id, Protocol::Text, alice.seat_token, Some("Alice")).await;
+        connect_user(&lobby, room_id, Protocol::Text, bob.seat_token, Some("Bob")).await;
+        lobby.send(ClientActorMessage { id: white, msg: "\\resign".to_owned(), room_id }).await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(accounts.rating(&bob.id, Category::Correspondence)?.rounded(), black_rating);
+        assert!(received.lock().unwrap().iter().any(|msg| msg.ends_with(", casual")));
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn lobby_rated_game_refuses_loads() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let (white, received) = connect(&lobby, room_id, Protocol::Text).await;
+        connect(&lobby, room_id, Protocol::Text).await;
+        let won = "[Result \"1-0\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0";
+        for msg in ["\\load_fen 4k3/8/8/8/8/8/8/QQQQK3 w - - 0 1".to_owned(), format!("\\load_pgn {}", won)] {
+            lobby.send(ClientActorMessage { id: white, msg, room_id }).await.expect("lobby is alive");
+        }
+        let state = lobby.send(GetGame { room_id }).await.expect("lobby is alive").unwrap().unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(state.history.is_empty());
+        assert_eq!(state.status, Status::Playing);
+        let received = received.lock().unwrap();
+        assert!(received.iter().any(|msg| msg.ends_with("Can't load a position in a rated game.")), "{:?}", received);
+        assert!(received.iter().any(|msg| msg.ends_with("Can't load a game in a rated game.")));
+    }
+
+    #[actix_web::test]
+    async fn lobby_load_pgn() {
+        let lobby = Lobby::default().start();
+        let view = lobby.send(CreateGame { time_control: TimeControl::Correspondence, bot: None, rated: false }).await
+            .expect("lobby is alive");
+        let room_id = view.id;
+        let (white, received) = connect(&lobby, room_id, Protocol::Text).await;
+        connect(&lobby, room_id, Protocol::Text).await;
+        let won = "[Result \"1-0\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0";
+        let ongoing = "[Result \"*\"]\n\n1. e4 e5 2. Nf3 *";
+        for pgn in [won, ongoing] {
+            let msg = format!("\\load_pgn {}", pgn);
+            lobby.send(ClientActorMessage { id: white, msg, room_id }).await.expect("lobby is alive");
+        }
+        let state = lobby.send(GetGame { room_id }).await.expect("lobby is alive").unwrap().unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        // only the game still in progress is loaded, the room keeps playing
+        assert_eq!(state.history.len(), 3);
+        assert_eq!(state.status, Status::Playing);
+        assert_eq!(state.turn, Color::Black);
+        assert!(received.lock().unwrap().iter().any(|msg| msg.ends_with("Can't load a game which is over.")));
+    }
+
+    #[actix_web::test]
+    async fn lobby_reconnect() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let token = Uuid::new_v4();
+        let (white, _) = connect_with_token(&lobby, room_id, Protocol::Text, token).await;
+        let (black, black_received) = connect(&lobby, room_id, Protocol::Text).await;
+        lobby.send(ClientActorMessage { id: white, msg: "\\move e2 e4".to_owned(), room_id }).await.expect("lobby is alive");
+        lobby.send(Disconnect { id: white, room_id }).await.expect("lobby is alive");
+        let disconnected = format!("{} disconnected.", white);
+
+        // the seat is held, a stranger can only watch
+        let (_, stranger) = connect(&lobby, room_id, Protocol::Text).await;
+        let (white, received) = connect_with_token(&lobby, room_id, Protocol::Text, token).await;
+        lobby.send(ClientActorMessage { id: black, msg: "\\move e7 e5".to_owned(), room_id }).await.expect("lobby is alive");
+        lobby.send(ClientActorMessage { id: white, msg: "\\move g1 f3".to_owned(), room_id }).await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(stranger.lock().unwrap()[0].ends_with("you are spectating"));
+        let received = received.lock().unwrap();
+        assert_eq!(received[1], "color: white");
+        assert!(received[2].starts_with("game stat: playing\nturn: 1\n"));
+        assert!(received[3].starts_with("board:\n"));
+        assert!(received.iter().any(|msg| msg.starts_with("history: Moving White Knight from g1 to f3")));
+        assert!(black_received.lock().unwrap().contains(&disconnected));
+    }
+
+    #[actix_web::test]
+    async fn lobby_grace_period() {
+        let lobby = Lobby { grace_period: Duration::from_millis(20), ..Lobby::default() }.start();
+        let room_id = Uuid::new_v4();
+        let (white, _) = connect(&lobby, room_id, Protocol::Text).await;
+        let (_, black_received) = connect(&lobby, room_id, Protocol::Text).await;
+        lobby.send(Disconnect { id: white, room_id }).await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
+
+        // the seat is released after the grace period
+        let (_, received) = connect(&lobby, room_id, Protocol::Text).await;
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(black_received.lock().unwrap().contains(&"status: waiting".to_owned()));
+        let received = received.lock().unwrap();
+        assert!(!received[0].ends_with("you are spectating"));
+        assert_eq!(received[1..], ["players: guest vs guest".to_owned()]);
+    }
 
-        } else if msg.msg.starts_with("\\get_possible_moves") {
-            let cmd = msg.msg.split(" ").collect::<Vec<&str>>();
-            let resp = self.rooms
-                .get_mut(&msg.room_id).unwrap()
-                .board.get_possible_moves_as_string(cmd[1]);
-            let output = format!("possible moves for:\n{}\n{}", cmd[1], resp);
-            self.send_message(&output, &msg.id);
-
-        } else if msg.msg.starts_with("\\get_captured") {
-            let cmd = msg.msg.split(" ").collect::<Vec<&str>>();
-            let color = if cmd[1] == "white" { color::Color::White } else { color::Color::Black };
-            let captured = self.rooms
-                .get_mut(&msg.room_id).unwrap()
-                .board.get_captured(color).unwrap()
-                .iter()
-                .map(|piece| piece.to_string())
-                .collect::<Vec<String>>()
-                .join(" ");
-            let message = format!("captured_{}: {}", cmd[1], captured);
-            for (pid, _color) in self.rooms.get(&msg.room_id).unwrap().players.iter() {
-                self.send_message(&message, pid);
+    #[actix_web::test]
+    async fn lobby_bot_replies() {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let received = Arc::new(Mutex::new(vec![]));
+        let id = Uuid::new_v4();
+        lobby.send(Connect {
+            addr: Client(received.clone()).start().recipient(),
+            lobby_id: room_id,
+            self_id: id,
+            token: Uuid::new_v4(),
+            name: None,
+            time_control: TimeControl::Correspondence,
+            bot: Some(chess::engine::Engine::new(1, Duration::from_secs(5))),
+            rated: true,
+            protocol: Protocol::Text
+        }).await.expect("lobby is alive");
+
+        // the bot holds the second seat, the game starts at once
+        lobby.send(ClientActorMessage { id, msg: "\\get_status".to_owned(), room_id }).await.expect("lobby is alive");
+        lobby.send(ClientActorMessage { id, msg: "\\move e2 e4".to_owned(), room_id }).await.expect("lobby is alive");
+        for _ in 0..100 {
+            sleep(Duration::from_millis(50)).await;
+            if received.lock().unwrap().iter().any(|msg| msg.starts_with("history: Moving Black")) {
+                break;
             }
+        }
 
-        } else {
-            let game = self.rooms.get(&msg.room_id).unwrap();
-            game.players.iter()
-                .filter(|player| *player.0 != msg.id)
-                .for_each(|player| self.send_message(&msg.msg, player.0));
+        let received = received.lock().unwrap();
+        assert!(received.contains(&"status: playing".to_owned()));
+        assert!(received.iter().any(|msg| msg.starts_with("history: Moving White Pawn from e2 to e4")));
+        assert!(received.iter().any(|msg| msg.starts_with("history: Moving Black")));
+    }
+
+    #[actix_web::test]
+    async fn lobby_available_games() {
+        let lobby = Lobby::default().start();
+        let (full, waiting) = (Uuid::new_v4(), Uuid::new_v4());
+        connect(&lobby, full, Protocol::Text).await;
+        connect(&lobby, full, Protocol::Text).await;
+        let (id, received) = connect(&lobby, waiting, Protocol::Text).await;
+        lobby.send(ClientActorMessage { id, msg: "\\get_available_games".to_owned(), room_id: waiting })
+            .await.expect("lobby is alive");
+        let own = Uuid::new_v4();
+        let (id, other) = connect(&lobby, own, Protocol::Text).await;
+        lobby.send(ClientActorMessage { id, msg: "\\get_available_games".to_owned(), room_id: own })
+            .await.expect("lobby is alive");
+        sleep(Duration::from_millis(50)).await;
+
+        // the full room and the own room aren't listed
+        assert!(received.lock().unwrap().contains(&"rooms:".to_owned()));
+        let other = other.lock().unwrap();
+        let rooms = other.iter().find(|msg| msg.starts_with("rooms:")).unwrap();
+        assert_eq!(rooms.lines().count(), 2);
+        assert!(rooms.contains(&format!("{} waiting 1/2 correspondence", waiting.simple())));
+    }
+
+    async fn seek(lobby: &Addr<Lobby>, token: Uuid, time_control: TimeControl, rating: u32) -> Arc<Mutex<Vec<String>>> {
+        let received = Arc::new(Mutex::new(vec![]));
+        let addr = Client(received.clone()).start();
+        lobby.send(Seek {
+            addr: addr.recipient(),
+            id: Uuid::new_v4(),
+            token,
+            time_control,
+            rating,
+            protocol: Protocol::Text
+        }).await.expect("lobby is alive");
+        received
+    }
+
+    #[actix_web::test]
+    async fn lobby_matchmaking() {
+        let lobby = Lobby::default().start();
+        let blitz = TimeControl::Clock { minutes: 5, increment: 3 };
+        let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+        let first = seek(&lobby, white, blitz, 1500).await;
+        let rapid = seek(&lobby, Uuid::new_v4(), TimeControl::Clock { minutes: 15, increment: 10 }, 1500).await;
+        let far = seek(&lobby, Uuid::new_v4(), blitz, 2000).await;
+        let second = seek(&lobby, black, blitz, 1450).await;
+        sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(first.lock().unwrap()[0], "seeking: 5+3 1500");
+        for received in [&rapid, &far] {
+            assert_eq!(received.lock().unwrap().len(), 1);
+        }
+        let matched = first.lock().unwrap()[1].clone();
+        let room_id = Uuid::parse_str(matched.strip_prefix("matched: ").unwrap().strip_suffix(" white").unwrap()).unwrap();
+        assert_eq!(second.lock().unwrap()[1], format!("matched: {} black", room_id));
+
+        // the seats of the new room are held for the matched players
+        let (_, received) = connect_with_token(&lobby, room_id, Protocol::Text, black).await;
+        let (_, stranger) = connect(&lobby, room_id, Protocol::Text).await;
+        connect_with_token(&lobby, room_id, Protocol::Text, white).await;
+        sleep(Duration::from_millis(50)).await;
+        assert!(received.lock().unwrap().contains(&"color: black".to_owned()));
+        assert!(stranger.lock().unwrap()[0].ends_with("you are spectating"));
+        let game = lobby.send(GetGame { room_id }).await.expect("lobby is alive").unwrap().unwrap();
+        assert_eq!(game.status, Status::Playing);
+        assert_eq!(game.time_control, "5+3");
+    }
+
+    #[actix_web::test]
+    async fn lobby_api_moves() -> Result<(), Error> {
+        let lobby = Lobby::default().start();
+        let created = lobby.send(CreateGame { time_control: TimeControl::Correspondence, bot: None, rated: true })
+            .await.expect("lobby is alive");
+        let room_id = created.id;
+        let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+        connect_with_token(&lobby, room_id, Protocol::Text, white).await;
+        let (_id, received) = connect_with_token(&lobby, room_id, Protocol::Text, black).await;
+
+        let games = lobby.send(ListGames { joinable: false }).await.expect("lobby is alive");
+        assert_eq!(games.iter().map(|game| game.id).collect::<Vec<Uuid>>(), [room_id]);
+        assert_eq!(games[0].status, Status::Playing);
+        assert!(lobby.send(ListGames { joinable: true }).await.expect("lobby is alive").is_empty());
+
+        let input = MoveInput::Cells { from: "e2".to_owned(), to: "e4".to_owned(), promotion: None };
+        let view = lobby.send(PlayMove { room_id, token: white, input }).await.expect("lobby is alive")?;
+        assert_eq!(view.unwrap().turn, Color::Black);
+
+        let input = MoveInput::San { san: "e5".to_owned() };
+        assert!(lobby.send(PlayMove { room_id, token: white, input: input.clone() }).await.expect("lobby is alive").is_err());
+        lobby.send(PlayMove { room_id, token: black, input }).await.expect("lobby is alive")?;
+
+        let view = lobby.send(GetGame { room_id }).await.expect("lobby is alive")?.unwrap();
+        assert_eq!(view.history.len(), 2);
+        assert!(lobby.send(GetGame { room_id: Uuid::new_v4() }).await.expect("lobby is alive")?.is_none());
+
+        // the players connected through the websocket see the moves
+        let received = received.lock().unwrap();
+        assert!(received.iter().any(|msg| msg == "history: Moving White Pawn from e2 to e4 (e4)"));
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn lobby_positions() -> Result<(), Error> {
+        let lobby = Lobby::default().start();
+        let room_id = Uuid::new_v4();
+        let (white, _) = connect(&lobby, room_id, Protocol::Text).await;
+        let (black, _) = connect(&lobby, room_id, Protocol::Text).await;
+        let (spectator, received) = connect(&lobby, room_id, Protocol::Json).await;
+        for (id, msg) in [(white, "\\move e4"), (black, "\\move e5"), (white, "\\move Nf3")] {
+            lobby.send(ClientActorMessage { id, msg: msg.to_owned(), room_id }).await.expect("lobby is alive");
         }
-        // Print Lobby instance
-        // self.rooms.get(&msg.room_id).unwrap().board.print().unwrap();
+
+        // anyone in the room can look back at an earlier position
+        for ply in [2, 4] {
+            let msg = json!({ "version": 1, "type": "get_position", "ply": ply }).to_string();
+            lobby.send(ClientActorMessage { id: spectator, msg, room_id }).await.expect("lobby is alive");
+        }
+        sleep(Duration::from_millis(50)).await;
+        let messages = received.lock().unwrap().clone();
+        let position = messages.iter().find(|msg| msg.contains(r#""type":"position""#)).unwrap();
+        assert!(position.contains(r#""ply":2,"san":"e5","fen":"rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2""#), "{}", position);
+        assert!(messages.last().unwrap().contains("The game has only 3 moves."));
+
+        let view = lobby.send(GetPosition { room_id, ply: 0 }).await.expect("lobby is alive")?.unwrap();
+        assert_eq!((view.plies, view.san, view.board), (3, None, Board::new()));
+        assert!(lobby.send(GetPosition { room_id, ply: 4 }).await.expect("lobby is alive").is_err());
+        assert!(lobby.send(GetPosition { room_id: Uuid::new_v4(), ply: 0 }).await.expect("lobby is alive")?.is_none());
+        Ok(())
     }
 }
@@ -1,10 +1,15 @@
+mod accounts;
+mod api;
+mod bot;
 mod lobby;
+mod matchmaking;
 mod message;
 mod model;
+mod protocol;
+mod rating;
+mod storage;
 mod webserver;
 mod ws;
-pub mod game;
-pub mod error;
 
 use actix::Actor;
 use actix_web::{web, App, HttpServer};
@@ -17,7 +22,13 @@ async fn main() -> std::io::Result<()> {
 
     // let tera = Tera::new("/home/ubuntu/webactix/templates/**/*").unwrap();
     let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
-    let chess_ws_server = lobby::Lobby::default().start();
+    let database: String = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "webactix.db".to_string());
+    let storage = storage::SqliteStorage::open(&database).unwrap();
+    // the accounts have their own connection, used from the blocking threads
+    let accounts = web::Data::new(accounts::Accounts::new(Box::new(storage::SqliteStorage::open(&database).unwrap())));
+    let chess_ws_server = lobby::Lobby::with_storage(Box::new(storage)).unwrap()
+        .with_accounts(accounts.clone().into_inner())
+        .start();
 
     println!("Web Actix server start on {}:{}", host, port);
     HttpServer::new(move || {
@@ -25,9 +36,24 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(tera.clone()))
             .service(webserver::index)
             .service(webserver::game)
+            .service(webserver::review)
+            .service(webserver::login)
+            .service(webserver::leaderboard)
             .service(webserver::staticfiles)
             .app_data(web::Data::new(chess_ws_server.clone()))
             .service(ws::start_connection)
+            .service(ws::start_seek)
+            .service(api::create_game)
+            .service(api::list_games)
+            .service(api::get_game)
+            .service(api::get_position)
+            .service(api::play_move)
+            .app_data(accounts.clone())
+            .service(accounts::register)
+            .service(accounts::login)
+            .service(accounts::logout)
+            .service(accounts::me)
+            .service(accounts::leaderboard)
     })
     .bind((host, port))?
     .run()
@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use crate::model::TimeControl;
+
+/// Largest rating difference between two players paired as soon as they seek
+const RATING_RANGE: u32 = 100;
+/// Widening of the rating range every `RANGE_STEP` a player waits
+const RANGE_GROWTH: u32 = 50;
+const RANGE_STEP: Duration = Duration::from_secs(10);
+
+/// Player waiting in the matchmaking queue
+#[derive(Clone, PartialEq, Eq)]
+pub struct Seeker {
+    /// Session of the seeking connection
+    pub id: Uuid,
+    /// Player token the seat of the new room is held for
+    pub token: Uuid,
+    pub time_control: TimeControl,
+    pub rating: u32,
+    pub since: Instant
+}
+
+impl std::fmt::Debug for Seeker {
+    // the player token is secret, it would give the seat away
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Seeker")
+            .field("id", &self.id)
+            .field("time_control", &self.time_control)
+            .field("rating", &self.rating)
+            .field("since", &self.since)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Seeker {
+    /// Largest rating difference the seeker accepts after waiting until `now`
+    pub fn rating_range(&self, now: Instant) -> u32 {
+        let steps = now.saturating_duration_since(self.since).as_secs() / RANGE_STEP.as_secs();
+        RATING_RANGE.saturating_add(RANGE_GROWTH.saturating_mul(steps as u32))
+    }
+
+    /// Whether the seekers can play each other, the longest waiting one
+    /// decides on the rating range
+    fn accepts(&self, other: &Seeker, now: Instant) -> bool {
+        self.token != other.token
+        && self.time_control == other.time_control
+        && self.rating.abs_diff(other.rating) <= self.rating_range(now).max(other.rating_range(now))
+    }
+}
+
+/// Matchmaking queue, in the order the players started seeking
+#[derive(Debug, Default)]
+pub struct Queue {
+    seekers: Vec<Seeker>
+}
+
+impl Queue {
+    /// Add a seeker at the end of the queue
+    pub fn push(&mut self, seeker: Seeker) {
+        self.seekers.push(seeker);
+    }
+
+    /// Remove the seeker of the session, if it's still waiting
+    pub fn remove(&mut self, id: &Uuid) -> Option<Seeker> {
+        let index = self.seekers.iter().position(|seeker| seeker.id == *id)?;
+        Some(self.seekers.remove(index))
+    }
+
+    /// Pair the waiting seekers, the longest waiting first, with the
+    /// closest rating among the players they accept
+    ///
+    /// The paired seekers leave the queue, the first one of each pair has
+    /// waited longer and plays White.
+    pub fn pair(&mut self, now: Instant) -> Vec<(Seeker, Seeker)> {
+        let mut pairs = vec![];
+        let mut i = 0;
+        while i < self.seekers.len() {
+            let seeker = &self.seekers[i];
+            let opponent = self.seekers.iter()
+                .enumerate()
+                .skip(i + 1)
+                .filter(|(_j, other)| seeker.accepts(other, now))
+                .min_by_key(|(_j, other)| seeker.rating.abs_diff(other.rating))
+                .map(|(j, _other)| j);
+
+            match opponent {
+                Some(j) => {
+                    let black = self.seekers.remove(j);
+                    let white = self.seekers.remove(i);
+                    pairs.push((white, black));
+                },
+                None => i += 1
+            }
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeker(time_control: TimeControl, rating: u32, since: Instant) -> Seeker {
+        Seeker { id: Uuid::new_v4(), token: Uuid::new_v4(), time_control, rating, since }
+    }
+
+    #[test]
+    fn queue_pairs_by_time_control_and_rating() {
+        let now = Instant::now();
+        let blitz = TimeControl::Clock { minutes: 5, increment: 3 };
+        let mut queue = Queue::default();
+        let first = seeker(blitz, 1500, now);
+        let far = seeker(blitz, 1800, now);
+        let close = seeker(blitz, 1550, now);
+        let rapid = seeker(TimeControl::Clock { minutes: 15, increment: 10 }, 1500, now);
+        for seeker in [&first, &far, &rapid, &close] {
+            queue.push(seeker.clone());
+        }
+
+        assert_eq!(queue.pair(now), [(first, close)]);
+        assert!(queue.remove(&rapid.id).is_some());
+        assert!(queue.remove(&rapid.id).is_none());
+        assert!(queue.pair(now).is_empty());
+        assert_eq!(queue.remove(&far.id), Some(far));
+    }
+
+    #[test]
+    fn queue_widens_rating_range() {
+        let now = Instant::now();
+        let since = now - Duration::from_secs(45);
+        let waiting = seeker(TimeControl::Correspondence, 1500, since);
+        assert_eq!(waiting.rating_range(now), 300);
+
+        let mut queue = Queue::default();
+        queue.push(waiting.clone());
+        let newcomer = seeker(TimeControl::Correspondence, 1750, now);
+        queue.push(newcomer.clone());
+        assert_eq!(queue.pair(since), []);
+        assert_eq!(queue.pair(now), [(waiting, newcomer)]);
+    }
+
+    #[test]
+    fn queue_skips_same_player() {
+        let now = Instant::now();
+        let mut queue = Queue::default();
+        let seeker = seeker(TimeControl::Correspondence, 1500, now);
+        queue.push(seeker.clone());
+        queue.push(Seeker { id: Uuid::new_v4(), ..seeker.clone() });
+        assert!(queue.pair(now).is_empty());
+        assert!(!format!("{:?}", queue).contains(&seeker.token.to_string()));
+    }
+}
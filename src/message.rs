@@ -1,5 +1,15 @@
 use actix::prelude::{Message, Recipient};
 use uuid::Uuid;
+use chess::{
+    engine::{BestMove, Engine},
+    game::prelude::{Board, Piece},
+    error::Error
+};
+use crate::{
+    api::{GameView, MoveInput, PositionView},
+    model::{GameSummary, TimeControl},
+    protocol::Protocol
+};
 
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -11,6 +21,18 @@ pub struct Connect {
     pub addr: Recipient<WsMessage>,
     pub lobby_id: Uuid,
     pub self_id: Uuid,
+    /// Player token to reclaim the seat of a previous connection, the seat
+    /// token of a signed-in player
+    pub token: Uuid,
+    /// Display name of a signed-in player, shown to the opponent
+    pub name: Option<String>,
+    /// Time control of the room when it's created by this connection
+    pub time_control: TimeControl,
+    /// Engine of the computer opponent of a new room, `None` for a human opponent
+    pub bot: Option<Engine>,
+    /// Whether a new room is rated, `false` for a casual game
+    pub rated: bool,
+    pub protocol: Protocol,
 }
 
 #[derive(Message)]
@@ -26,4 +48,75 @@ pub struct ClientActorMessage {
     pub id: Uuid,
     pub msg: String,
     pub room_id: Uuid
-}
\ No newline at end of file
+}
+
+/// Search the best move of the side to move on the board
+#[derive(Message)]
+#[rtype(result = "Result<Option<BestMove>, Error>")]
+pub struct Think {
+    pub engine: Engine,
+    pub board: Board<Piece>
+}
+
+/// Create a room with the time control and the computer opponent
+#[derive(Message)]
+#[rtype(result = "GameView")]
+pub struct CreateGame {
+    pub time_control: TimeControl,
+    pub bot: Option<Engine>,
+    /// Whether the result updates the ratings, `false` for a casual game
+    pub rated: bool
+}
+
+/// Get the game of a room, `None` when it's neither open nor saved
+#[derive(Message)]
+#[rtype(result = "Result<Option<GameView>, Error>")]
+pub struct GetGame {
+    pub room_id: Uuid
+}
+
+/// Get the board of a game, open or saved, after the first `ply` moves
+#[derive(Message)]
+#[rtype(result = "Result<Option<PositionView>, Error>")]
+pub struct GetPosition {
+    pub room_id: Uuid,
+    pub ply: usize
+}
+
+/// List the open rooms, only the ones a player can join when `joinable` is set
+#[derive(Message)]
+#[rtype(result = "Vec<GameSummary>")]
+pub struct ListGames {
+    pub joinable: bool
+}
+
+/// Play a move for the player token holding a seat in the room, `None`
+/// when the room isn't open
+#[derive(Message)]
+#[rtype(result = "Result<Option<GameView>, Error>")]
+pub struct PlayMove {
+    pub room_id: Uuid,
+    pub token: Uuid,
+    pub input: MoveInput
+}
+
+/// Enter the matchmaking queue, the session is sent the room of its game
+/// once an opponent is found
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Seek {
+    pub addr: Recipient<WsMessage>,
+    pub id: Uuid,
+    /// Player token the seat of the new room is held for
+    pub token: Uuid,
+    pub time_control: TimeControl,
+    pub rating: u32,
+    pub protocol: Protocol
+}
+
+/// Leave the matchmaking queue
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct CancelSeek {
+    pub id: Uuid
+}
@@ -1,10 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use crate::game::{board::Board, piece::Piece, color};
+use chess::{
+    engine::Engine,
+    game::{board::Board, piece::Piece, color, draw::DrawReason},
+    notation::{pgn::Pgn, san},
+    error::Error::{self, *}
+};
 
 /// Statuses for the game: `Waiting`, `Playing`, and `GameOver`
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
 pub enum Status {
     #[default]
     Waiting,
@@ -25,6 +34,7 @@ impl Status {
 
 /// Winners for the game: `NotDecided`, `Draw`, `White`, and `Black`
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
 pub enum Winner {
     #[default]
     NotDecided,
@@ -41,10 +51,248 @@ impl Winner {
             Color::White => Winner::White
         }
     }
+
+    /// Score of White for the ratings, `None` while the game isn't decided
+    pub fn white_score(&self) -> Option<f64> {
+        match *self {
+            Winner::NotDecided => None,
+            Winner::Draw => Some(0.5),
+            Winner::White => Some(1.0),
+            Winner::Black => Some(0.0)
+        }
+    }
+
+    /// Parse `Winner` as PGN game result, e.g. `"1-0"`
+    pub fn as_result(&self) -> &str {
+        match *self {
+            Winner::NotDecided => "*",
+            Winner::Draw => "1/2-1/2",
+            Winner::White => "1-0",
+            Winner::Black => "0-1"
+        }
+    }
+
+    /// Convert PGN game result to `Winner`
+    pub fn from_result(result: &str) -> Winner {
+        match result {
+            "1/2-1/2" => Winner::Draw,
+            "1-0" => Winner::White,
+            "0-1" => Winner::Black,
+            _ => Winner::NotDecided
+        }
+    }
+}
+
+/// How the game ended: by `Checkmate`, `Resignation`, draw `Agreement`,
+/// or one of the drawing rules
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    Agreement,
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+    Timeout,
+    TimeoutVsInsufficientMaterial
+}
+
+impl Termination {
+    /// Parse `Termination` as `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Termination::Checkmate => "checkmate",
+            Termination::Resignation => "resignation",
+            Termination::Agreement => "agreement",
+            Termination::Stalemate => "stalemate",
+            Termination::FiftyMoveRule => "fifty-move rule",
+            Termination::ThreefoldRepetition => "threefold repetition",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::Timeout => "timeout",
+            Termination::TimeoutVsInsufficientMaterial => "timeout vs insufficient material"
+        }
+    }
+
+    /// Convert `DrawReason` to `Termination`
+    pub fn from_draw_reason(reason: DrawReason) -> Termination {
+        match reason {
+            DrawReason::Stalemate => Termination::Stalemate,
+            DrawReason::FiftyMoveRule => Termination::FiftyMoveRule,
+            DrawReason::ThreefoldRepetition => Termination::ThreefoldRepetition,
+            DrawReason::InsufficientMaterial => Termination::InsufficientMaterial
+        }
+    }
+}
+
+/// Time controls for the game: `Correspondence` (no clock), or a
+/// `Clock` with the initial minutes and the increment seconds per move
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum TimeControl {
+    #[default]
+    Correspondence,
+    Clock { minutes: u64, increment: u64 }
+}
+
+impl TimeControl {
+    /// Parse time control, e.g. `"5+3"` or `"correspondence"`
+    pub fn parse(tc: &str) -> Result<TimeControl, Error> {
+        if tc == "correspondence" {
+            return Ok(TimeControl::Correspondence);
+        }
+
+        let invalid = || GameError(format!("Invalid time control '{tc}'."));
+        let (minutes, increment) = tc.split_once('+').ok_or_else(invalid)?;
+        let minutes = minutes.parse::<u64>().map_err(|_| invalid())?;
+        let increment = increment.parse::<u64>().map_err(|_| invalid())?;
+        if minutes == 0 {
+            return Err(invalid());
+        }
+
+        Ok(TimeControl::Clock { minutes, increment })
+    }
+
+    /// Rating category of the time control, by the estimated duration of a
+    /// game of 40 moves
+    pub fn category(&self) -> Category {
+        match *self {
+            TimeControl::Correspondence => Category::Correspondence,
+            TimeControl::Clock { minutes, increment } => match minutes * 60 + increment * 40 {
+                0..=179 => Category::Bullet,
+                180..=479 => Category::Blitz,
+                480..=1499 => Category::Rapid,
+                _ => Category::Classical
+            }
+        }
+    }
+
+    /// Parse `TimeControl` as PGN `TimeControl` tag, in seconds, e.g. `"300+3"`
+    pub fn as_pgn(&self) -> String {
+        match *self {
+            TimeControl::Correspondence => "-".to_owned(),
+            TimeControl::Clock { minutes, increment } => format!("{}+{}", minutes * 60, increment)
+        }
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TimeControl::Correspondence => write!(f, "correspondence"),
+            TimeControl::Clock { minutes, increment } => write!(f, "{}+{}", minutes, increment)
+        }
+    }
+}
+
+/// Rating categories of the time controls, the players have a rating in each
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Bullet,
+    #[default]
+    Blitz,
+    Rapid,
+    Classical,
+    Correspondence
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::Bullet, Category::Blitz, Category::Rapid, Category::Classical, Category::Correspondence
+    ];
+
+    /// Parse `Category` as `&str`
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Category::Bullet => "bullet",
+            Category::Blitz => "blitz",
+            Category::Rapid => "rapid",
+            Category::Classical => "classical",
+            Category::Correspondence => "correspondence"
+        }
+    }
+}
+
+/// Chess clock of both players
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Clock {
+    pub white: Duration,
+    pub black: Duration,
+    pub increment: Duration,
+    /// Color of the running clock and since when it runs
+    pub running: Option<(Color, Instant)>
+}
+
+impl Clock {
+    /// Create new `Clock` for the time control, `None` for correspondence
+    pub fn new(time_control: TimeControl) -> Option<Clock> {
+        match time_control {
+            TimeControl::Correspondence => None,
+            TimeControl::Clock { minutes, increment } => Some(Clock {
+                white: Duration::from_secs(minutes * 60),
+                black: Duration::from_secs(minutes * 60),
+                increment: Duration::from_secs(increment),
+                running: None
+            })
+        }
+    }
+
+    fn time_mut(&mut self, color: Color) -> &mut Duration {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black
+        }
+    }
+
+    /// Get the remaining time of the player at `now`
+    pub fn remaining(&self, color: Color, now: Instant) -> Duration {
+        let time = match color {
+            Color::White => self.white,
+            Color::Black => self.black
+        };
+        match self.running {
+            Some((running, since)) if running == color => time.saturating_sub(now.saturating_duration_since(since)),
+            _ => time
+        }
+    }
+
+    /// Start the clock of the player
+    pub fn start(&mut self, color: Color, now: Instant) {
+        self.stop(now);
+        self.running = Some((color, now));
+    }
+
+    /// Stop the running clock
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((color, _since)) = self.running {
+            let remaining = self.remaining(color, now);
+            self.running = None;
+            *self.time_mut(color) = remaining;
+        }
+    }
+
+    /// End the turn of the player: add the increment and start the opponent's clock
+    pub fn press(&mut self, color: Color, now: Instant) {
+        self.stop(now);
+        let increment = self.increment;
+        *self.time_mut(color) += increment;
+        self.running = Some((color.opposite(), now));
+    }
+}
+
+/// Remaining time of both players in milliseconds
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ClockState {
+    pub white: u64,
+    pub black: u64,
+    /// Color of the running clock
+    pub running: Option<Color>
 }
 
 /// Player's Color: `White` or `Black`
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Color {
     White,
     Black
@@ -74,25 +322,745 @@ impl Color {
             Color::White => color::Color::White
         }
     }
+
+    /// Convert `game::color::Color` to `model::Color`
+    pub fn from_color(color: color::Color) -> Color {
+        match color {
+            color::Color::Black => Color::Black,
+            color::Color::White => Color::White
+        }
+    }
+}
+
+/// Computer opponent of a room, playing the `color` with the `engine`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Bot {
+    pub color: Color,
+    pub engine: Engine
+}
+
+/// Summary of a room for the listings
+#[derive(Clone, Debug, Serialize)]
+pub struct GameSummary {
+    pub id: Uuid,
+    pub status: Status,
+    pub time_control: String,
+    /// Seats held by the players, the computer opponent included
+    pub players: usize,
+    pub bot: Option<Bot>,
+    pub rated: bool,
+    pub created_at: DateTime<Utc>
+}
+
+impl GameSummary {
+    /// Create new `GameSummary` of the game in the room
+    pub fn new(id: Uuid, game: &Game) -> GameSummary {
+        GameSummary {
+            id,
+            status: game.status.clone(),
+            time_control: game.time_control.to_string(),
+            players: game.held_seats(),
+            bot: game.bot,
+            rated: game.rated,
+            created_at: game.created_at
+        }
+    }
 }
 
 /// Chess `Game` Struct
-#[derive(Debug)]
 pub struct Game {
+    /// Sessions of the connected players
     pub players: HashMap<Uuid, Color>,
+    /// Colors held by the player tokens, kept while a player reconnects
+    pub seats: HashMap<Uuid, Color>,
+    /// Display names of the signed-in players by the token of their seat,
+    /// which is the seat token of their account
+    pub names: HashMap<Uuid, String>,
+    /// Sessions watching the game without a seat
+    pub spectators: HashSet<Uuid>,
     pub status: Status,
     pub winner: Winner,
-    pub board: Board<Piece>
+    pub board: Board<Piece>,
+    pub created_at: DateTime<Utc>,
+    pub termination: Option<Termination>,
+    /// Color of the player whose draw offer is pending
+    pub draw_offer: Option<Color>,
+    pub time_control: TimeControl,
+    pub clock: Option<Clock>,
+    /// Computer opponent holding the second seat
+    pub bot: Option<Bot>,
+    /// Whether the result updates the ratings of the signed-in players,
+    /// `false` for a casual game
+    pub rated: bool
+}
+
+// the player tokens are secret, only their colors are shown
+impl std::fmt::Debug for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Game")
+            .field("players", &self.players)
+            .field("seats", &self.seats.values().collect::<Vec<&Color>>())
+            .field("names", &self.names.values().collect::<Vec<&String>>())
+            .field("spectators", &self.spectators)
+            .field("status", &self.status)
+            .field("winner", &self.winner)
+            .field("board", &self.board)
+            .field("created_at", &self.created_at)
+            .field("termination", &self.termination)
+            .field("draw_offer", &self.draw_offer)
+            .field("time_control", &self.time_control)
+            .field("clock", &self.clock)
+            .field("bot", &self.bot)
+            .field("rated", &self.rated)
+            .finish()
+    }
 }
 
 impl Game {
     /// Create new `Game` instance
     pub fn new() -> Game {
+        Game::with_time_control(TimeControl::default())
+    }
+
+    /// Create new `Game` instance with the time control
+    pub fn with_time_control(time_control: TimeControl) -> Game {
         Game {
             players: HashMap::new(),
+            seats: HashMap::new(),
+            names: HashMap::new(),
+            spectators: HashSet::new(),
             status: Status::default(),
             winner: Winner::default(),
-            board: Board::new()
+            board: Board::new(),
+            created_at: Utc::now(),
+            termination: None,
+            draw_offer: None,
+            time_control,
+            clock: Clock::new(time_control),
+            bot: None,
+            rated: true
         }
     }
+
+    /// Create new `Game` instance against the computer playing the `bot` color
+    pub fn with_bot(time_control: TimeControl, bot: Bot) -> Game {
+        Game { bot: Some(bot), ..Game::with_time_control(time_control) }
+    }
+
+    /// Get the color of the computer opponent when it's to move in a game in progress
+    pub fn bot_to_move(&self) -> Option<Bot> {
+        self.bot.filter(|bot| self.status == Status::Playing && bot.color == self.side_to_move())
+    }
+
+    /// Start the clock of the side to move while the game is in progress
+    pub fn start_clock(&mut self, now: Instant) {
+        let color = self.side_to_move();
+        if let (Some(clock), Status::Playing) = (self.clock.as_mut(), &self.status) {
+            clock.start(color, now);
+        }
+    }
+
+    /// Stop the running clock
+    pub fn stop_clock(&mut self, now: Instant) {
+        if let Some(clock) = self.clock.as_mut() {
+            clock.stop(now);
+        }
+    }
+
+    /// End the turn of the player on the clock
+    pub fn press_clock(&mut self, color: Color, now: Instant) {
+        if let Some(clock) = self.clock.as_mut() {
+            clock.press(color, now);
+        }
+    }
+
+    /// Get the remaining time of the side to move while the game is in progress
+    pub fn time_to_flag(&self, now: Instant) -> Option<Duration> {
+        match (&self.clock, &self.status) {
+            (Some(clock), Status::Playing) => Some(clock.remaining(self.side_to_move(), now)),
+            _ => None
+        }
+    }
+
+    /// End the game when the side to move has run out of time
+    ///
+    /// The opponent wins, or it's a draw when the opponent can't
+    /// checkmate. Returns the color of the player who ran out of time.
+    pub fn check_flag(&mut self, now: Instant) -> Option<Color> {
+        if !self.time_to_flag(now)?.is_zero() {
+            return None;
+        }
+
+        let color = self.side_to_move();
+        self.stop_clock(now);
+        if self.board.has_insufficient_material(color.opposite().as_color()) {
+            self.finish(Winner::Draw, Termination::TimeoutVsInsufficientMaterial);
+        } else {
+            self.finish(Winner::from_color(color.opposite()), Termination::Timeout);
+        }
+        Some(color)
+    }
+
+    /// Get the remaining time of both players at `now`
+    pub fn clock_state(&self, now: Instant) -> Option<ClockState> {
+        self.clock.as_ref().map(|clock| ClockState {
+            white: clock.remaining(Color::White, now).as_millis() as u64,
+            black: clock.remaining(Color::Black, now).as_millis() as u64,
+            running: clock.running.map(|(color, _since)| color)
+        })
+    }
+
+    /// End the game with the `winner` and how it was ended
+    pub fn finish(&mut self, winner: Winner, termination: Termination) {
+        self.status = Status::GameOver;
+        self.winner = winner;
+        self.termination = Some(termination);
+        self.draw_offer = None;
+        self.stop_clock(Instant::now());
+    }
+
+    /// Seat the session of a player token, a token with a seat reclaims its color
+    ///
+    /// The game starts when both players are connected, a computer opponent
+    /// holds its seat for good. Returns `None` when both seats are held,
+    /// then the session watches the game.
+    pub fn join(&mut self, session_id: Uuid, token: Uuid, now: Instant) -> Option<Color> {
+        let bot_color = self.bot.map(|bot| bot.color);
+        let held = self.seats.values().copied().chain(bot_color).collect::<Vec<Color>>();
+        let color = match self.seats.get(&token) {
+            Some(color) => *color,
+            None if held.len() < 2 => held.first().map_or(Color::White, Color::opposite),
+            None => {
+                self.spectators.insert(session_id);
+                return None;
+            }
+        };
+
+        // an older session of the same seat is left watching
+        let older = self.players.iter().find(|(_id, c)| **c == color).map(|(id, _c)| *id);
+        if let Some(older) = older {
+            self.players.remove(&older);
+            self.spectators.insert(older);
+        }
+
+        self.seats.insert(token, color);
+        self.players.insert(session_id, color);
+        if self.status == Status::Waiting && self.players.len() + usize::from(self.bot.is_some()) == 2 {
+            self.status = Status::Playing;
+            self.start_clock(now);
+        }
+        Some(color)
+    }
+
+    /// Number of seats held by the players and the computer opponent
+    pub fn held_seats(&self) -> usize {
+        self.seats.len() + usize::from(self.bot.is_some())
+    }
+
+    /// Whether a new player can take a seat in the game
+    pub fn is_joinable(&self) -> bool {
+        self.status == Status::Waiting && self.held_seats() < 2
+    }
+
+    /// Release the seat of a token whose player didn't reconnect, the game
+    /// waits for another player. Seats of a finished game are kept.
+    pub fn release_seat(&mut self, token: &Uuid, now: Instant) -> bool {
+        let color = match self.seats.get(token) {
+            Some(color) => *color,
+            None => return false
+        };
+        if self.status == Status::GameOver || self.players.values().any(|c| *c == color) {
+            return false;
+        }
+
+        self.seats.remove(token);
+        self.names.remove(token);
+        self.status = Status::Waiting;
+        self.stop_clock(now);
+        true
+    }
+
+    /// Seat token of the signed-in player of the color, `None` for a guest,
+    /// the computer or an empty seat
+    pub fn signed_in_seat(&self, color: Color) -> Option<Uuid> {
+        self.seats.iter()
+            .find(|(token, c)| **c == color && self.names.contains_key(token))
+            .map(|(token, _c)| *token)
+    }
+
+    /// Seat tokens of White and Black and the score of White when the game
+    /// counts for the ratings, a finished rated game of two signed-in players
+    pub fn rated_result(&self) -> Option<(Uuid, Uuid, f64)> {
+        if !self.rated || self.bot.is_some() || self.status != Status::GameOver {
+            return None;
+        }
+        Some((self.signed_in_seat(Color::White)?, self.signed_in_seat(Color::Black)?, self.winner.white_score()?))
+    }
+
+    /// Display name of the player of the color, `None` for a guest or an empty seat
+    pub fn player_name(&self, color: Color) -> Option<&str> {
+        if self.bot.is_some_and(|bot| bot.color == color) {
+            return Some("Computer");
+        }
+        self.seats.iter()
+            .find(|(_token, c)| **c == color)
+            .and_then(|(token, _c)| self.names.get(token))
+            .map(String::as_str)
+    }
+
+    /// Whether the session is watching the game without a seat
+    pub fn is_spectator(&self, id: &Uuid) -> bool {
+        self.spectators.contains(id)
+    }
+
+    /// Sessions of the players and the spectators of the game
+    pub fn sessions(&self) -> impl Iterator<Item = &Uuid> {
+        self.players.keys().chain(self.spectators.iter())
+    }
+
+    /// Get the color of a player of a game in progress
+    fn playing_color(&self, player_id: &Uuid) -> Result<Color, Error> {
+        let color = *self.players.get(player_id)
+            .ok_or_else(|| GameError("You are not a player in this game.".to_owned()))?;
+
+        if self.status != Status::Playing {
+            return Err(GameError(format!("The game is {}.", self.status.as_str())));
+        }
+
+        Ok(color)
+    }
+
+    /// Resign the game, the opponent of the player wins
+    pub fn resign(&mut self, player_id: &Uuid) -> Result<Color, Error> {
+        let color = self.playing_color(player_id)?;
+        self.finish(Winner::from_color(color.opposite()), Termination::Resignation);
+        Ok(color)
+    }
+
+    /// Offer a draw to the opponent of the player
+    pub fn offer_draw(&mut self, player_id: &Uuid) -> Result<Color, Error> {
+        let color = self.playing_color(player_id)?;
+        match self.draw_offer {
+            Some(offer) if offer == color => Err(GameError("You already offered a draw.".to_owned())),
+            Some(_) => Err(GameError("Your opponent already offered a draw.".to_owned())),
+            None => {
+                self.draw_offer = Some(color);
+                Ok(color)
+            }
+        }
+    }
+
+    /// Accept the draw offered by the opponent of the player
+    pub fn accept_draw(&mut self, player_id: &Uuid) -> Result<Color, Error> {
+        let color = self.playing_color(player_id)?;
+        if self.draw_offer != Some(color.opposite()) {
+            return Err(GameError("There is no draw offer to accept.".to_owned()));
+        }
+        self.finish(Winner::Draw, Termination::Agreement);
+        Ok(color)
+    }
+
+    /// Decline the draw offered by the opponent of the player
+    pub fn decline_draw(&mut self, player_id: &Uuid) -> Result<Color, Error> {
+        let color = self.playing_color(player_id)?;
+        if self.draw_offer != Some(color.opposite()) {
+            return Err(GameError("There is no draw offer to decline.".to_owned()));
+        }
+        self.draw_offer = None;
+        Ok(color)
+    }
+
+    /// Get the board after the first `ply` moves, and the last of them in SAN
+    pub fn position(&self, ply: usize) -> Result<(Board<Piece>, Option<String>), Error> {
+        let board = self.board.position_at(ply)?;
+        let san = match ply.checked_sub(1) {
+            Some(last) => {
+                let his = &self.board.get_history()[last];
+                Some(san::to_san(&self.board.position_at(last)?, &his.from, &his.to, his.promotion)?)
+            },
+            None => None
+        };
+        Ok((board, san))
+    }
+
+    /// Export the game as PGN with the Seven Tag Roster
+    ///
+    /// The players are tagged with their display names, `Guest` for a
+    /// guest and `?` for an empty seat.
+    pub fn to_pgn(&self) -> Result<String, Error> {
+        let player = |color: Color| match (self.bot, self.player_name(color)) {
+            (Some(bot), _) if bot.color == color => format!("Webactix Engine (depth {})", bot.engine.depth),
+            (_, Some(name)) => name.to_owned(),
+            _ if self.seats.values().any(|c| *c == color) => "Guest".to_owned(),
+            _ => "?".to_owned()
+        };
+
+        let tags = vec![
+            ("Event".to_owned(), "Webactix Chess Game".to_owned()),
+            ("Site".to_owned(), "Webactix".to_owned()),
+            ("Date".to_owned(), self.created_at.format("%Y.%m.%d").to_string()),
+            ("Round".to_owned(), "-".to_owned()),
+            ("White".to_owned(), player(Color::White)),
+            ("Black".to_owned(), player(Color::Black)),
+            ("Result".to_owned(), self.winner.as_result().to_owned()),
+            ("TimeControl".to_owned(), self.time_control.as_pgn()),
+        ];
+
+        Ok(Pgn::new(tags, &self.board)?.to_string())
+    }
+
+    /// Reconstruct a game by replaying the moves of a PGN
+    ///
+    /// The game is over when the PGN has a result, players are not restored.
+    pub fn from_pgn(pgn: &str) -> Result<Game, Error> {
+        let pgn = Pgn::parse(pgn)?;
+        let mut game = Game::new();
+        game.board = pgn.to_board()?;
+        game.winner = Winner::from_result(pgn.tag("Result").unwrap_or("*"));
+        if game.winner != Winner::NotDecided {
+            game.status = Status::GameOver;
+        }
+        Ok(game)
+    }
+
+    /// Get the color of the side to move
+    pub fn side_to_move(&self) -> Color {
+        Color::from_color(self.board.side_to_move())
+    }
+
+    /// Check that the player is allowed to move the piece on `cell`
+    ///
+    /// The game must be in progress, it must be the player's turn,
+    /// and the piece on `cell` must belong to the player.
+    pub fn validate_move(&self, player_id: &Uuid, cell: &str) -> Result<Color, Error> {
+        let color = *self.players.get(player_id)
+            .ok_or_else(|| GameError("You are not a player in this game.".to_owned()))?;
+        self.validate_color_move(color, cell)
+    }
+
+    /// Check that the player token holding a seat is allowed to move the
+    /// piece on `cell`, whether its player is connected or not
+    pub fn validate_seat_move(&self, token: &Uuid, cell: &str) -> Result<Color, Error> {
+        let color = *self.seats.get(token)
+            .ok_or_else(|| GameError("You are not a player in this game.".to_owned()))?;
+        self.validate_color_move(color, cell)
+    }
+
+    fn validate_color_move(&self, color: Color, cell: &str) -> Result<Color, Error> {
+        if self.status != Status::Playing {
+            return Err(GameError(format!("Can't move, the game is {}.", self.status.as_str())));
+        }
+
+        if color != self.side_to_move() {
+            return Err(TurnError(format!("It's {} turn.", self.side_to_move().as_str())));
+        }
+
+        match self.board.get(cell)? {
+            Some(piece) if piece.color == color.as_color() => Ok(color),
+            Some(_) => Err(TurnError(format!("Can't move your opponent's piece on {cell}."))),
+            None => Err(IllegalMoves(format!("There is no piece on {cell}.")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing_game() -> (Game, Uuid, Uuid) {
+        let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = Game::new();
+        game.players.insert(white, Color::White);
+        game.players.insert(black, Color::Black);
+        game.status = Status::Playing;
+        (game, white, black)
+    }
+
+    #[test]
+    fn game_validate_move() -> Result<(), Error> {
+        let (mut game, white, black) = playing_game();
+        assert_eq!(game.validate_move(&white, "e2")?, Color::White);
+        assert!(matches!(game.validate_move(&black, "e7"), Err(TurnError(_))));
+        assert!(matches!(game.validate_move(&white, "e7"), Err(TurnError(_))));
+        assert!(matches!(game.validate_move(&white, "e4"), Err(IllegalMoves(_))));
+        assert!(matches!(game.validate_move(&Uuid::new_v4(), "e2"), Err(GameError(_))));
+
+        game.board.moves_piece("e2", "e4", None)?;
+        assert_eq!(game.side_to_move(), Color::Black);
+        assert_eq!(game.validate_move(&black, "e7")?, Color::Black);
+        assert!(matches!(game.validate_move(&white, "d2"), Err(TurnError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn game_position() -> Result<(), Error> {
+        let mut game = Game::new();
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("g1", "f3")] {
+            game.board.moves_piece(from, to, None)?;
+        }
+
+        let (board, san) = game.position(0)?;
+        assert_eq!((board, san), (Board::new(), None));
+        let (board, san) = game.position(2)?;
+        assert_eq!(board.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+        assert_eq!(san.as_deref(), Some("e5"));
+        assert_eq!(game.position(3)?.1.as_deref(), Some("Nf3"));
+        assert!(matches!(game.position(4), Err(GameError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn game_pgn() -> Result<(), Error> {
+        let (mut game, white, black) = playing_game();
+        let token = Uuid::new_v4();
+        game.seats.insert(token, Color::White);
+        game.names.insert(token, "Alice".to_owned());
+        game.board.moves_piece("e2", "e4", None)?;
+        game.board.moves_piece("e7", "e5", None)?;
+        game.board.moves_piece("d1", "h5", None)?;
+        game.board.moves_piece("b8", "c6", None)?;
+        game.board.moves_piece("f1", "c4", None)?;
+        game.board.moves_piece("g8", "f6", None)?;
+        game.board.moves_piece("h5", "f7", None)?;
+        game.status = Status::GameOver;
+        game.winner = Winner::White;
+
+        let pgn = game.to_pgn()?;
+        assert!(pgn.contains("[White \"Alice\"]"));
+        assert!(pgn.contains("[Black \"?\"]"));
+        game.seats.insert(Uuid::new_v4(), Color::Black);
+        assert!(game.to_pgn()?.contains("[Black \"Guest\"]"));
+        for id in [white, black, token] {
+            assert!(!pgn.contains(&id.to_string()));
+        }
+        assert!(pgn.contains("[Result \"1-0\"]"));
+        assert!(pgn.contains(&format!("[Date \"{}\"]", game.created_at.format("%Y.%m.%d"))));
+        assert!(pgn.ends_with("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n"));
+
+        let replayed = Game::from_pgn(&pgn)?;
+        assert_eq!(replayed.board.cells, game.board.cells);
+        assert_eq!(replayed.winner, Winner::White);
+        assert_eq!(replayed.status, Status::GameOver);
+        Ok(())
+    }
+
+    #[test]
+    fn game_validate_seat_move() -> Result<(), Error> {
+        let (mut game, white, black) = playing_game();
+        let token = Uuid::new_v4();
+        game.seats.insert(token, Color::Black);
+        assert!(matches!(game.validate_seat_move(&token, "e7"), Err(TurnError(_))));
+        assert!(matches!(game.validate_seat_move(&white, "e2"), Err(GameError(_))));
+
+        game.board.moves_piece("e2", "e4", None)?;
+        game.players.remove(&black);
+        assert_eq!(game.validate_seat_move(&token, "e7")?, Color::Black);
+        Ok(())
+    }
+
+    #[test]
+    fn game_validate_move_not_playing() {
+        let (mut game, white, _black) = playing_game();
+        game.status = Status::Waiting;
+        assert!(matches!(game.validate_move(&white, "e2"), Err(GameError(_))));
+    }
+
+    #[test]
+    fn game_resign() -> Result<(), Error> {
+        let (mut game, white, black) = playing_game();
+        assert!(game.resign(&Uuid::new_v4()).is_err());
+        assert_eq!(game.resign(&black)?, Color::Black);
+        assert_eq!(game.status, Status::GameOver);
+        assert_eq!(game.winner, Winner::White);
+        assert_eq!(game.termination, Some(Termination::Resignation));
+        assert!(matches!(game.resign(&white), Err(GameError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn game_draw_offer() -> Result<(), Error> {
+        let (mut game, white, black) = playing_game();
+        assert!(game.accept_draw(&black).is_err());
+        game.offer_draw(&white)?;
+        assert!(game.offer_draw(&white).is_err());
+        assert!(game.offer_draw(&black).is_err());
+        assert!(game.accept_draw(&white).is_err());
+        game.decline_draw(&black)?;
+        assert_eq!(game.draw_offer, None);
+        assert!(game.accept_draw(&black).is_err());
+
+        game.offer_draw(&black)?;
+        game.accept_draw(&white)?;
+        assert_eq!(game.status, Status::GameOver);
+        assert_eq!(game.winner, Winner::Draw);
+        assert_eq!(game.termination, Some(Termination::Agreement));
+        Ok(())
+    }
+
+    #[test]
+    fn game_rated_result() {
+        let mut game = Game::new();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = Instant::now();
+        game.join(Uuid::new_v4(), alice, now);
+        game.join(Uuid::new_v4(), bob, now);
+        game.names.insert(alice, "Alice".to_owned());
+        game.finish(Winner::Black, Termination::Resignation);
+
+        // a guest doesn't play rated games
+        assert_eq!(game.rated_result(), None);
+        game.names.insert(bob, "Bob".to_owned());
+        assert_eq!(game.rated_result(), Some((alice, bob, 0.0)));
+        game.rated = false;
+        assert_eq!(game.rated_result(), None);
+    }
+
+    #[test]
+    fn game_join() {
+        let mut game = Game::new();
+        let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = Instant::now();
+        assert!(game.is_joinable());
+        assert_eq!(game.join(Uuid::new_v4(), white, now), Some(Color::White));
+        assert_eq!(game.status, Status::Waiting);
+        assert!(game.is_joinable());
+        assert_eq!(game.join(Uuid::new_v4(), black, now), Some(Color::Black));
+        assert_eq!(game.status, Status::Playing);
+        assert!(!game.is_joinable());
+
+        let spectator = Uuid::new_v4();
+        assert_eq!(game.join(spectator, Uuid::new_v4(), now), None);
+        assert!(game.is_spectator(&spectator));
+
+        // a new session of the token takes over the seat
+        let older = *game.players.iter().find(|(_id, c)| **c == Color::White).unwrap().0;
+        let session = Uuid::new_v4();
+        assert_eq!(game.join(session, white, now), Some(Color::White));
+        assert_eq!(game.players.get(&session), Some(&Color::White));
+        assert!(game.is_spectator(&older));
+        assert_eq!(game.players.len(), 2);
+    }
+
+    #[test]
+    fn game_release_seat() {
+        let mut game = Game::new();
+        let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = Instant::now();
+        let session = Uuid::new_v4();
+        game.join(session, white, now);
+        game.join(Uuid::new_v4(), black, now);
+        game.names.insert(white, "Alice".to_owned());
+        assert_eq!(game.player_name(Color::White), Some("Alice"));
+        assert_eq!(game.player_name(Color::Black), None);
+
+        // the seat is kept while its player is connected
+        assert!(!game.release_seat(&white, now));
+        game.players.remove(&session);
+        assert!(game.release_seat(&white, now));
+        assert_eq!(game.status, Status::Waiting);
+        assert_eq!(game.player_name(Color::White), None);
+        assert_eq!(game.signed_in_seat(Color::White), None);
+        assert!(!game.release_seat(&white, now));
+
+        // someone else takes the released color
+        assert_eq!(game.join(Uuid::new_v4(), Uuid::new_v4(), now), Some(Color::White));
+        assert_eq!(game.status, Status::Playing);
+    }
+
+    #[test]
+    fn game_bot() -> Result<(), Error> {
+        let bot = Bot { color: Color::White, engine: Engine::default() };
+        let mut game = Game::with_bot(TimeControl::Correspondence, bot);
+        let now = Instant::now();
+        assert_eq!(game.bot_to_move(), None);
+
+        // the human takes the other seat and the game starts with the bot to move
+        assert_eq!(game.join(Uuid::new_v4(), Uuid::new_v4(), now), Some(Color::Black));
+        assert_eq!(game.status, Status::Playing);
+        assert_eq!(game.bot_to_move(), Some(bot));
+        assert_eq!(game.player_name(Color::White), Some("Computer"));
+        assert_eq!(game.join(Uuid::new_v4(), Uuid::new_v4(), now), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn time_control_parse() -> Result<(), Error> {
+        assert_eq!(TimeControl::parse("5+3")?, TimeControl::Clock { minutes: 5, increment: 3 });
+        assert_eq!(TimeControl::parse("correspondence")?, TimeControl::Correspondence);
+        assert_eq!(TimeControl::parse("15+10")?.to_string(), "15+10");
+        assert!(TimeControl::parse("5").is_err());
+        assert!(TimeControl::parse("0+0").is_err());
+        assert!(TimeControl::parse("a+b").is_err());
+        assert_eq!(TimeControl::parse("5+3")?.as_pgn(), "300+3");
+        assert_eq!(TimeControl::Correspondence.as_pgn(), "-");
+        Ok(())
+    }
+
+    #[test]
+    fn time_control_category() -> Result<(), Error> {
+        assert_eq!(TimeControl::parse("1+0")?.category(), Category::Bullet);
+        assert_eq!(TimeControl::parse("2+1")?.category(), Category::Bullet);
+        assert_eq!(TimeControl::parse("3+0")?.category(), Category::Blitz);
+        assert_eq!(TimeControl::parse("5+3")?.category(), Category::Blitz);
+        assert_eq!(TimeControl::parse("15+10")?.category(), Category::Rapid);
+        assert_eq!(TimeControl::parse("30+0")?.category(), Category::Classical);
+        assert_eq!(TimeControl::Correspondence.category(), Category::Correspondence);
+        Ok(())
+    }
+
+    #[test]
+    fn clock_press() {
+        let now = Instant::now();
+        let mut clock = Clock::new(TimeControl::Clock { minutes: 5, increment: 3 }).unwrap();
+        clock.start(Color::White, now);
+        assert_eq!(clock.remaining(Color::White, now + Duration::from_secs(10)), Duration::from_secs(290));
+        assert_eq!(clock.remaining(Color::Black, now + Duration::from_secs(10)), Duration::from_secs(300));
+
+        clock.press(Color::White, now + Duration::from_secs(10));
+        assert_eq!(clock.white, Duration::from_secs(293));
+        assert_eq!(clock.remaining(Color::Black, now + Duration::from_secs(30)), Duration::from_secs(280));
+
+        clock.stop(now + Duration::from_secs(30));
+        assert_eq!(clock.black, Duration::from_secs(280));
+        assert_eq!(clock.running, None);
+        assert_eq!(clock.remaining(Color::Black, now + Duration::from_secs(400)), Duration::from_secs(280));
+        assert!(Clock::new(TimeControl::Correspondence).is_none());
+    }
+
+    #[test]
+    fn game_check_flag() -> Result<(), Error> {
+        let now = Instant::now();
+        let (mut game, _white, _black) = playing_game();
+        game.clock = Clock::new(TimeControl::Clock { minutes: 1, increment: 0 });
+        game.start_clock(now);
+        assert_eq!(game.check_flag(now + Duration::from_secs(59)), None);
+        assert_eq!(game.check_flag(now + Duration::from_secs(60)), Some(Color::White));
+        assert_eq!(game.winner, Winner::Black);
+        assert_eq!(game.termination, Some(Termination::Timeout));
+        assert_eq!(game.time_to_flag(now), None);
+
+        let (mut game, _white, _black) = playing_game();
+        game.board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1")?;
+        game.clock = Clock::new(TimeControl::Clock { minutes: 1, increment: 0 });
+        game.start_clock(now);
+        assert_eq!(game.check_flag(now + Duration::from_secs(61)), Some(Color::White));
+        assert_eq!(game.winner, Winner::Draw);
+        assert_eq!(game.termination, Some(Termination::TimeoutVsInsufficientMaterial));
+        Ok(())
+    }
+
+    #[test]
+    fn game_clock_state() {
+        let now = Instant::now();
+        let mut game = Game::with_time_control(TimeControl::Clock { minutes: 5, increment: 0 });
+        game.status = Status::Playing;
+        assert_eq!(game.clock_state(now), Some(ClockState { white: 300000, black: 300000, running: None }));
+        game.start_clock(now);
+        assert_eq!(
+            game.clock_state(now + Duration::from_millis(1500)),
+            Some(ClockState { white: 298500, black: 300000, running: Some(Color::White) })
+        );
+        assert_eq!(Game::new().clock_state(now), None);
+    }
 }
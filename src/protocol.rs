@@ -0,0 +1,453 @@
+//! WebSocket Protocol
+//!
+//! Clients send a [`Request`] and receive [`Response`]s as JSON objects
+//! tagged by `type` with the protocol `version`, e.g.
+//! `{"version":1,"type":"move","from":"e2","to":"e4"}`.
+//!
+//! Sessions connected with `?protocol=text` keep using the legacy
+//! backslash commands, e.g. `\move e2 e4`, and free-form replies.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chess::{
+    game::prelude::{Board, Piece, Level, History, DrawReason},
+    error::Error::{self, *}
+};
+use crate::model::{Color, Status, Winner, Termination, ClockState, GameSummary};
+
+/// Version of the JSON protocol
+pub const VERSION: u32 = 1;
+
+/// Protocol of a session: `Json` or the legacy `Text` commands
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    #[default]
+    Json,
+    Text
+}
+
+/// Requests from a client
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Whisper { to: Uuid, message: String },
+    Chat { message: String },
+    GetLobby,
+    GetAvailableGames,
+    GetGameState,
+    GetColor,
+    GetBoard,
+    GetStatus,
+    GetFen,
+    LoadFen { fen: String },
+    GetPgn,
+    LoadPgn { pgn: String },
+    Move { from: String, to: String, #[serde(default)] promotion: Option<Level> },
+    MoveSan { san: String },
+    Castle { king: String, rook: String },
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    GetPossibleMoves { cell: String },
+    GetCaptured { color: Color },
+    /// Board after the first `ply` moves of the game
+    GetPosition { ply: usize }
+}
+
+#[derive(Deserialize)]
+struct RequestEnvelope {
+    version: u32,
+    #[serde(flatten)]
+    request: Request
+}
+
+impl Request {
+    /// Parse a request sent with the `protocol`
+    pub fn parse(text: &str, protocol: Protocol) -> Result<Request, Error> {
+        match protocol {
+            Protocol::Json => Request::from_json(text),
+            Protocol::Text => Request::from_text(text)
+        }
+    }
+
+    /// Parse a JSON request, e.g. `{"version":1,"type":"get_board"}`
+    pub fn from_json(text: &str) -> Result<Request, Error> {
+        let envelope: RequestEnvelope = serde_json::from_str(text)
+            .map_err(|err| ProtocolError(format!("invalid request, {}", err)))?;
+        if envelope.version != VERSION {
+            return Err(ProtocolError(format!("unsupported version {}, expected {}", envelope.version, VERSION)));
+        }
+        Ok(envelope.request)
+    }
+
+    /// Parse a legacy text command, e.g. `\move e2 e4`,
+    /// anything not starting with a backslash is a chat message
+    pub fn from_text(text: &str) -> Result<Request, Error> {
+        if !text.starts_with('\\') {
+            return Ok(Request::Chat { message: text.to_owned() });
+        }
+
+        let args = text.split_whitespace().collect::<Vec<&str>>();
+        let arg = |i: usize| args.get(i)
+            .map(|arg| arg.to_string())
+            .ok_or_else(|| ProtocolError(format!("missing argument for {}", args[0])));
+        let rest = |cmd: &str| text.trim_start_matches(cmd).trim().to_owned();
+
+        let request = match args[0] {
+            "\\w" => Request::Whisper {
+                to: Uuid::parse_str(&arg(1)?).map_err(|err| ProtocolError(err.to_string()))?,
+                message: text.to_owned()
+            },
+            "\\get_lobby" => Request::GetLobby,
+            "\\get_available_games" => Request::GetAvailableGames,
+            "\\get_game_stat" => Request::GetGameState,
+            "\\get_color" => Request::GetColor,
+            "\\get_board" => Request::GetBoard,
+            "\\get_status" => Request::GetStatus,
+            "\\get_fen" => Request::GetFen,
+            "\\load_fen" => Request::LoadFen { fen: rest("\\load_fen") },
+            "\\get_pgn" => Request::GetPgn,
+            "\\load_pgn" => Request::LoadPgn { pgn: rest("\\load_pgn") },
+            "\\move" if args.len() == 2 => Request::MoveSan { san: arg(1)? },
+            "\\move" => {
+                let promotion = match args.get(3).copied() {
+                    None => None,
+                    Some("queen") => Some(Level::Queen),
+                    Some("rook") => Some(Level::Rook),
+                    Some("bishop") => Some(Level::Bishop),
+                    Some("knight") => Some(Level::Knight),
+                    Some(other) => return Err(ProtocolError(format!("can't promote to {}", other)))
+                };
+                Request::Move { from: arg(1)?, to: arg(2)?, promotion }
+            },
+            "\\castling" => Request::Castle { king: arg(1)?, rook: arg(2)? },
+            "\\resign" => Request::Resign,
+            "\\offer_draw" => Request::OfferDraw,
+            "\\accept_draw" => Request::AcceptDraw,
+            "\\decline_draw" => Request::DeclineDraw,
+            "\\get_possible_moves" => Request::GetPossibleMoves { cell: arg(1)? },
+            "\\get_captured" => {
+                let color = match arg(1)?.as_str() {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    other => return Err(ProtocolError(format!("unknown color {}", other)))
+                };
+                Request::GetCaptured { color }
+            },
+            "\\get_position" => Request::GetPosition {
+                ply: arg(1)?.parse().map_err(|_| ProtocolError(format!("invalid ply {}", args[1])))?
+            },
+            cmd => return Err(ProtocolError(format!("unknown command {}", cmd)))
+        };
+
+        Ok(request)
+    }
+
+    /// Whether the request needs a seat in the game, so spectators can't send it
+    pub fn is_player_action(&self) -> bool {
+        matches!(
+            self,
+            Request::LoadFen { .. } | Request::LoadPgn { .. } | Request::Move { .. } | Request::MoveSan { .. }
+            | Request::Castle { .. } | Request::Resign | Request::OfferDraw
+            | Request::AcceptDraw | Request::DeclineDraw
+        )
+    }
+}
+
+/// Responses to the clients
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Session { id: Uuid, spectator: bool },
+    Joined { id: Uuid },
+    Spectators { count: usize },
+    /// Display names of the players, `None` for a guest or an empty seat,
+    /// and the ratings of the signed-in ones
+    Players {
+        white: Option<String>,
+        black: Option<String>,
+        white_rating: Option<u32>,
+        black_rating: Option<u32>,
+        rated: bool
+    },
+    Disconnected { id: Uuid },
+    Chat { from: Uuid, message: String },
+    Lobby { dump: String },
+    /// Joinable rooms
+    Rooms { rooms: Vec<GameSummary> },
+    /// The session waits in the matchmaking queue
+    Seeking { time_control: String, rating: u32 },
+    /// The matchmaking has paired the session, the player's seat is held in the room
+    Matched { room: Uuid, color: Color },
+    GameState {
+        status: Status,
+        winner: Winner,
+        termination: Option<Termination>,
+        time_control: String,
+        history: Vec<History>,
+        spectators: usize
+    },
+    Color { color: Color },
+    Board { board: Board<Piece>, clock: Option<ClockState> },
+    /// Board after the first `ply` moves, and the last of them in SAN
+    Position { ply: usize, san: Option<String>, fen: String, board: Board<Piece> },
+    Status { status: Status },
+    Fen { fen: String },
+    Pgn { pgn: String },
+    Moved {
+        description: String,
+        san: Option<String>,
+        check: bool,
+        checkmate: bool,
+        draw: Option<DrawReason>
+    },
+    PossibleMoves { cell: String, moves: Vec<String> },
+    Captured { color: Color, pieces: Vec<Piece> },
+    Resigned { color: Color },
+    DrawOffered { color: Color },
+    DrawAccepted { color: Color },
+    DrawDeclined { color: Color },
+    Flagged { color: Color },
+    Result { winner: Winner, termination: Option<Termination> },
+    Error { message: String }
+}
+
+#[derive(Serialize)]
+struct ResponseEnvelope<'a> {
+    version: u32,
+    #[serde(flatten)]
+    response: &'a Response
+}
+
+impl From<Error> for Response {
+    fn from(err: Error) -> Response {
+        Response::Error { message: err.to_string() }
+    }
+}
+
+impl Response {
+    /// Render the response for the `protocol`
+    pub fn render(&self, protocol: Protocol) -> String {
+        match protocol {
+            Protocol::Json => self.to_json(),
+            Protocol::Text => self.to_text()
+        }
+    }
+
+    /// Render the response as JSON with the protocol version
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&ResponseEnvelope { version: VERSION, response: self })
+            .unwrap_or_else(|err| format!(
+                "{{\"version\":{},\"type\":\"error\",\"message\":\"{}\"}}",
+                VERSION, err.to_string().replace('"', "'")
+            ))
+    }
+
+    /// Render the response as the legacy text reply
+    pub fn to_text(&self) -> String {
+        match self {
+            Response::Session { id, spectator: false } => format!("your session_id is {}", id),
+            Response::Session { id, spectator: true } => format!("your session_id is {}, you are spectating", id),
+            Response::Joined { id } => format!("{} just joined!", id),
+            Response::Spectators { count } => format!("spectators: {}", count),
+            Response::Players { white, black, white_rating, black_rating, rated } => {
+                let player = |name: &Option<String>, rating: &Option<u32>| match rating {
+                    Some(rating) => format!("{} ({})", name.as_deref().unwrap_or("guest"), rating),
+                    None => name.as_deref().unwrap_or("guest").to_owned()
+                };
+                let mut output = format!("players: {} vs {}", player(white, white_rating), player(black, black_rating));
+                if !rated {
+                    output.push_str(", casual");
+                }
+                output
+            },
+            Response::Disconnected { id } => format!("{} disconnected.", id),
+            Response::Chat { message, .. } => message.to_owned(),
+            Response::Lobby { dump } => dump.to_owned(),
+            Response::Rooms { rooms } => {
+                let mut buf = [b'!'; 36];
+                let mut output = "rooms:".to_owned();
+                for room in rooms {
+                    output.push_str(&format!(
+                        "\n{} {} {}/2 {} {}",
+                        room.id.simple().encode_lower(&mut buf),
+                        room.status.as_str(),
+                        room.players,
+                        room.time_control,
+                        room.created_at.to_rfc3339()
+                    ));
+                }
+                output
+            },
+            Response::Seeking { time_control, rating } => format!("seeking: {} {}", time_control, rating),
+            Response::Matched { room, color } => format!("matched: {} {}", room, color.as_str()),
+            Response::GameState { status, history, .. } => {
+                let mut output = format!("game stat: {}\nturn: {}\nhistories:\n", status.as_str(), history.len());
+                for his in history {
+                    output.push_str(&format!("{}\n", his));
+                }
+                output
+            },
+            Response::Color { color } => format!("color: {}", color.as_str()),
+            Response::Board { board, clock } => {
+                let mut output = format!("board:\n{}", board);
+                if let Some(clock) = clock {
+                    let running = clock.running.as_ref().map_or("-", |color| color.as_str());
+                    output.push_str(&format!("\nclock: {} {} {}", clock.white, clock.black, running));
+                }
+                output
+            },
+            Response::Position { ply, san, fen, board } => match san {
+                Some(san) => format!("position {} after {}:\n{}fen: {}", ply, san, board, fen),
+                None => format!("position {}:\n{}fen: {}", ply, board, fen)
+            },
+            Response::Status { status } => format!("status: {}", status.as_str()),
+            Response::Fen { fen } => format!("fen: {}", fen),
+            Response::Pgn { pgn } => format!("pgn:\n{}", pgn),
+            Response::Moved { description, san, check, checkmate, draw } => {
+                let mut output = match san {
+                    Some(san) => format!("history: {} ({})", description, san),
+                    None => format!("history: {}", description)
+                };
+                if *checkmate {
+                    output.push_str(" Checkmate");
+                } else if *check {
+                    output.push_str(" Check");
+                }
+                if let Some(reason) = draw {
+                    output.push_str(&format!(" Draw ({})", reason));
+                }
+                output
+            },
+            Response::PossibleMoves { cell, moves } => format!("possible moves for:\n{}\n{}", cell, moves.join(" ")),
+            Response::Captured { color, pieces } => {
+                let pieces = pieces.iter().map(|piece| piece.to_string()).collect::<Vec<String>>();
+                format!("captured_{}: {}", color.as_str(), pieces.join(" "))
+            },
+            Response::Resigned { color } => format!("{} resigned.", color.as_str()),
+            Response::DrawOffered { color } => format!("draw offered: {}", color.as_str()),
+            Response::DrawAccepted { color } => format!("draw accepted: {}", color.as_str()),
+            Response::DrawDeclined { color } => format!("draw declined: {}", color.as_str()),
+            Response::Flagged { color } => format!("{} ran out of time.", color.as_str()),
+            Response::Result { winner, termination } => match termination {
+                Some(termination) => format!("result: {} {}", winner.as_result(), termination.as_str()),
+                None => format!("result: {}", winner.as_result())
+            },
+            Response::Error { message } => format!("Error: {}", message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Game, TimeControl};
+    use super::*;
+
+    #[test]
+    fn request_from_json() -> Result<(), Error> {
+        let request = Request::from_json(r#"{"version":1,"type":"move","from":"e2","to":"e4"}"#)?;
+        assert_eq!(request, Request::Move { from: "e2".to_owned(), to: "e4".to_owned(), promotion: None });
+
+        let request = Request::from_json(r#"{"version":1,"type":"move","from":"e7","to":"e8","promotion":"Queen"}"#)?;
+        assert_eq!(request, Request::Move { from: "e7".to_owned(), to: "e8".to_owned(), promotion: Some(Level::Queen) });
+        assert!(matches!(Request::from_json(r#"{"version":1,"type":"promote","cell":"e8","level":"Queen"}"#), Err(ProtocolError(_))));
+
+        let request = Request::from_json(r#"{"version":1,"type":"get_captured","color":"black"}"#)?;
+        assert_eq!(request, Request::GetCaptured { color: Color::Black });
+
+        assert!(matches!(Request::from_json(r#"{"version":2,"type":"get_board"}"#), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_json(r#"{"type":"get_board"}"#), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_json(r#"{"version":1,"type":"fly"}"#), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_json("\\get_board"), Err(ProtocolError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn request_from_text() -> Result<(), Error> {
+        assert_eq!(Request::from_text("\\get_board")?, Request::GetBoard);
+        assert_eq!(Request::from_text("\\move Nf3")?, Request::MoveSan { san: "Nf3".to_owned() });
+        assert_eq!(
+            Request::from_text("\\move e2 e4")?,
+            Request::Move { from: "e2".to_owned(), to: "e4".to_owned(), promotion: None }
+        );
+        assert_eq!(
+            Request::from_text("\\move e7 e8 knight")?,
+            Request::Move { from: "e7".to_owned(), to: "e8".to_owned(), promotion: Some(Level::Knight) }
+        );
+        assert_eq!(
+            Request::from_text("\\load_fen 8/8/8/8/8/8/8/8 w - - 0 1")?,
+            Request::LoadFen { fen: "8/8/8/8/8/8/8/8 w - - 0 1".to_owned() }
+        );
+        assert_eq!(Request::from_text("\\get_position 12")?, Request::GetPosition { ply: 12 });
+        assert_eq!(Request::from_text("hello")?, Request::Chat { message: "hello".to_owned() });
+
+        assert!(matches!(Request::from_text("\\move"), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_text("\\move e7 e8 king"), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_text("\\promote e8 queen"), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_text("\\w not-a-uuid hi"), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_text("\\get_position"), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_text("\\get_position -1"), Err(ProtocolError(_))));
+        assert!(matches!(Request::from_text("\\fly"), Err(ProtocolError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn response_to_json() {
+        let json = Response::Color { color: Color::White }.to_json();
+        assert_eq!(json, r#"{"version":1,"type":"color","color":"white"}"#);
+
+        let json = Response::Status { status: Status::GameOver }.to_json();
+        assert_eq!(json, r#"{"version":1,"type":"status","status":"game_over"}"#);
+
+        let json = Response::Board { board: Board::new(), clock: None }.to_json();
+        assert!(json.starts_with(r#"{"version":1,"type":"board","board":{"cells":[[{"level":"Rook""#));
+    }
+
+    #[test]
+    fn response_to_text() {
+        assert_eq!(Response::Color { color: Color::Black }.to_text(), "color: black");
+        assert_eq!(
+            Response::Result { winner: Winner::White, termination: Some(Termination::Resignation) }.to_text(),
+            "result: 1-0 resignation"
+        );
+        assert_eq!(Response::from(GameError("oops".to_owned())).to_text(), "Error: Game Error: oops");
+        assert_eq!(Response::Spectators { count: 2 }.to_text(), "spectators: 2");
+        let players = Response::Players {
+            white: Some("Alice".to_owned()),
+            black: None,
+            white_rating: Some(1500),
+            black_rating: None,
+            rated: false
+        };
+        assert_eq!(players.to_text(), "players: Alice (1500) vs guest, casual");
+
+        let id = Uuid::new_v4();
+        let game = Game::with_time_control(TimeControl::Clock { minutes: 5, increment: 3 });
+        let text = Response::Rooms { rooms: vec![GameSummary::new(id, &game)] }.to_text();
+        assert!(text.starts_with(&format!("rooms:\n{} waiting 0/2 5+3 ", id.simple())));
+        assert_eq!(
+            Response::Matched { room: id, color: Color::White }.to_text(),
+            format!("matched: {} white", id)
+        );
+
+        let clock = ClockState { white: 300000, black: 295000, running: Some(Color::Black) };
+        let text = Response::Board { board: Board::new(), clock: Some(clock) }.to_text();
+        assert!(text.starts_with("board:\n"));
+        assert!(text.ends_with("\nclock: 300000 295000 black"));
+
+        let position = Response::Position { ply: 1, san: Some("e4".to_owned()), fen: "fen".to_owned(), board: Board::new() };
+        assert!(position.to_text().starts_with("position 1 after e4:\n"));
+        assert!(position.to_text().ends_with("\nfen: fen"));
+
+        let moved = Response::Moved {
+            description: "Moving White Queen from h5 to f7, captured Black Pawn".to_owned(),
+            san: Some("Qxf7#".to_owned()),
+            check: true,
+            checkmate: true,
+            draw: None
+        };
+        assert_eq!(moved.to_text(), "history: Moving White Queen from h5 to f7, captured Black Pawn (Qxf7#) Checkmate");
+    }
+}
@@ -0,0 +1,144 @@
+//! Glicko-2 Ratings
+//!
+//! Every finished rated game is a rating period of its own for both
+//! players, see <http://www.glicko.net/glicko/glicko2.pdf>.
+
+use std::f64::consts::PI;
+use serde::Serialize;
+
+/// Rating of a player who hasn't played a rated game yet
+pub const DEFAULT_RATING: f64 = 1500.0;
+const DEFAULT_DEVIATION: f64 = 350.0;
+const DEFAULT_VOLATILITY: f64 = 0.06;
+
+/// Conversion factor between the Glicko and the Glicko-2 scales
+const SCALE: f64 = 173.7178;
+/// Constraint on the change of the volatility over time
+const TAU: f64 = 0.5;
+/// Convergence tolerance of the volatility iteration
+const EPSILON: f64 = 0.000001;
+
+/// Glicko-2 rating of a player, on the Glicko scale
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Rating {
+    pub rating: f64,
+    /// Rating deviation, the uncertainty of the rating
+    pub deviation: f64,
+    pub volatility: f64
+}
+
+impl Default for Rating {
+    fn default() -> Rating {
+        Rating { rating: DEFAULT_RATING, deviation: DEFAULT_DEVIATION, volatility: DEFAULT_VOLATILITY }
+    }
+}
+
+/// Weight of an opponent by the uncertainty of its rating
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+/// Expected score against an opponent
+fn expected(mu: f64, mu_j: f64, phi_j: f64) -> f64 {
+    1.0 / (1.0 + (-g(phi_j) * (mu - mu_j)).exp())
+}
+
+impl Rating {
+    /// Rating shown to the players
+    pub fn rounded(&self) -> u32 {
+        self.rating.round().max(0.0) as u32
+    }
+
+    /// Rating after a period with the scores (1 win, 0.5 draw, 0 loss)
+    /// against the opponents, the deviation only grows without games
+    pub fn update(&self, results: &[(Rating, f64)]) -> Rating {
+        let mu = (self.rating - DEFAULT_RATING) / SCALE;
+        let phi = self.deviation / SCALE;
+        if results.is_empty() {
+            let deviation = (phi * phi + self.volatility * self.volatility).sqrt() * SCALE;
+            return Rating { deviation: deviation.min(DEFAULT_DEVIATION), ..*self };
+        }
+
+        // estimated variance and improvement from the game outcomes
+        let opponents = results.iter()
+            .map(|(opponent, score)| {
+                let mu_j = (opponent.rating - DEFAULT_RATING) / SCALE;
+                let phi_j = opponent.deviation / SCALE;
+                (g(phi_j), expected(mu, mu_j, phi_j), *score)
+            })
+            .collect::<Vec<(f64, f64, f64)>>();
+        let v = 1.0 / opponents.iter().map(|(g, e, _s)| g * g * e * (1.0 - e)).sum::<f64>();
+        let improvement = opponents.iter().map(|(g, e, s)| g * (s - e)).sum::<f64>();
+        let delta = v * improvement;
+
+        // new volatility by the Illinois algorithm
+        let a = (self.volatility * self.volatility).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2)) - (x - a) / (TAU * TAU)
+        };
+        let mut big_a = a;
+        let mut big_b = if delta * delta > phi * phi + v {
+            (delta * delta - phi * phi - v).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * TAU) < 0.0 {
+                k += 1.0;
+            }
+            a - k * TAU
+        };
+        let (mut f_a, mut f_b) = (f(big_a), f(big_b));
+        while (big_b - big_a).abs() > EPSILON {
+            let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+            let f_c = f(big_c);
+            if f_c * f_b <= 0.0 {
+                big_a = big_b;
+                f_a = f_b;
+            } else {
+                f_a /= 2.0;
+            }
+            big_b = big_c;
+            f_b = f_c;
+        }
+        let volatility = (big_a / 2.0).exp();
+
+        let phi_star = (phi * phi + volatility * volatility).sqrt();
+        let phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+        let mu = mu + phi * phi * improvement;
+        Rating { rating: mu * SCALE + DEFAULT_RATING, deviation: phi * SCALE, volatility }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rating_glickman_example() {
+        let player = Rating { rating: 1500.0, deviation: 200.0, volatility: 0.06 };
+        let results = [
+            (Rating { rating: 1400.0, deviation: 30.0, volatility: 0.06 }, 1.0),
+            (Rating { rating: 1550.0, deviation: 100.0, volatility: 0.06 }, 0.0),
+            (Rating { rating: 1700.0, deviation: 300.0, volatility: 0.06 }, 0.0)
+        ];
+        let updated = player.update(&results);
+        assert!((updated.rating - 1464.06).abs() < 0.01, "{:?}", updated);
+        assert!((updated.deviation - 151.52).abs() < 0.01, "{:?}", updated);
+        assert!((updated.volatility - 0.05999).abs() < 0.00001, "{:?}", updated);
+    }
+
+    #[test]
+    fn rating_single_games() {
+        let (white, black) = (Rating::default(), Rating::default());
+        let winner = white.update(&[(black, 1.0)]);
+        let loser = black.update(&[(white, 0.0)]);
+        assert!(winner.rating > 1600.0 && loser.rating < 1400.0);
+        assert_eq!(winner.rounded() + loser.rounded(), 3000);
+        assert!(winner.deviation < DEFAULT_DEVIATION);
+        assert_eq!(white.update(&[(black, 0.5)]).rounded(), 1500);
+
+        // the deviation grows back without games
+        assert!(winner.update(&[]).deviation > winner.deviation);
+        assert_eq!(Rating::default().update(&[]).deviation, DEFAULT_DEVIATION);
+    }
+}
@@ -0,0 +1,60 @@
+//! Game Storage
+//!
+//! Games are saved as the moves are made, so the rooms in progress survive
+//! a restart of the server and the finished games are kept for review.
+//! The player accounts are kept next to them.
+mod sqlite;
+
+use std::fmt::Debug;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use chess::error::Error;
+use crate::{
+    accounts::{User, Standing},
+    model::{Game, Category},
+    rating::Rating
+};
+
+pub use sqlite::SqliteStorage;
+
+/// Persistent store of the games by room id
+pub trait Storage: Debug {
+    /// Save the game with the seats of the player tokens and its full history
+    fn save_game(&self, id: &Uuid, game: &Game) -> Result<(), Error>;
+
+    /// Load a game, `None` if it was never saved
+    fn load_game(&self, id: &Uuid) -> Result<Option<Game>, Error>;
+
+    /// Load all the games which are not over yet
+    fn load_in_progress(&self) -> Result<Vec<(Uuid, Game)>, Error>;
+}
+
+/// Persistent store of the player accounts and their login sessions
+pub trait AccountStorage: Debug + Send {
+    /// Save a new user, `false` when the username is taken
+    fn create_user(&self, user: &User) -> Result<bool, Error>;
+
+    /// Load a user by username, `None` if there is no such account
+    fn load_user(&self, username: &str) -> Result<Option<User>, Error>;
+
+    /// Load the user holding seats with the player token, `None` for a guest
+    fn seat_user(&self, token: &Uuid) -> Result<Option<User>, Error>;
+
+    /// Save a login session of the user
+    fn create_session(&self, session: &Uuid, user_id: &Uuid) -> Result<(), Error>;
+
+    /// Load the user of a session opened after `since`
+    fn session_user(&self, session: &Uuid, since: DateTime<Utc>) -> Result<Option<User>, Error>;
+
+    /// Delete a login session
+    fn delete_session(&self, session: &Uuid) -> Result<(), Error>;
+
+    /// Load the rating of a user in the category, `None` before its first rated game
+    fn load_rating(&self, user_id: &Uuid, category: Category) -> Result<Option<Rating>, Error>;
+
+    /// Save the rating of a user in the category after a rated game
+    fn save_rating(&self, user_id: &Uuid, category: Category, rating: &Rating) -> Result<(), Error>;
+
+    /// Best rated players of the category
+    fn leaderboard(&self, category: Category, limit: usize) -> Result<Vec<Standing>, Error>;
+}
@@ -0,0 +1,661 @@
+//! SQLite Storage
+
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+use chess::{
+    game::prelude::{Board, Piece, History},
+    error::Error::{self, *}
+};
+use crate::{
+    accounts::{User, Standing},
+    model::{Game, Bot, Color, Status, Winner, Termination, TimeControl, Clock, Category},
+    rating::Rating
+};
+use super::{Storage, AccountStorage};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS games (
+    id TEXT PRIMARY KEY,
+    status TEXT NOT NULL,
+    winner TEXT NOT NULL,
+    termination TEXT,
+    time_control TEXT NOT NULL,
+    board TEXT NOT NULL,
+    white_ms INTEGER,
+    black_ms INTEGER,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS seats (
+    game_id TEXT NOT NULL REFERENCES games (id),
+    token TEXT NOT NULL,
+    color TEXT NOT NULL,
+    PRIMARY KEY (game_id, token)
+);
+CREATE TABLE IF NOT EXISTS moves (
+    game_id TEXT NOT NULL REFERENCES games (id),
+    ply INTEGER NOT NULL,
+    from_cell TEXT NOT NULL,
+    to_cell TEXT NOT NULL,
+    history TEXT NOT NULL,
+    PRIMARY KEY (game_id, ply)
+);
+";
+
+/// Changes of the schema applied in order to older databases, the count
+/// of the applied ones is kept in the `user_version` pragma
+const MIGRATIONS: [&str; 6] = [
+    "ALTER TABLE games ADD COLUMN bot TEXT;",
+    "CREATE TABLE users (
+        id TEXT PRIMARY KEY,
+        username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        display_name TEXT NOT NULL,
+        password_hash TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE sessions (
+        id TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL REFERENCES users (id),
+        created_at TEXT NOT NULL
+    );",
+    "ALTER TABLE seats ADD COLUMN name TEXT;",
+    "ALTER TABLE games ADD COLUMN rated INTEGER NOT NULL DEFAULT 1;",
+    "CREATE TABLE ratings (
+        user_id TEXT NOT NULL REFERENCES users (id),
+        category TEXT NOT NULL,
+        rating REAL NOT NULL,
+        deviation REAL NOT NULL,
+        volatility REAL NOT NULL,
+        games INTEGER NOT NULL,
+        updated_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, category)
+    );",
+    // the seats of the users were held by their public ids
+    "ALTER TABLE users ADD COLUMN seat_token TEXT;
+    UPDATE users SET seat_token = lower(hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-'
+        || hex(randomblob(2)) || '-' || hex(randomblob(2)) || '-' || hex(randomblob(6)));
+    UPDATE seats SET token = (SELECT seat_token FROM users WHERE users.id = seats.token)
+    WHERE token IN (SELECT id FROM users);
+    CREATE UNIQUE INDEX users_seat_token ON users (seat_token);"
+];
+
+const SELECT_GAME: &str = "
+SELECT id, status, winner, termination, time_control, board, white_ms, black_ms, created_at, bot, rated
+FROM games";
+
+const SELECT_USER: &str = "
+SELECT users.id, username, display_name, password_hash, users.created_at, seat_token
+FROM users";
+
+/// Storage in an embedded SQLite database
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection
+}
+
+fn storage_error(err: impl Display) -> Error {
+    StorageError(err.to_string())
+}
+
+/// Encode a unit variant as its serde name, e.g. `Status::GameOver` as `game_over`
+fn encode<T: Serialize>(value: &T) -> Result<String, Error> {
+    match serde_json::to_value(value).map_err(storage_error)? {
+        serde_json::Value::String(name) => Ok(name),
+        value => Err(StorageError(format!("can't encode {} as a name", value)))
+    }
+}
+
+/// Decode a unit variant from its serde name
+fn decode<T: DeserializeOwned>(name: String) -> Result<T, Error> {
+    serde_json::from_value(serde_json::Value::String(name)).map_err(storage_error)
+}
+
+/// Columns of a `games` row, decoded after the query
+struct GameRow {
+    id: String,
+    status: String,
+    winner: String,
+    termination: Option<String>,
+    time_control: String,
+    board: String,
+    white_ms: Option<u64>,
+    black_ms: Option<u64>,
+    created_at: String,
+    bot: Option<String>,
+    rated: bool
+}
+
+impl GameRow {
+    fn from_row(row: &Row) -> rusqlite::Result<GameRow> {
+        Ok(GameRow {
+            id: row.get(0)?,
+            status: row.get(1)?,
+            winner: row.get(2)?,
+            termination: row.get(3)?,
+            time_control: row.get(4)?,
+            board: row.get(5)?,
+            white_ms: row.get(6)?,
+            black_ms: row.get(7)?,
+            created_at: row.get(8)?,
+            bot: row.get(9)?,
+            rated: row.get(10)?
+        })
+    }
+}
+
+/// Columns of a `users` row, decoded after the query
+struct UserRow {
+    id: String,
+    username: String,
+    display_name: String,
+    password_hash: String,
+    created_at: String,
+    seat_token: String
+}
+
+impl UserRow {
+    fn from_row(row: &Row) -> rusqlite::Result<UserRow> {
+        Ok(UserRow {
+            id: row.get(0)?,
+            username: row.get(1)?,
+            display_name: row.get(2)?,
+            password_hash: row.get(3)?,
+            created_at: row.get(4)?,
+            seat_token: row.get(5)?
+        })
+    }
+
+    fn into_user(self) -> Result<User, Error> {
+        Ok(User {
+            id: Uuid::parse_str(&self.id).map_err(storage_error)?,
+            username: self.username,
+            display_name: self.display_name,
+            password_hash: self.password_hash,
+            created_at: DateTime::parse_from_rfc3339(&self.created_at)
+                .map_err(storage_error)?
+                .with_timezone(&Utc),
+            seat_token: Uuid::parse_str(&self.seat_token).map_err(storage_error)?
+        })
+    }
+}
+
+impl SqliteStorage {
+    /// Open the database file, creating the tables if they don't exist
+    ///
+    /// The path `:memory:` opens a database which only lives in memory.
+    pub fn open(path: &str) -> Result<SqliteStorage, Error> {
+        let conn = Connection::open(path).map_err(storage_error)?;
+        conn.execute_batch(SCHEMA).map_err(storage_error)?;
+        SqliteStorage::migrate(&conn)?;
+        Ok(SqliteStorage { conn })
+    }
+
+    /// Apply the migrations the database hasn't seen yet
+    fn migrate(conn: &Connection) -> Result<(), Error> {
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(storage_error)?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            conn.execute_batch(&format!("BEGIN; {migration} PRAGMA user_version = {}; COMMIT;", index + 1))
+                .map_err(storage_error)?;
+        }
+        Ok(())
+    }
+
+    /// Build the game of a row with its seats and history
+    fn to_game(&self, row: GameRow) -> Result<(Uuid, Game), Error> {
+        let id = Uuid::parse_str(&row.id).map_err(storage_error)?;
+        let time_control = TimeControl::parse(&row.time_control)?;
+        let mut game = Game::with_time_control(time_control);
+
+        game.status = decode::<Status>(row.status)?;
+        game.winner = decode::<Winner>(row.winner)?;
+        game.termination = row.termination.map(decode::<Termination>).transpose()?;
+        game.bot = row.bot
+            .map(|bot| serde_json::from_str::<Bot>(&bot).map_err(storage_error))
+            .transpose()?;
+        game.rated = row.rated;
+        game.created_at = DateTime::parse_from_rfc3339(&row.created_at)
+            .map_err(storage_error)?
+            .with_timezone(&Utc);
+        game.clock = Clock::new(time_control).map(|mut clock| {
+            clock.white = row.white_ms.map_or(clock.white, Duration::from_millis);
+            clock.black = row.black_ms.map_or(clock.black, Duration::from_millis);
+            clock
+        });
+
+        let mut board: Board<Piece> = serde_json::from_str(&row.board).map_err(storage_error)?;
+        let mut stmt = self.conn
+            .prepare("SELECT history FROM moves WHERE game_id = ?1 ORDER BY ply")
+            .map_err(storage_error)?;
+        board.history = stmt.query_map(params![row.id], |row| row.get::<_, String>(0))
+            .map_err(storage_error)?
+            .map(|history| serde_json::from_str::<History>(&history.map_err(storage_error)?).map_err(storage_error))
+            .collect::<Result<Vec<History>, Error>>()?;
+        game.board = board;
+
+        let mut stmt = self.conn
+            .prepare("SELECT token, color, name FROM seats WHERE game_id = ?1")
+            .map_err(storage_error)?;
+        let seats = stmt.query_map(params![row.id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+            })
+            .map_err(storage_error)?
+            .collect::<rusqlite::Result<Vec<(String, String, Option<String>)>>>()
+            .map_err(storage_error)?;
+        for (token, color, name) in seats {
+            let token = Uuid::parse_str(&token).map_err(storage_error)?;
+            game.seats.insert(token, decode::<Color>(color)?);
+            if let Some(name) = name {
+                game.names.insert(token, name);
+            }
+        }
+
+        Ok((id, game))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn save_game(&self, id: &Uuid, game: &Game) -> Result<(), Error> {
+        let tx = self.conn.unchecked_transaction().map_err(storage_error)?;
+        let id = id.to_string();
+
+        // the history is saved move by move in its own table
+        let mut board = game.board.clone();
+        let history = std::mem::take(&mut board.history);
+        let clock = game.clock_state(Instant::now());
+
+        tx.execute(
+            "INSERT INTO games (id, status, winner, termination, time_control, board, white_ms, black_ms, created_at, updated_at, bot, rated)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            ON CONFLICT (id) DO UPDATE SET
+                status = excluded.status,
+                winner = excluded.winner,
+                termination = excluded.termination,
+                board = excluded.board,
+                white_ms = excluded.white_ms,
+                black_ms = excluded.black_ms,
+                updated_at = excluded.updated_at",
+            params![
+                id,
+                encode(&game.status)?,
+                encode(&game.winner)?,
+                game.termination.map(|termination| encode(&termination)).transpose()?,
+                game.time_control.to_string(),
+                serde_json::to_string(&board).map_err(storage_error)?,
+                clock.map(|clock| clock.white),
+                clock.map(|clock| clock.black),
+                game.created_at.to_rfc3339(),
+                Utc::now().to_rfc3339(),
+                game.bot.map(|bot| serde_json::to_string(&bot)).transpose().map_err(storage_error)?,
+                game.rated
+            ]
+        ).map_err(storage_error)?;
+
+        tx.execute("DELETE FROM seats WHERE game_id = ?1", params![id]).map_err(storage_error)?;
+        for (token, color) in game.seats.iter() {
+            tx.execute(
+                "INSERT INTO seats (game_id, token, color, name) VALUES (?1, ?2, ?3, ?4)",
+                params![id, token.to_string(), encode(color)?, game.names.get(token)]
+            ).map_err(storage_error)?;
+        }
+
+        // a loaded position replaces the history, the last move is rewritten after an undo
+        let saved: usize = tx.query_row("SELECT COUNT(*) FROM moves WHERE game_id = ?1", params![id], |row| row.get(0))
+            .map_err(storage_error)?;
+        if saved > history.len() {
+            tx.execute("DELETE FROM moves WHERE game_id = ?1", params![id]).map_err(storage_error)?;
+        }
+        let unchanged = if saved > history.len() { 0 } else { saved.saturating_sub(1) };
+        for (ply, his) in history.iter().enumerate().skip(unchanged) {
+            tx.execute(
+                "INSERT OR REPLACE INTO moves (game_id, ply, from_cell, to_cell, history) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![id, ply, his.from, his.to, serde_json::to_string(his).map_err(storage_error)?]
+            ).map_err(storage_error)?;
+        }
+
+        tx.commit().map_err(storage_error)
+    }
+
+    fn load_game(&self, id: &Uuid) -> Result<Option<Game>, Error> {
+        let row = self.conn
+            .query_row(&format!("{SELECT_GAME} WHERE id = ?1"), params![id.to_string()], GameRow::from_row)
+            .optional()
+            .map_err(storage_error)?;
+
+        row.map(|row| self.to_game(row).map(|(_id, game)| game)).transpose()
+    }
+
+    fn load_in_progress(&self) -> Result<Vec<(Uuid, Game)>, Error> {
+        let mut stmt = self.conn
+            .prepare(&format!("{SELECT_GAME} WHERE status != ?1 ORDER BY created_at"))
+            .map_err(storage_error)?;
+        let rows = stmt.query_map(params![encode(&Status::GameOver)?], GameRow::from_row)
+            .map_err(storage_error)?
+            .collect::<rusqlite::Result<Vec<GameRow>>>()
+            .map_err(storage_error)?;
+
+        rows.into_iter().map(|row| self.to_game(row)).collect()
+    }
+}
+
+impl AccountStorage for SqliteStorage {
+    fn create_user(&self, user: &User) -> Result<bool, Error> {
+        let inserted = self.conn.execute(
+            "INSERT INTO users (id, username, display_name, password_hash, created_at, seat_token)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (username) DO NOTHING",
+            params![
+                user.id.to_string(),
+                user.username,
+                user.display_name,
+                user.password_hash,
+                user.created_at.to_rfc3339(),
+                user.seat_token.to_string()
+            ]
+        ).map_err(storage_error)?;
+        Ok(inserted == 1)
+    }
+
+    fn load_user(&self, username: &str) -> Result<Option<User>, Error> {
+        self.conn
+            .query_row(&format!("{SELECT_USER} WHERE username = ?1"), params![username], UserRow::from_row)
+            .optional()
+            .map_err(storage_error)?
+            .map(UserRow::into_user)
+            .transpose()
+    }
+
+    fn seat_user(&self, token: &Uuid) -> Result<Option<User>, Error> {
+        self.conn
+            .query_row(&format!("{SELECT_USER} WHERE seat_token = ?1"), params![token.to_string()], UserRow::from_row)
+            .optional()
+            .map_err(storage_error)?
+            .map(UserRow::into_user)
+            .transpose()
+    }
+
+    fn create_session(&self, session: &Uuid, user_id: &Uuid) -> Result<(), Error> {
+        self.conn.execute(
+            "INSERT INTO sessions (id, user_id, created_at) VALUES (?1, ?2, ?3)",
+            params![session.to_string(), user_id.to_string(), Utc::now().to_rfc3339()]
+        ).map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn session_user(&self, session: &Uuid, since: DateTime<Utc>) -> Result<Option<User>, Error> {
+        self.conn
+            .query_row(
+                &format!("{SELECT_USER} JOIN sessions ON sessions.user_id = users.id
+                WHERE sessions.id = ?1 AND sessions.created_at > ?2"),
+                params![session.to_string(), since.to_rfc3339()],
+                UserRow::from_row
+            )
+            .optional()
+            .map_err(storage_error)?
+            .map(UserRow::into_user)
+            .transpose()
+    }
+
+    fn delete_session(&self, session: &Uuid) -> Result<(), Error> {
+        self.conn.execute("DELETE FROM sessions WHERE id = ?1", params![session.to_string()])
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn load_rating(&self, user_id: &Uuid, category: Category) -> Result<Option<Rating>, Error> {
+        self.conn
+            .query_row(
+                "SELECT rating, deviation, volatility FROM ratings WHERE user_id = ?1 AND category = ?2",
+                params![user_id.to_string(), encode(&category)?],
+                |row| Ok(Rating { rating: row.get(0)?, deviation: row.get(1)?, volatility: row.get(2)? })
+            )
+            .optional()
+            .map_err(storage_error)
+    }
+
+    fn save_rating(&self, user_id: &Uuid, category: Category, rating: &Rating) -> Result<(), Error> {
+        self.conn.execute(
+            "INSERT INTO ratings (user_id, category, rating, deviation, volatility, games, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6)
+            ON CONFLICT (user_id, category) DO UPDATE SET
+                rating = excluded.rating,
+                deviation = excluded.deviation,
+                volatility = excluded.volatility,
+                games = games + 1,
+                updated_at = excluded.updated_at",
+            params![
+                user_id.to_string(),
+                encode(&category)?,
+                rating.rating,
+                rating.deviation,
+                rating.volatility,
+                Utc::now().to_rfc3339()
+            ]
+        ).map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn leaderboard(&self, category: Category, limit: usize) -> Result<Vec<Standing>, Error> {
+        let mut stmt = self.conn
+            .prepare(
+                "SELECT username, display_name, rating, deviation, games
+                FROM ratings JOIN users ON users.id = ratings.user_id
+                WHERE category = ?1
+                ORDER BY rating DESC
+                LIMIT ?2"
+            )
+            .map_err(storage_error)?;
+        let standings = stmt.query_map(params![encode(&category)?, limit], |row| {
+                let (rating, deviation) = (row.get::<_, f64>(2)?, row.get::<_, f64>(3)?);
+                Ok(Standing {
+                    username: row.get(0)?,
+                    display_name: row.get(1)?,
+                    rating: rating.round() as u32,
+                    deviation: deviation.round() as u32,
+                    games: row.get(4)?
+                })
+            })
+            .map_err(storage_error)?
+            .collect::<rusqlite::Result<Vec<Standing>>>()
+            .map_err(storage_error)?;
+        Ok(standings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::game::prelude::Level;
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[(&str, &str)]) -> Result<(), Error> {
+        for (from, to) in moves {
+            game.board.moves_piece(from, to, None)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn save_and_load_game() -> Result<(), Error> {
+        let storage = SqliteStorage::open(":memory:")?;
+        let id = Uuid::new_v4();
+        let white = Uuid::new_v4();
+        let mut game = Game::with_time_control(TimeControl::Clock { minutes: 5, increment: 3 });
+        game.join(Uuid::new_v4(), white, Instant::now());
+        game.names.insert(white, "Alice".to_owned());
+
+        play(&mut game, &[("e2", "e4"), ("e7", "e5")])?;
+        storage.save_game(&id, &game)?;
+        play(&mut game, &[("g1", "f3")])?;
+        storage.save_game(&id, &game)?;
+
+        let loaded = storage.load_game(&id)?.unwrap();
+        assert_eq!(loaded.board, game.board);
+        assert_eq!(loaded.board.get_history().len(), 3);
+        assert_eq!(loaded.status, Status::Waiting);
+        assert_eq!(loaded.seats.get(&white), Some(&Color::White));
+        assert_eq!(loaded.player_name(Color::White), Some("Alice"));
+        assert!(loaded.players.is_empty());
+        assert_eq!(loaded.time_control, game.time_control);
+        assert_eq!(loaded.clock.unwrap().white, Duration::from_secs(300));
+        assert_eq!(loaded.created_at.timestamp(), game.created_at.timestamp());
+        assert!(storage.load_game(&Uuid::new_v4())?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn save_promotion_and_loaded_position() -> Result<(), Error> {
+        let storage = SqliteStorage::open(":memory:")?;
+        let id = Uuid::new_v4();
+        let mut game = Game::new();
+        game.board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1")?;
+        storage.save_game(&id, &game)?;
+        game.board.moves_piece("a7", "a8", Some(Level::Queen))?;
+        storage.save_game(&id, &game)?;
+        let loaded = storage.load_game(&id)?.unwrap();
+        assert_eq!(loaded.board, game.board);
+        assert_eq!(loaded.board.get_history()[0].promotion, Some(Level::Queen));
+
+        game.board = Board::new();
+        storage.save_game(&id, &game)?;
+        assert!(storage.load_game(&id)?.unwrap().board.get_history().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn load_in_progress() -> Result<(), Error> {
+        let storage = SqliteStorage::open(":memory:")?;
+        let (playing, finished) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = Game::new();
+        game.status = Status::Playing;
+        storage.save_game(&playing, &game)?;
+        game.finish(Winner::Draw, Termination::Agreement);
+        storage.save_game(&finished, &game)?;
+
+        let games = storage.load_in_progress()?;
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].0, playing);
+        assert_eq!(games[0].1.bot, None);
+
+        let finished = storage.load_game(&finished)?.unwrap();
+        assert_eq!(finished.winner, Winner::Draw);
+        assert_eq!(finished.termination, Some(Termination::Agreement));
+        Ok(())
+    }
+
+    #[test]
+    fn save_bot_and_migrate() -> Result<(), Error> {
+        let storage = SqliteStorage::open(":memory:")?;
+        let id = Uuid::new_v4();
+        let bot = Bot { color: Color::Black, engine: chess::engine::Engine::new(4, Duration::from_secs(3)) };
+        let mut game = Game::with_bot(TimeControl::Correspondence, bot);
+        play(&mut game, &[("e2", "e4")])?;
+        storage.save_game(&id, &game)?;
+        assert_eq!(storage.load_game(&id)?.unwrap().bot, Some(bot));
+
+        // migrations are applied once
+        SqliteStorage::migrate(&storage.conn)?;
+        let version: usize = storage.conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_seat_tokens() -> Result<(), Error> {
+        // a database of the accounts before the seat tokens
+        let conn = Connection::open_in_memory().map_err(storage_error)?;
+        conn.execute_batch(SCHEMA).map_err(storage_error)?;
+        for migration in &MIGRATIONS[..5] {
+            conn.execute_batch(migration).map_err(storage_error)?;
+        }
+        conn.execute_batch("PRAGMA user_version = 5;").map_err(storage_error)?;
+        let (user_id, guest) = (Uuid::new_v4(), Uuid::new_v4());
+        conn.execute(
+            "INSERT INTO users (id, username, display_name, password_hash, created_at)
+            VALUES (?1, 'alice', 'Alice', 'hash', ?2)",
+            params![user_id.to_string(), Utc::now().to_rfc3339()]
+        ).map_err(storage_error)?;
+        conn.execute(
+            "INSERT INTO games (id, status, winner, time_control, board, created_at, updated_at)
+            VALUES ('game', 'playing', 'not_decided', 'correspondence', '{}', ?1, ?1)",
+            params![Utc::now().to_rfc3339()]
+        ).map_err(storage_error)?;
+        for (token, color) in [(user_id, "white"), (guest, "black")] {
+            conn.execute(
+                "INSERT INTO seats (game_id, token, color) VALUES ('game', ?1, ?2)",
+                params![token.to_string(), color]
+            ).map_err(storage_error)?;
+        }
+
+        SqliteStorage::migrate(&conn)?;
+        let storage = SqliteStorage { conn };
+        let user = storage.load_user("alice")?.unwrap();
+        assert_ne!(user.seat_token, user_id);
+        let tokens = storage.conn.prepare("SELECT token FROM seats ORDER BY color").map_err(storage_error)?
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(storage_error)?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .map_err(storage_error)?;
+        assert_eq!(tokens, [guest.to_string(), user.seat_token.to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn save_users_and_sessions() -> Result<(), Error> {
+        let storage = SqliteStorage::open(":memory:")?;
+        let user = User {
+            id: Uuid::new_v4(),
+            username: "alice".to_owned(),
+            display_name: "Alice".to_owned(),
+            password_hash: "$argon2id$hash".to_owned(),
+            created_at: Utc::now(),
+            seat_token: Uuid::new_v4()
+        };
+        assert!(storage.create_user(&user)?);
+        let taken = User { id: Uuid::new_v4(), username: "Alice".to_owned(), seat_token: Uuid::new_v4(), ..user.clone() };
+        assert!(!storage.create_user(&taken)?);
+        assert_eq!(storage.load_user("ALICE")?.map(|user| user.id), Some(user.id));
+        assert!(storage.load_user("bob")?.is_none());
+        assert_eq!(storage.seat_user(&user.seat_token)?.map(|user| user.id), Some(user.id));
+        assert!(storage.seat_user(&user.id)?.is_none());
+
+        let session = Uuid::new_v4();
+        storage.create_session(&session, &user.id)?;
+        let since = Utc::now() - chrono::Duration::days(1);
+        assert_eq!(storage.session_user(&session, since)?.map(|user| user.display_name), Some("Alice".to_owned()));
+        assert!(storage.session_user(&session, Utc::now() + chrono::Duration::days(1))?.is_none());
+        storage.delete_session(&session)?;
+        assert!(storage.session_user(&session, since)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn save_ratings() -> Result<(), Error> {
+        let storage = SqliteStorage::open(":memory:")?;
+        let user = User {
+            id: Uuid::new_v4(),
+            username: "alice".to_owned(),
+            display_name: "Alice".to_owned(),
+            password_hash: "$argon2id$hash".to_owned(),
+            created_at: Utc::now(),
+            seat_token: Uuid::new_v4()
+        };
+        storage.create_user(&user)?;
+        assert!(storage.load_rating(&user.id, Category::Blitz)?.is_none());
+
+        let rating = Rating { rating: 1662.31, deviation: 290.32, volatility: 0.059996 };
+        storage.save_rating(&user.id, Category::Blitz, &rating)?;
+        storage.save_rating(&user.id, Category::Blitz, &rating)?;
+        assert_eq!(storage.load_rating(&user.id, Category::Blitz)?, Some(rating));
+        assert!(storage.load_rating(&user.id, Category::Bullet)?.is_none());
+
+        let standings = storage.leaderboard(Category::Blitz, 10)?;
+        assert_eq!(standings.len(), 1);
+        assert_eq!((standings[0].display_name.as_str(), standings[0].rating, standings[0].games), ("Alice", 1662, 2));
+        assert!(storage.leaderboard(Category::Bullet, 10)?.is_empty());
+        Ok(())
+    }
+}
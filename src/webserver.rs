@@ -1,27 +1,88 @@
 use std::path::PathBuf;
 use actix_files::NamedFile;
-use actix_web::{get, HttpRequest, HttpResponse, Responder, web};
+use actix_web::{get, cookie::Cookie, error, HttpRequest, HttpResponse, HttpResponseBuilder, Responder, web};
 use uuid::Uuid;
 use tera::{Tera, Context};
+use crate::{
+    accounts::{Accounts, LeaderboardQuery},
+    model::Category,
+    ws::PLAYER_TOKEN_COOKIE
+};
 
-/// Handler for home (root) page
+/// Give the browser a player token when it doesn't have one yet
+fn set_player_token(request: &HttpRequest, response: &mut HttpResponseBuilder) {
+    if request.cookie(PLAYER_TOKEN_COOKIE).is_none() {
+        let token = Cookie::build(PLAYER_TOKEN_COOKIE, Uuid::new_v4().to_string())
+            .path("/")
+            .http_only(true)
+            .permanent()
+            .finish();
+        response.cookie(token);
+    }
+}
+
+/// Handler for home (root) page, the player token is set for the
+/// matchmaking to hold the seat of the matched game
 #[get("/")]
-async fn index(template: web::Data<Tera>) -> impl Responder {
+async fn index(request: HttpRequest, template: web::Data<Tera>) -> impl Responder {
     let mut ctx = Context::new();
     ctx.insert("game_id", &Uuid::new_v4());
     let rendered = template.render("index.html", &ctx).unwrap();
-    HttpResponse::Ok().body(rendered)
+
+    let mut response = HttpResponse::Ok();
+    set_player_token(&request, &mut response);
+    response.body(rendered)
 }
 
-/// Handler for page game
+/// Handler for page game, gives the browser a player token to reclaim
+/// its seat when it reconnects
 #[get("/game/{game_id}")]
-async fn game(game_id: web::Path<Uuid>, template: web::Data<Tera>) -> impl Responder {
+async fn game(request: HttpRequest, game_id: web::Path<Uuid>, template: web::Data<Tera>) -> impl Responder {
     let mut ctx = Context::new();
     ctx.insert("game_id", &game_id.to_string());
     let rendered = template.render("game.html", &ctx).unwrap();
+
+    let mut response = HttpResponse::Ok();
+    set_player_token(&request, &mut response);
+    response.body(rendered)
+}
+
+/// Handler for the review page of a game, open or saved, which steps
+/// through its positions
+#[get("/game/{game_id}/review")]
+async fn review(game_id: web::Path<Uuid>, template: web::Data<Tera>) -> impl Responder {
+    let mut ctx = Context::new();
+    ctx.insert("game_id", &game_id.to_string());
+    let rendered = template.render("review.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+/// Handler for the login and registration page
+#[get("/login")]
+async fn login(template: web::Data<Tera>) -> impl Responder {
+    let rendered = template.render("login.html", &Context::new()).unwrap();
     HttpResponse::Ok().body(rendered)
 }
 
+/// Handler for the leaderboard page of a category, e.g. `/leaderboard?category=rapid`
+#[get("/leaderboard")]
+async fn leaderboard(
+    query: web::Query<LeaderboardQuery>,
+    accounts: web::Data<Accounts>,
+    template: web::Data<Tera>
+) -> Result<HttpResponse, actix_web::Error> {
+    let category = query.category;
+    let standings = web::block(move || accounts.leaderboard(category)).await?
+        .map_err(error::ErrorInternalServerError)?;
+
+    let mut ctx = Context::new();
+    ctx.insert("categories", &Category::ALL.iter().map(Category::as_str).collect::<Vec<&str>>());
+    ctx.insert("category", category.as_str());
+    ctx.insert("standings", &standings);
+    let rendered = template.render("leaderboard.html", &ctx).map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().body(rendered))
+}
+
 /// Handler for static files
 #[get("/static/{filename}")]
 async fn staticfiles(request: HttpRequest) -> impl Responder {
@@ -33,7 +94,7 @@ async fn staticfiles(request: HttpRequest) -> impl Responder {
 
 #[cfg(test)]
 mod tests {
-    use actix_web::{test, App};
+    use actix_web::{test, App, http::StatusCode};
     use super::*;
 
     #[actix_web::test]
@@ -47,6 +108,7 @@ mod tests {
         let req = test::TestRequest::get().uri("/").to_request();
         let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_success());
+        assert!(resp.response().cookies().any(|cookie| cookie.name() == PLAYER_TOKEN_COOKIE));
     }
 
     #[actix_web::test]
@@ -61,6 +123,59 @@ mod tests {
         let req = test::TestRequest::get().uri(&uri).to_request();
         let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_success());
+        assert!(resp.response().cookies().any(|cookie| cookie.name() == PLAYER_TOKEN_COOKIE));
+
+        let token = Cookie::new(PLAYER_TOKEN_COOKIE, Uuid::new_v4().to_string());
+        let req = test::TestRequest::get().uri(&uri).cookie(token).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.response().cookies().count(), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_review_get() {
+        let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(tera.clone()))
+                .service(review)
+        ).await;
+        let req = test::TestRequest::get().uri(&format!("/game/{}/review", Uuid::new_v4())).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let req = test::TestRequest::get().uri("/game/not-a-uuid/review").to_request();
+        assert!(test::call_service(&app, req).await.status().is_client_error());
+    }
+
+    #[actix_web::test]
+    async fn test_login_get() {
+        let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(tera.clone()))
+                .service(login)
+        ).await;
+        let req = test::TestRequest::get().uri("/login").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_leaderboard_get() {
+        let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+        let accounts = Accounts::in_memory();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(tera.clone()))
+                .app_data(web::Data::new(accounts))
+                .service(leaderboard)
+        ).await;
+        let req = test::TestRequest::get().uri("/leaderboard?category=rapid").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let req = test::TestRequest::get().uri("/leaderboard?category=hyperbullet").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
     }
 
     #[actix_web::test]
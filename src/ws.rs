@@ -3,39 +3,131 @@ use actix::{
     AsyncContext, WrapFuture, ActorFutureExt, fut,
     ContextFutureSpawner, Running
 };
-use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::{get, web, error, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
+use serde::Deserialize;
 use uuid::Uuid;
 use std::time::{Duration, Instant};
+use chess::engine::Engine;
 use crate::{
+    accounts::{current_user, is_account_token, Accounts, User},
     lobby::Lobby,
-    message::{ClientActorMessage, WsMessage, Connect, Disconnect}
+    message::{ClientActorMessage, WsMessage, Connect, Disconnect, Seek, CancelSeek},
+    model::TimeControl,
+    protocol::Protocol,
+    rating::Rating
 };
 
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Deepest search a client can ask of the computer opponent
+const MAX_BOT_DEPTH: u32 = 6;
+/// Longest thinking time a client can give the computer opponent, in seconds
+const MAX_BOT_TIME: u64 = 30;
+
+/// Cookie of the player token, set by the game page
+pub const PLAYER_TOKEN_COOKIE: &str = "player_token";
+
+/// Options of the room when the connection creates it
+#[derive(Debug)]
+pub struct RoomOptions {
+    pub time_control: TimeControl,
+    /// Engine of the computer opponent, `None` for a human opponent
+    pub bot: Option<Engine>,
+    /// Whether the room is rated, `false` for a casual game
+    pub rated: bool
+}
+
 /// Define HTTP actor
 #[derive(Debug)]
 pub struct ChessWebSocket {
     id: Uuid,
     room: Uuid,
+    /// Player token to reclaim the seat after a reconnection, the seat
+    /// token of a signed-in player
+    token: Uuid,
+    /// Display name of a signed-in player
+    name: Option<String>,
+    options: RoomOptions,
+    protocol: Protocol,
     hb: Instant,
     lobby_addr: Addr<Lobby>
 }
 
 impl ChessWebSocket {
     /// Create new Chess Websocket instance
-    pub fn new(room: Uuid, lobby: Addr<Lobby>) -> ChessWebSocket {
+    pub fn new(
+        room: Uuid,
+        token: Uuid,
+        name: Option<String>,
+        options: RoomOptions,
+        protocol: Protocol,
+        lobby: Addr<Lobby>
+    ) -> ChessWebSocket {
         ChessWebSocket {
             id: Uuid::new_v4(),
             room,
+            token,
+            name,
+            options,
+            protocol,
             hb: Instant::now(),
             lobby_addr: lobby
         }
     }
 }
 
+/// Query of the websocket connection, `tc` is the time control
+/// of a new room, e.g. `/ws/{room_id}?tc=5%2B3`, `protocol=text`
+/// switches to the legacy text commands, and `token` is the player
+/// token when the client doesn't send the cookie
+///
+/// `opponent=bot` makes the computer play Black in a new room, searching
+/// `depth` plies (1 to 6) for at most `time` seconds per move, and
+/// `rated=false` opens a casual room which doesn't change the ratings.
+#[derive(Debug, Deserialize)]
+pub struct ConnectQuery {
+    tc: Option<String>,
+    token: Option<Uuid>,
+    #[serde(default)]
+    protocol: Protocol,
+    opponent: Option<String>,
+    depth: Option<u32>,
+    time: Option<u64>,
+    rated: Option<bool>
+}
+
+impl ConnectQuery {
+    /// Engine of the computer opponent asked by the query
+    fn bot(&self) -> Result<Option<Engine>, String> {
+        bot_engine(self.opponent.as_deref(), self.depth, self.time)
+    }
+}
+
+/// Engine of the computer opponent for the `opponent` option of a new room,
+/// `None` for a human opponent
+pub fn bot_engine(opponent: Option<&str>, depth: Option<u32>, time: Option<u64>) -> Result<Option<Engine>, String> {
+    match opponent {
+        None | Some("human") => return Ok(None),
+        Some("bot") => (),
+        Some(opponent) => return Err(format!("Invalid opponent '{opponent}'."))
+    }
+
+    let default = Engine::default();
+    let depth = depth.unwrap_or(default.depth);
+    if !(1..=MAX_BOT_DEPTH).contains(&depth) {
+        return Err(format!("The depth must be between 1 and {MAX_BOT_DEPTH}."));
+    }
+    let time = match time {
+        Some(time) if (1..=MAX_BOT_TIME).contains(&time) => Duration::from_secs(time),
+        Some(_) => return Err(format!("The time must be between 1 and {MAX_BOT_TIME} seconds.")),
+        None => default.time
+    };
+
+    Ok(Some(Engine::new(depth, time)))
+}
+
 impl Actor for ChessWebSocket {
     type Context = ws::WebsocketContext<Self>;
 
@@ -47,6 +139,12 @@ impl Actor for ChessWebSocket {
             addr: addr.recipient(),
             lobby_id: self.room,
             self_id: self.id,
+            token: self.token,
+            name: self.name.clone(),
+            time_control: self.options.time_control,
+            bot: self.options.bot,
+            rated: self.options.rated,
+            protocol: self.protocol,
         };
 
         self.lobby_addr
@@ -107,7 +205,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ChessWebSocket {
                     room_id: self.room
                 }
             ),
-            Err(e) => panic!("{}", e),
+            Err(_) => ctx.stop(),
         }
     }
 }
@@ -120,16 +218,185 @@ impl Handler<WsMessage> for ChessWebSocket {
     }
 }
 
-/// Start the websocket connection
+/// Websocket of a player waiting in the matchmaking queue, closed by the
+/// client once it's sent the room of its game
+#[derive(Debug)]
+pub struct SeekWebSocket {
+    id: Uuid,
+    token: Uuid,
+    time_control: TimeControl,
+    rating: u32,
+    protocol: Protocol,
+    hb: Instant,
+    lobby_addr: Addr<Lobby>
+}
+
+impl SeekWebSocket {
+    /// Create new Seek Websocket instance
+    pub fn new(
+        token: Uuid,
+        time_control: TimeControl,
+        rating: u32,
+        protocol: Protocol,
+        lobby: Addr<Lobby>
+    ) -> SeekWebSocket {
+        SeekWebSocket {
+            id: Uuid::new_v4(),
+            token,
+            time_control,
+            rating,
+            protocol,
+            hb: Instant::now(),
+            lobby_addr: lobby
+        }
+    }
+}
+
+impl Actor for SeekWebSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"hi");
+        });
+
+        self.lobby_addr.do_send(Seek {
+            addr: ctx.address().recipient(),
+            id: self.id,
+            token: self.token,
+            time_control: self.time_control,
+            rating: self.rating,
+            protocol: self.protocol
+        });
+    }
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        self.lobby_addr.do_send(CancelSeek { id: self.id });
+        Running::Stop
+    }
+}
+
+/// Handler for ws::Message message, the seeker only keeps the connection alive
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for SeekWebSocket {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => self.hb = Instant::now(),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(ws::Message::Continuation(_)) | Err(_) => ctx.stop(),
+            Ok(_) => ()
+        }
+    }
+}
+
+impl Handler<WsMessage> for SeekWebSocket {
+    type Result = ();
+
+    fn handle(&mut self, msg: WsMessage, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
+}
+
+/// Query of the matchmaking connection, `tc` is the time control of the
+/// game, e.g. `/seek?tc=5%2B3`, with the same `protocol` and `token` as
+/// the game connection
+#[derive(Debug, Deserialize)]
+pub struct SeekQuery {
+    tc: Option<String>,
+    token: Option<Uuid>,
+    #[serde(default)]
+    protocol: Protocol
+}
+
+/// Seat token of the signed-in user, or the player token of the query,
+/// or of the cookie, or a new one for a guest, who can't present the
+/// seat token of an account
+async fn player(
+    req: &HttpRequest,
+    token: Option<Uuid>,
+    accounts: &web::Data<Accounts>
+) -> Result<(Uuid, Option<User>), Error> {
+    if let Some(user) = current_user(req, accounts).await? {
+        return Ok((user.seat_token, Some(user)));
+    }
+
+    let token = token
+        .or_else(|| req.cookie(PLAYER_TOKEN_COOKIE).and_then(|cookie| Uuid::parse_str(cookie.value()).ok()));
+    match token {
+        Some(token) if is_account_token(token, accounts).await? => {
+            Err(error::ErrorForbidden("The player token belongs to an account, log in to use it."))
+        },
+        Some(token) => Ok((token, None)),
+        None => Ok((Uuid::new_v4(), None))
+    }
+}
+
+/// Start the websocket connection of a player seeking an opponent
+#[get("/seek")]
+pub async fn start_seek(
+    req: HttpRequest,
+    stream: web::Payload,
+    query: web::Query<SeekQuery>,
+    data_lobby_addr: web::Data<Addr<Lobby>>,
+    accounts: web::Data<Accounts>
+) -> Result<HttpResponse, Error> {
+    let time_control = match &query.tc {
+        Some(tc) => TimeControl::parse(tc).map_err(|err| error::ErrorBadRequest(err.to_string()))?,
+        None => TimeControl::default()
+    };
+    let (token, user) = player(&req, query.token, &accounts).await?;
+    // a guest is paired by the rating of a new player
+    let rating = match user {
+        Some(user) => {
+            let (accounts, category) = (accounts.clone(), time_control.category());
+            web::block(move || accounts.rating(&user.id, category)).await?
+                .map_err(error::ErrorInternalServerError)?
+        },
+        None => Rating::default()
+    };
+    let seek_ws = SeekWebSocket::new(
+        token,
+        time_control,
+        rating.rounded(),
+        query.protocol,
+        data_lobby_addr.get_ref().clone()
+    );
+    ws::start(seek_ws, &req, stream)
+}
+
+/// Start the websocket connection, as the signed-in user of the session
+/// cookie or as a guest
 #[get("/ws/{room_id}")]
 pub async fn start_connection(
     req: HttpRequest,
     stream: web::Payload,
     room_id: web::Path<Uuid>,
-    data_lobby_addr: web::Data<Addr<Lobby>>
+    query: web::Query<ConnectQuery>,
+    data_lobby_addr: web::Data<Addr<Lobby>>,
+    accounts: web::Data<Accounts>
 ) -> Result<HttpResponse, Error> {
+    let time_control = match &query.tc {
+        Some(tc) => TimeControl::parse(tc).map_err(|err| error::ErrorBadRequest(err.to_string()))?,
+        None => TimeControl::default()
+    };
+    let bot = query.bot().map_err(error::ErrorBadRequest)?;
+    let (token, user) = player(&req, query.token, &accounts).await?;
     let chess_ws = ChessWebSocket::new(
         room_id.into_inner(),
+        token,
+        user.map(|user| user.display_name),
+        RoomOptions { time_control, bot, rated: query.rated.unwrap_or(true) },
+        query.protocol,
         data_lobby_addr.get_ref().clone()
     );
     let resp = ws::start(chess_ws, &req, stream)?;
@@ -139,6 +406,7 @@ pub async fn start_connection(
 #[cfg(test)]
 mod tests {
     use actix_web::{test, App, http::header};
+    use crate::accounts::SESSION_COOKIE;
     use super::*;
 
     #[actix_web::test]
@@ -147,6 +415,7 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(chess_ws_server.clone()))
+                .app_data(web::Data::new(Accounts::in_memory()))
                 .service(start_connection)
         ).await;
 
@@ -163,4 +432,100 @@ mod tests {
         let conn = test::call_service(&app, req).await;
         assert_eq!(conn.headers().get("upgrade"), Some(&header::HeaderValue::from_static("websocket")));
     }
-}
\ No newline at end of file
+
+    #[actix_web::test]
+    async fn ws_connect_query() {
+        let chess_ws_server = Lobby::default().start();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(chess_ws_server.clone()))
+                .app_data(web::Data::new(Accounts::in_memory()))
+                .service(start_connection)
+        ).await;
+
+        for (query, upgraded) in [
+            ("tc=5%2B3", true),
+            ("tc=correspondence", true),
+            ("tc=fast", false),
+            ("tc=5%2B3&protocol=text", true),
+            ("protocol=binary", false),
+            ("token=3f1b0c1e-8d4a-4c55-9a43-2f4c1f0e7d10", true),
+            ("token=secret", false),
+            ("opponent=bot", true),
+            ("opponent=bot&depth=6&time=5", true),
+            ("opponent=bot&depth=0", false),
+            ("opponent=bot&depth=7", false),
+            ("opponent=bot&time=3600", false),
+            ("opponent=alien", false),
+            ("rated=false", true),
+            ("rated=maybe", false)
+        ] {
+            let req = test::TestRequest::default()
+                .uri(&format!("/ws/{}?{}", Uuid::new_v4(), query))
+                .insert_header((header::HOST, "localhost:7878"))
+                .insert_header((header::CONNECTION, "Upgrade"))
+                .insert_header((header::UPGRADE, "websocket"))
+                .insert_header((header::SEC_WEBSOCKET_VERSION, 13))
+                .insert_header((header::SEC_WEBSOCKET_KEY, "WGit0IWCAKNhwphfG2Zi2Q=="))
+                .to_request();
+
+            let conn = test::call_service(&app, req).await;
+            assert_eq!(conn.headers().get("upgrade").is_some(), upgraded, "{}", query);
+        }
+    }
+
+    #[actix_web::test]
+    async fn ws_seek() {
+        let chess_ws_server = Lobby::default().start();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(chess_ws_server.clone()))
+                .app_data(web::Data::new(Accounts::in_memory()))
+                .service(start_seek)
+        ).await;
+
+        for (query, upgraded) in [("", true), ("tc=5%2B3&protocol=text", true), ("tc=fast", false)] {
+            let req = test::TestRequest::default()
+                .uri(&format!("/seek?{}", query))
+                .insert_header((header::HOST, "localhost:7878"))
+                .insert_header((header::CONNECTION, "Upgrade"))
+                .insert_header((header::UPGRADE, "websocket"))
+                .insert_header((header::SEC_WEBSOCKET_VERSION, 13))
+                .insert_header((header::SEC_WEBSOCKET_KEY, "WGit0IWCAKNhwphfG2Zi2Q=="))
+                .to_request();
+
+            let conn = test::call_service(&app, req).await;
+            assert_eq!(conn.headers().get("upgrade").is_some(), upgraded, "{}", query);
+        }
+    }
+
+    #[actix_web::test]
+    async fn ws_player_session() {
+        let accounts = web::Data::new(Accounts::in_memory());
+        let user = accounts.register("alice", "correct horse", Some("Alice")).unwrap();
+        let session = accounts.open_session(&user).unwrap();
+        let token = Uuid::new_v4();
+
+        let req = test::TestRequest::default()
+            .cookie(actix_web::cookie::Cookie::new(SESSION_COOKIE, session.to_string()))
+            .to_http_request();
+        let (seat, signed_in) = player(&req, Some(token), &accounts).await.unwrap();
+        assert_eq!((seat, signed_in.map(|user| user.display_name)), (user.seat_token, Some("Alice".to_owned())));
+
+        // an unknown session plays as a guest
+        let req = test::TestRequest::default()
+            .cookie(actix_web::cookie::Cookie::new(SESSION_COOKIE, Uuid::new_v4().to_string()))
+            .to_http_request();
+        let (seat, guest) = player(&req, Some(token), &accounts).await.unwrap();
+        assert_eq!((seat, guest.is_none()), (token, true));
+
+        // who can't take the seats of an account
+        let err = player(&req, Some(user.seat_token), &accounts).await.err().unwrap();
+        assert_eq!(err.as_response_error().status_code(), actix_web::http::StatusCode::FORBIDDEN);
+        let req = test::TestRequest::default()
+            .cookie(actix_web::cookie::Cookie::new(PLAYER_TOKEN_COOKIE, user.seat_token.to_string()))
+            .to_http_request();
+        assert!(player(&req, None, &accounts).await.is_err());
+        assert_eq!(player(&req, Some(user.id), &accounts).await.unwrap().0, user.id);
+    }
+}
@@ -1,10 +1,20 @@
 let self_uuid = document.getElementById('game-id').innerText;
+let pageQuery = new URLSearchParams(window.location.search);
+let wsQuery = new URLSearchParams();
+for (const key of ['tc', 'opponent', 'depth', 'rated']) {
+  if (pageQuery.get(key)) wsQuery.set(key, pageQuery.get(key));
+}
 let uri = 'ws://' + window.location.host + '/ws/' + self_uuid;
+if (wsQuery.toString()) uri += '?' + wsQuery.toString();
 let ws = new WebSocket(uri);
 let session_id = "";
 
+const PROTOCOL_VERSION = 1;
+const RESULTS = { white: "1-0", black: "0-1", draw: "1/2-1/2", not_decided: "*" };
+
 let ss = [];
 let color = "";
+let spectator = false;
 
 let chess = {
   board: [],
@@ -15,7 +25,8 @@ let chess = {
   captured_white: [],
   gameOver: false,
   possible_moves: [],
-  yourTurn: false
+  yourTurn: false,
+  clock: null
 };
 
 let gameState = new Proxy(chess, {
@@ -54,96 +65,190 @@ let gameState = new Proxy(chess, {
 
 document.addEventListener('DOMContentLoaded', () => {
   ws.onopen = (_event) => {
-    ws.send("\\get_color");
-    ws.send("\\get_game_stat");
-    ws.send("\\get_board");
-    ws.send("\\get_status");
+    send("get_game_state");
+    send("get_board");
+    send("get_status");
   };
 
+  document.getElementById("offer-draw-button").addEventListener("click", () => {
+    send("offer_draw");
+  });
+
+  document.getElementById("resign-button").addEventListener("click", () => {
+    if (confirm("Are you sure you want to resign?")) send("resign");
+  });
+
   ws.onmessage = (event) => {
-    let msg = event.data;
+    let msg = JSON.parse(event.data);
     // console.log(msg);
 
-    if (msg.startsWith("board:")) {
-      gameState.turn += 1;
-      let devided = (color == "white" || color == "") ? 1 : 0;
-      gameState.yourTurn = ((gameState.turn % 2) == devided) ? true : false;
-      gameState.board = msg.split("\n")
-        .splice(1, 8)
-        .map((row) => row.trim().split(" "));
-
-    } else if (msg.startsWith("promoted")) {
-      gameState.board = msg.split("\n")
-        .splice(1, 8)
-        .map((row) => row.trim().split(" "));
-
-    } else if (msg.startsWith("game stat")) {
-      let gameStatus = msg.split("\n")[0].split(": ")[1];
-      gameState.status = gameStatus;
-      if (gameStatus == "game over") { gameState.gameOver = true }
-
-      let stat = msg.split("\n").splice(1);
-      gameState.turn = parseInt(stat[0].split(": ")[1]);
-      updateStatus();
-
-      let moveHistory = stat.splice(2, gameState.turn-1);
-      if (moveHistory[0] !== "") {
-        gameState.history = moveHistory;
+    switch (msg.type) {
+      case "board":
+        gameState.turn = msg.board.history.length + 1;
+        gameState.yourTurn = msg.board.turn.toLowerCase() == color;
+        gameState.board = msg.board.cells
+          .slice()
+          .reverse()
+          .map((row) => row.map((cell) => (cell) ? cell.icon : ""));
+        updateClock(msg.clock);
+        break;
+
+      case "game_state":
+        gameState.status = msg.status;
+        if (msg.status == "game_over") { gameState.gameOver = true }
+        gameState.turn = msg.history.length + 1;
+        updateSpectators(msg.spectators);
+        updateStatus();
+
+        if (msg.history.length > 0) {
+          gameState.history = msg.history.map((his) => `moves ${his.from} to ${his.to}`);
+          let historyContainer = document.getElementById("chess-move-history");
+          historyContainer.innerHTML = "";
+          gameState.history.forEach((his, idx) => {
+            let pieceMove = document.createElement("div");
+            pieceMove.innerText = `${idx+1}. ${his}`;
+            historyContainer.append(pieceMove);
+          })
+        }
+        break;
+
+      case "status":
+        gameState.status = msg.status;
+        updateStatus();
+        break;
+
+      case "result": {
+        gameState.gameOver = true;
         let historyContainer = document.getElementById("chess-move-history");
-        moveHistory.forEach((his, idx) => {
-          let pieceMove = document.createElement("div");
-          pieceMove.innerText = `${idx+1}. ${his}`;
-          historyContainer.append(pieceMove);
-        })
+        let result = document.createElement("div");
+        let termination = (msg.termination) ? msg.termination.replaceAll("_", " ") : "";
+        result.innerText = `${RESULTS[msg.winner]} ${termination}`;
+        historyContainer.append(result);
+        break;
       }
 
-    } else if (msg.startsWith("status:")) {
-      let status = msg.split(": ")[1];
-      gameState.status = status;
-      updateStatus()
-
-    } else if (msg.startsWith("color:")) {
-        color = msg.split(": ")[1];
-
-    } else if (msg.startsWith("your session_id")) {
-      session_id = msg.split(" ")[3];
-
-    } else if (msg.startsWith("captured_white")) {
-      gameState.captured_white = msg.split(" ").splice(1);
-
-    } else if (msg.startsWith("captured_black")) {
-      gameState.captured_black = msg.split(" ").splice(1);
-
-    } else if (msg.startsWith("history")) {
-      let moveHistory = msg.split(": ").splice(1)[0];
-      gameState.history.push(moveHistory);
-      // Update Chess Move History
-      let historyContainer = document.getElementById("chess-move-history");
-      let pieceMoves = document.createElement("div");
-      pieceMoves.innerText = `${gameState.turn}. ${moveHistory}`;
-      historyContainer.append(pieceMoves);
-      if (msg.includes("Checkmate") || msg.includes("Draw")) {
-        gameState.gameOver = true;
+      case "draw_offered":
+        if (!spectator && msg.color != color) {
+          let accepted = confirm("Your opponent offers a draw. Accept?");
+          send(accepted ? "accept_draw" : "decline_draw");
+        }
+        break;
+
+      case "draw_declined":
+        if (!spectator && msg.color != color) alert("Your draw offer was declined.");
+        break;
+
+      case "color":
+        color = msg.color;
+        break;
+
+      case "session":
+        session_id = msg.id;
+        spectator = msg.spectator;
+        if (spectator) {
+          document.getElementById("game-actions").classList.add("d-none");
+        } else {
+          send("get_color");
+        }
+        updateStatus();
+        break;
+
+      case "spectators":
+        updateSpectators(msg.count);
+        break;
+
+      case "players": {
+        let player = (name, rating) => (name || "Guest") + (rating ? ` ${rating}` : "");
+        document.getElementById("chess-players").innerText =
+          `${player(msg.white, msg.white_rating)} (White) vs ${player(msg.black, msg.black_rating)} (Black)` +
+          (msg.rated ? "" : " · Casual");
+        break;
       }
 
-    } else if (msg.startsWith("possible moves")) {
-      let [pos, posMove] = msg.split("\n").splice(1);
-      let vpm = posMove.split(" ");
-      if (vpm[0] !== "") {
-        ss.push(pos);
-        document.getElementById(pos).classList.add("selected");
+      case "captured":
+        gameState[`captured_${msg.color}`] = msg.pieces.map((piece) => piece.icon);
+        break;
+
+      case "moved": {
+        let moveHistory = (msg.san) ? `${msg.description} (${msg.san})` : msg.description;
+        if (msg.checkmate) moveHistory += " Checkmate";
+        else if (msg.check) moveHistory += " Check";
+        if (msg.draw) moveHistory += " Draw";
+        gameState.history.push(moveHistory);
+        // Update Chess Move History
+        let historyContainer = document.getElementById("chess-move-history");
+        let pieceMoves = document.createElement("div");
+        pieceMoves.innerText = `${gameState.history.length}. ${moveHistory}`;
+        historyContainer.append(pieceMoves);
+        if (msg.checkmate || msg.draw) {
+          gameState.gameOver = true;
+        }
+        break;
       }
-      gameState.possible_moves = vpm;
-
-    } else if (msg.startsWith("Error")) {
-      alert(msg);
-      ss = [ss[0]];
 
+      case "possible_moves":
+        if (msg.moves.length > 0) {
+          ss.push(msg.cell);
+          document.getElementById(msg.cell).classList.add("selected");
+        }
+        gameState.possible_moves = msg.moves;
+        break;
+
+      case "error":
+        alert(`Error: ${msg.message}`);
+        ss = [ss[0]];
+        break;
+
+      default:
+        break;
     }
-
   };
 });
 
+/**
+ * Send a request to the server
+ * @param {string} type request type, e.g. "move"
+ * @param {Object} fields request fields, e.g. { from: "e2", to: "e4" }
+ */
+function send(type, fields = {}) {
+  ws.send(JSON.stringify({ version: PROTOCOL_VERSION, type: type, ...fields }));
+}
+
+/**
+ * Update the clock from the remaining time of a board message
+ * @param {Object} clock remaining time in milliseconds, `null` without a clock
+ */
+function updateClock(clock) {
+  if (!clock) return;
+  gameState.clock = {
+    white: clock.white,
+    black: clock.black,
+    running: clock.running,
+    since: Date.now()
+  };
+  document.getElementById("chess-clock-container").classList.remove("d-none");
+  renderClock();
+}
+
+/**
+ * Render the remaining time of both players, counting down the running clock
+ */
+function renderClock() {
+  let clock = gameState.clock;
+  if (!clock) return;
+  ["white", "black"].forEach(side => {
+    let remaining = clock[side];
+    if (clock.running == side && !gameState.gameOver) {
+      remaining = Math.max(0, remaining - (Date.now() - clock.since));
+    }
+    let seconds = Math.ceil(remaining / 1000);
+    let text = Math.floor(seconds / 60) + ":" + String(seconds % 60).padStart(2, "0");
+    document.getElementById(`chess-clock-${side}`).innerText = text;
+  });
+}
+
+setInterval(renderClock, 200);
+
 /**
  * Convert tuple (row, col) into chess notation
  */
@@ -190,13 +295,8 @@ function renderBoard(data) {
       });
   }
 
-  if (promotable(ss[1])) {
-    promoteLevelSelector(ss[1]);
-    ss = [ss[1]];
-  }
-
-  ws.send("\\get_captured white");
-  ws.send("\\get_captured black");
+  send("get_captured", { color: "white" });
+  send("get_captured", { color: "black" });
 
   if (gameState.gameOver) {
     let gameOverDiv = document.createElement("div");
@@ -205,6 +305,15 @@ function renderBoard(data) {
   }
 }
 
+/**
+ * Show how many spectators watch the game
+ * @param {number} count number of spectators
+ */
+function updateSpectators(count) {
+  let spectatorsContainer = document.getElementById("chess-spectators");
+  spectatorsContainer.innerText = (count > 0) ? `${count} watching` : "";
+}
+
 /**
  * Update status container with 'White Turn', 'Black Turn', or 'Game Over'
  */
@@ -219,6 +328,7 @@ function updateStatus() {
   }
 
   if (gameState.status == "waiting") statusText += " (Waiting Other Player)";
+  if (spectator) statusText += " (Spectating)";
   statusContainer.innerText = statusText;
 }
 
@@ -318,6 +428,7 @@ function createChessElement(app, cell, x, _row, y, cellColor) {
 function squareClickHandler(event, elm, childElm, pos) {
   event.preventDefault();
 
+  if (spectator) return false;
   if (!childElm.className.match(/chess-piece/) && ss.length == 0) return false;
 
   if (childElm.className.match(/chess-piece-white/) && gameState.turn % 2 === 0 && ss.length === 0) {
@@ -337,45 +448,50 @@ function squareClickHandler(event, elm, childElm, pos) {
       .map(pm => document.getElementById(pm).classList.remove("path"));
 
   } else if (ss.length == 0) {
-    ws.send(`\\get_possible_moves ${pos}`);
+    send("get_possible_moves", { cell: pos });
 
   } else if (ss.length == 1) {
-    if (gameState.possible_moves.includes(pos)) {
+    if (gameState.possible_moves.includes(pos) && promotable(ss[0], pos)) {
+      promoteLevelSelector(ss[0], pos);
+
+    } else if (gameState.possible_moves.includes(pos)) {
       ss.push(pos);
-      ws.send(`\\move ${ss[0]} ${pos}`);
+      send("move", { from: ss[0], to: pos });
 
     } else {
       let king = document.getElementById(ss[0]).querySelector('i');
       let rook = document.getElementById(pos).querySelector('i');
       if (king.className.match(/fa-chess-king/) && rook.className.match(/fa-chess-rook/)) {
-        ws.send(`\\castling ${ss[0]} ${pos}`);
+        send("castle", { king: ss[0], rook: pos });
       }
     }
   }
 }
 
 /**
- * Check if the pos is promotable or not
- * @param {string} pos position in the chess board to check
+ * Check if the move from pos to the destination promotes a pawn
+ * @param {string} from position of the moving piece
+ * @param {string} to destination of the move
  * @returns Boolean
  */
-function promotable(pos) {
-  let piece = document.getElementById(pos);
+function promotable(from, to) {
+  let piece = document.getElementById(from);
   if (piece == null) return false;
   let isPawn = piece.querySelector('i').className.match(/fa-chess-pawn/);
   if (!isPawn) return false;
-  if (!pos.includes("1") && !pos.includes("8")) return false;
-  return true;
+  return to.endsWith("1") || to.endsWith("8");
 }
 
 /**
- * Create level selector for pawn promotion
- * @param {string} pos position in the chess board
+ * Create level selector for pawn promotion, the move is sent with the chosen level
+ * @param {string} from position of the pawn
+ * @param {string} to destination of the pawn
  */
-function promoteLevelSelector(pos) {
-  let pawn = document.getElementById(pos);
-  let isPawn = pawn.querySelector('i').className.match(/chess-piece-white/);
-  let color = (isPawn) ? "chess-piece-white" : "chess-piece-black";
+function promoteLevelSelector(from, to) {
+  if (document.querySelector(".promotion-level-selector")) return;
+  let pawn = document.getElementById(from);
+  let isWhite = pawn.querySelector('i').className.match(/chess-piece-white/);
+  let color = (isWhite) ? "chess-piece-white" : "chess-piece-black";
   let options = ["queen", "bishop", "knight", "rook"];
   let optionContainer = document.createElement("div");
 
@@ -389,8 +505,10 @@ function promoteLevelSelector(pos) {
     optionContainer.append(child);
     child.addEventListener('click', (event) => {
       event.preventDefault();
-      ws.send(`\\promote ${pos} ${opt}`);
+      event.stopPropagation();
+      ss.push(to);
+      send("move", { from: from, to: to, promotion: opt[0].toUpperCase() + opt.slice(1) });
     })
   });
-  pawn.append(optionContainer);
+  document.getElementById(to).append(optionContainer);
 }
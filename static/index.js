@@ -6,8 +6,113 @@ function str2uuid(uuid) {
 let self_uuid = document.getElementById("game-id").innerText;
 console.log(self_uuid);
 
+let createBtn = document.getElementById("button-create");
+createBtn.addEventListener('click', (event) => {
+  event.preventDefault();
+  let timeControl = document.getElementById('select-time-control').value;
+  let rated = document.getElementById('select-rated').value;
+  window.location.href = '/game/' + self_uuid + '?tc=' + encodeURIComponent(timeControl) + '&rated=' + rated;
+})
+
+let botBtn = document.getElementById("button-bot");
+botBtn.addEventListener('click', (event) => {
+  event.preventDefault();
+  let timeControl = document.getElementById('select-time-control').value;
+  let depth = document.getElementById('select-bot-depth').value;
+  window.location.href = '/game/' + self_uuid + '?opponent=bot&depth=' + depth + '&tc=' + encodeURIComponent(timeControl);
+})
+
 let joinBtn = document.getElementById("button-join");
 joinBtn.addEventListener('click', (_event) => {
   let targetId = document.getElementById('input-game-id').value;
   window.location.href = '/game/' + targetId;
 })
+
+let seekSocket = null;
+let seekBtn = document.getElementById("button-seek");
+let seekStatus = document.getElementById("seek-status");
+seekBtn.addEventListener('click', (_event) => {
+  if (seekSocket) {
+    seekSocket.close();
+    return;
+  }
+
+  let timeControl = document.getElementById('select-time-control').value;
+  seekSocket = new WebSocket('ws://' + window.location.host + '/seek?tc=' + encodeURIComponent(timeControl));
+  seekSocket.onmessage = (event) => {
+    let response = JSON.parse(event.data);
+    if (response.type === 'seeking') {
+      seekStatus.innerText = 'Searching an opponent for ' + response.time_control + ' (rating ' + response.rating + ')...';
+      seekStatus.classList.remove('d-none');
+      seekBtn.innerText = 'Cancel';
+    } else if (response.type === 'matched') {
+      seekSocket.onclose = null;
+      seekSocket.close();
+      window.location.href = '/game/' + response.room;
+    }
+  };
+  seekSocket.onclose = (_event) => {
+    seekSocket = null;
+    seekStatus.classList.add('d-none');
+    seekBtn.innerText = 'Find Opponent';
+  };
+})
+
+function renderOpenGames(games) {
+  let body = document.getElementById('open-games');
+  body.innerHTML = '';
+  if (games.length === 0) {
+    body.innerHTML = '<tr><td colspan="5" class="text-muted">No open games.</td></tr>';
+    return;
+  }
+
+  for (const game of games) {
+    let row = document.createElement('tr');
+    let cells = [
+      game.id.substring(0, 8),
+      game.time_control + (game.rated ? '' : ' casual'),
+      game.players + '/2' + (game.bot ? ' (computer)' : ''),
+      new Date(game.created_at).toLocaleTimeString()
+    ];
+    for (const text of cells) {
+      let cell = document.createElement('td');
+      cell.innerText = text;
+      row.appendChild(cell);
+    }
+    let link = document.createElement('a');
+    link.href = '/game/' + game.id;
+    link.className = 'btn btn-sm btn-outline-primary';
+    link.innerText = 'Join';
+    let cell = document.createElement('td');
+    cell.appendChild(link);
+    row.appendChild(cell);
+    body.appendChild(row);
+  }
+}
+
+function loadOpenGames() {
+  fetch('/api/games?joinable=true')
+    .then((response) => response.json())
+    .then(renderOpenGames)
+    .catch((err) => console.log(err));
+}
+
+loadOpenGames();
+setInterval(loadOpenGames, 5000);
+
+fetch('/api/me')
+  .then((response) => (response.ok) ? response.json() : null)
+  .then((user) => {
+    if (!user) return;
+    let account = document.getElementById('account');
+    account.innerText = user.display_name + ' ';
+    let logout = document.createElement('a');
+    logout.href = '#';
+    logout.innerText = '(Log out)';
+    logout.addEventListener('click', (event) => {
+      event.preventDefault();
+      fetch('/api/logout', { method: 'POST' }).then(() => window.location.reload());
+    });
+    account.appendChild(logout);
+  })
+  .catch((err) => console.log(err));
@@ -0,0 +1,26 @@
+function submitAccount(form, uri) {
+  form.addEventListener('submit', (event) => {
+    event.preventDefault();
+    let body = Object.fromEntries(new FormData(form));
+    fetch(uri, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(body)
+    })
+      .then((response) => {
+        if (response.ok) {
+          window.location.href = '/';
+          return;
+        }
+        return response.json().then((err) => {
+          let error = document.getElementById('account-error');
+          error.innerText = err.error;
+          error.classList.remove('d-none');
+        });
+      })
+      .catch((err) => console.log(err));
+  });
+}
+
+submitAccount(document.getElementById('login-form'), '/api/login');
+submitAccount(document.getElementById('register-form'), '/api/register');
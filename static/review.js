@@ -0,0 +1,171 @@
+const gameId = document.getElementById('game-id').innerText.trim();
+const RESULTS = { white: "1-0", black: "0-1", draw: "1/2-1/2", not_decided: "*" };
+const ICONS = {
+  Pawn: "fa-chess-pawn",
+  Rook: "fa-chess-rook",
+  Knight: "fa-chess-knight",
+  Bishop: "fa-chess-bishop",
+  Queen: "fa-chess-queen",
+  King: "fa-chess-king"
+};
+
+let review = {
+  game: null,
+  ply: 0,
+  // positions already fetched, by ply
+  positions: {}
+};
+
+document.addEventListener('DOMContentLoaded', () => {
+  fetch('/api/games/' + gameId)
+    .then((response) => response.json())
+    .then((game) => {
+      if (game.error) throw new Error(game.error);
+      review.game = game;
+      // every position carries the SAN of its move, fetch them all for the move list
+      let all = [...Array(game.history.length + 1).keys()];
+      return Promise.all(all.map(fetchPosition));
+    })
+    .then(() => {
+      renderHistory();
+      // start at the last position, like the game page
+      goTo(plies());
+    })
+    .catch((err) => {
+      document.getElementById("review-status").innerText = err.message;
+    });
+
+  document.getElementById("button-first").addEventListener("click", () => goTo(0));
+  document.getElementById("button-prev").addEventListener("click", () => goTo(review.ply - 1));
+  document.getElementById("button-next").addEventListener("click", () => goTo(review.ply + 1));
+  document.getElementById("button-last").addEventListener("click", () => goTo(plies()));
+
+  document.addEventListener("keydown", (event) => {
+    switch (event.key) {
+      case "Home": goTo(0); break;
+      case "ArrowLeft": goTo(review.ply - 1); break;
+      case "ArrowRight": goTo(review.ply + 1); break;
+      case "End": goTo(plies()); break;
+      default: return;
+    }
+    event.preventDefault();
+  });
+});
+
+/**
+ * Number of moves of the reviewed game
+ */
+function plies() {
+  return (review.game) ? review.game.history.length : 0;
+}
+
+/**
+ * Show the board after the first `ply` moves
+ * @param {number} ply
+ */
+function goTo(ply) {
+  if (!review.game || ply < 0 || ply > plies()) return;
+  review.ply = ply;
+
+  let position = review.positions[ply];
+  if (position) {
+    renderPosition(position);
+    return;
+  }
+  fetchPosition(ply)
+    .then((position) => {
+      // a slower reply of an earlier click doesn't override the current ply
+      if (review.ply == ply) renderPosition(position);
+    })
+    .catch((err) => {
+      document.getElementById("review-status").innerText = err.message;
+    });
+}
+
+/**
+ * Fetch the position after the first `ply` moves and keep it
+ * @param {number} ply
+ */
+function fetchPosition(ply) {
+  return fetch(`/api/games/${gameId}/positions/${ply}`)
+    .then((response) => response.json())
+    .then((position) => {
+      if (position.error) throw new Error(position.error);
+      review.positions[ply] = position;
+      return position;
+    });
+}
+
+/**
+ * Number of the move played at `ply`, with `.` for White and `...` for Black
+ *
+ * The starting position of the game decides the first move number and side,
+ * like the movetext of the PGN export.
+ * @param {number} ply
+ */
+function moveNumber(ply) {
+  let fields = (review.game.board.start_fen || "").split(" ");
+  let index = ply - 1 + ((fields[1] == "b") ? 1 : 0);
+  let number = (parseInt(fields[5]) || 1) + Math.floor(index / 2);
+  return `${number}${(index % 2 == 0) ? "." : "..."}`;
+}
+
+/**
+ * Render the board, the status and the current move of the position
+ */
+function renderPosition(position) {
+  let app = document.getElementById("chess-game");
+  app.innerHTML = '';
+  position.board.cells.slice().reverse().forEach((row, y) => {
+    row.forEach((cell, x) => {
+      let elm = document.createElement("div");
+      elm.classList.add("square");
+      elm.style.backgroundColor = ((x+y)%2 == 0) ? "lightgrey" : "darkgrey";
+      if (cell) {
+        let piece = document.createElement("i");
+        piece.classList.add("fas", ICONS[cell.level], `chess-piece-${cell.color.toLowerCase()}`);
+        elm.append(piece);
+      }
+      app.append(elm);
+    });
+  });
+
+  let status = `Move ${position.ply} of ${position.plies}`;
+  if (position.san) {
+    status += ` · ${moveNumber(position.ply)} ${position.san}`;
+  }
+  if (position.ply == position.plies && review.game.status == "game_over") {
+    status += ` · ${RESULTS[review.game.winner]}`;
+  }
+  document.getElementById("review-status").innerText = status;
+  document.getElementById("review-fen").innerText = position.fen;
+
+  document.getElementById("button-first").disabled = position.ply == 0;
+  document.getElementById("button-prev").disabled = position.ply == 0;
+  document.getElementById("button-next").disabled = position.ply == position.plies;
+  document.getElementById("button-last").disabled = position.ply == position.plies;
+
+  document.querySelectorAll("#chess-move-history .move").forEach((elm) => {
+    elm.classList.toggle("fw-bold", Number(elm.dataset.ply) == position.ply);
+  });
+}
+
+/**
+ * Render the moves of the game, each of them jumps to its position
+ */
+function renderHistory() {
+  let historyContainer = document.getElementById("chess-move-history");
+  historyContainer.innerHTML = "";
+  review.game.history.forEach((_, idx) => {
+    let move = document.createElement("a");
+    move.href = "#";
+    move.classList.add("move", "d-block", "text-reset", "text-decoration-none");
+    move.dataset.ply = idx + 1;
+    move.innerText = `${moveNumber(idx + 1)} ${review.positions[idx + 1].san}`;
+    move.addEventListener("click", (event) => {
+      event.preventDefault();
+      goTo(idx + 1);
+    });
+    historyContainer.append(move);
+  });
+}